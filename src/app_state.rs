@@ -1,18 +1,30 @@
 //! The core state machine bridging document sections and the interactive editor.
 //!
 //! A TUI needs a single source of truth that can be interrogated and mutated as the user navigates
-//! and edits. We achieve this by syncing the editor save state with the files on disk. We keep
-//! track of the cumulative total number of lines that have been added to the file during the
-//! session so that we can determine the correct offset to insert content at without re-parsing.
+//! and edits. We achieve this by syncing the editor save state with the files on disk. After each
+//! write the file's sections are refreshed from its kept syntax tree, which is edited to match the
+//! new content so only the changed part is reparsed, and sections are found again by their IDs.
 
+use crate::atomic::{self, WriteOptions};
+use crate::diff_stats::DiffStats;
+use crate::diff_view::DiffView;
 use crate::edit_plan::{Edit, EditPlan};
+use crate::formats::conflict::{self, Resolution};
+use crate::formats::difftastic::{self, Hunk};
 use crate::formats::markdown::MarkdownFormat;
-use crate::input;
+use crate::formats::Format;
+use crate::git;
+use crate::index::{self, ParseIndex};
+use crate::input::{self, SyntaxTree};
+use crate::keymap::{Action, Keymap};
+use crate::patch::{file_patch, PatchHunk};
+use crate::reflow;
 use crate::section::ChunkType;
-use crate::section::{Section, TreeNode};
-use edtui::{EditorState, Lines};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::section::{self, NodeType, Section, SectionId, TreeNode};
+use edtui::{EditorState, Index2, Lines};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{fs, io};
 
 /// Determines navigation scope and quit behavior based on project size.
@@ -35,6 +47,26 @@ pub enum MoveState {
     Moved,
 }
 
+/// Where the original (LHS) side of compared files is read from.
+#[derive(Clone, Debug)]
+pub enum OriginalSource {
+    /// One file for a single comparison, or a directory mirroring the compared paths.
+    Path(PathBuf),
+    /// Staged versions in the index of the git repository at this root.
+    GitIndex(PathBuf),
+}
+
+/// Review outcome for a diff hunk, written into the modified (RHS) file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HunkDecision {
+    /// Keep the modified lines.
+    Accepted,
+    /// Restore the original lines.
+    Rejected,
+    /// Replace the hunk with lines edited in the editor.
+    Edited,
+}
+
 /// Bridges document sections and the interactive editor, maintaining session state.
 pub struct AppState {
     /// All parsed sections across loaded files.
@@ -55,14 +87,47 @@ pub struct AppState {
     pub command_buffer: String,
     /// Status feedback displayed in the help bar.
     pub message: Option<String>,
-    /// Maximum line width for text wrapping in the editor.
+    /// Width prose is hard-wrapped at when reflowed.
     pub wrap_width: usize,
-    /// Tracks line count changes per section to calculate write positions without re-parsing.
-    pub file_offsets: HashMap<String, HashMap<i64, usize>>,
+    /// Syntax trees of files parsed since launch, reused to reparse them incrementally.
+    pub trees: HashMap<String, SyntaxTree>,
     /// Tracks section being moved for visual feedback
     pub move_state: MoveState,
     /// Index of section being moved (if any)
     pub moving_section_index: Option<usize>,
+    /// Start byte and level of each file's sections when the move began, to tell which files
+    /// it reordered.
+    pub move_origin: HashMap<String, Vec<(usize, usize)>>,
+    /// Format resolved for each file path; files absent from the map are treated as markdown.
+    pub formats: HashMap<String, Arc<dyn Format>>,
+    /// Aligned rows of the hunk open in the diff view.
+    pub diff_view: Option<DiffView>,
+    /// Where the original side of compared files is read from.
+    pub lhs_source: Option<OriginalSource>,
+    /// File the staged patch is written to in git mode (stdout on exit when absent).
+    pub patch_out: Option<PathBuf>,
+    /// Decisions on diff hunks by section ID; rejected and edited hunks stage their
+    /// replacement lines in `section_content`.
+    pub hunk_decisions: HashMap<SectionId, HunkDecision>,
+    /// Resolutions staged for merge conflicts by section ID, with the resolved lines in
+    /// `section_content`.
+    pub conflict_resolutions: HashMap<SectionId, Resolution>,
+    /// Editor text as loaded or last written, to tell whether the buffer has unsaved changes.
+    pub editor_baseline: Option<String>,
+    /// Whether saves keep backups of the files they replace.
+    pub write_options: WriteOptions,
+    /// Keys bound to section list actions.
+    pub keymap: Keymap,
+    /// Whether saving from the editor reflows the section's prose first.
+    pub reflow_on_save: bool,
+    /// Orders diffed files by lines changed, most first, instead of by path.
+    pub sort_by_churn: bool,
+    /// Paths of folders whose contents are hidden in the tree.
+    pub collapsed: HashSet<String>,
+    /// Documents left unparsed until their folder is first unfolded.
+    pub unparsed: HashSet<String>,
+    /// Index that documents parsed later are looked up in and added to.
+    pub parse_index: Option<ParseIndex>,
 }
 
 /// Determines which UI screen renders and how input is interpreted.
@@ -74,19 +139,32 @@ pub enum View {
     Detail,
     /// Captures vim-style command input after ':' keystroke.
     Command,
+    /// Shows a diff hunk with original and modified lines side by side.
+    Diff,
 }
 
 impl AppState {
     /// Initialises application state with parsed sections and determines file mode.
     #[must_use]
-    pub fn new(files: Vec<PathBuf>, sections: Vec<Section>, wrap_width: usize) -> Self {
+    pub fn new(files: Vec<PathBuf>, mut sections: Vec<Section>, wrap_width: usize) -> Self {
         let file_mode = if files.len() == 1 {
             FileMode::Single
         } else {
             FileMode::Multi
         };
 
-        let tree_nodes = Self::build_tree(&files, &sections);
+        // Difftastic reports created files without chunks, so their length comes from the file
+        for section in &mut sections {
+            if section.hunk.as_deref().and_then(Hunk::whole_file) == Some(ChunkType::Added) {
+                if let Ok(text) = fs::read_to_string(&section.file_path) {
+                    difftastic::size_whole_file(section, &text);
+                }
+            }
+        }
+
+        section::assign_ids(&mut sections);
+        let formats = HashMap::new();
+        let tree_nodes = Self::build_tree(&files, &sections, &formats, false, &HashSet::new());
 
         // Find first navigable node
         let initial_index = tree_nodes.iter().position(|n| n.navigable).unwrap_or(0);
@@ -102,24 +180,158 @@ impl AppState {
             command_buffer: String::new(),
             message: None,
             wrap_width,
-            file_offsets: HashMap::new(),
+            trees: HashMap::new(),
             move_state: MoveState::None,
             moving_section_index: None,
+            move_origin: HashMap::new(),
+            diff_view: None,
+            lhs_source: None,
+            patch_out: None,
+            hunk_decisions: HashMap::new(),
+            conflict_resolutions: HashMap::new(),
+            editor_baseline: None,
+            write_options: WriteOptions::default(),
+            keymap: Keymap::default(),
+            reflow_on_save: false,
+            sort_by_churn: false,
+            collapsed: HashSet::new(),
+            unparsed: HashSet::new(),
+            parse_index: None,
+            formats,
         }
     }
 
-    fn build_tree(files: &[PathBuf], sections: &[Section]) -> Vec<TreeNode> {
-        let mut nodes = Vec::new();
+    /// Assigns the format resolved for each file, replacing the markdown default.
+    ///
+    /// The tree layout depends on the formats (diff hunks group under file nodes), so the tree is
+    /// rebuilt and the cursor returned to the first navigable node.
+    #[must_use]
+    pub fn with_formats(mut self, formats: HashMap<String, Arc<dyn Format>>) -> Self {
+        self.formats = formats;
+        self.tree_nodes = Self::build_tree(
+            &self.files,
+            &self.sections,
+            &self.formats,
+            self.sort_by_churn,
+            &self.collapsed,
+        );
+        self.current_node_index = self.navigate_to_first().unwrap_or(0);
+        self
+    }
 
-        // Determine if this is difftastic mode by checking if multiple sections share the same file_path
-        // (in markdown mode, each section has a unique file path or sections are from the same file)
-        let mut file_section_counts: HashMap<String, usize> = HashMap::new();
-        for section in sections {
-            *file_section_counts
-                .entry(section.file_path.clone())
-                .or_insert(0) += 1;
+    /// Reads original file text from `source`, sizing deleted files' hunks from it.
+    #[must_use]
+    pub fn with_lhs_source(mut self, source: Option<OriginalSource>) -> Self {
+        self.lhs_source = source;
+        for idx in 0..self.sections.len() {
+            let section = &self.sections[idx];
+            if section.hunk.as_deref().and_then(Hunk::whole_file) != Some(ChunkType::Deleted) {
+                continue;
+            }
+            if let Some(text) = self.read_lhs(&section.file_path) {
+                difftastic::size_whole_file(&mut self.sections[idx], &text);
+            }
+        }
+        self.rebuild_tree();
+        self
+    }
+
+    /// Leaves documents unparsed until their folder is unfolded, starting with those folders folded.
+    ///
+    /// `files` must already list the documents so that the tree shows where they are.
+    #[must_use]
+    pub fn with_unparsed(mut self, unparsed: HashSet<String>, index: Option<ParseIndex>) -> Self {
+        for file in &unparsed {
+            if let Some(folder) = Path::new(file).parent() {
+                self.collapsed.insert(folder.to_string_lossy().to_string());
+            }
         }
-        let is_difftastic = file_section_counts.values().any(|&count| count > 1);
+        self.unparsed = unparsed;
+        self.parse_index = index;
+        self.rebuild_tree();
+        self.current_node_index = self.navigate_to_first().unwrap_or(0);
+        self
+    }
+
+    /// Parse the documents directly inside a folder that were left for later.
+    fn parse_folder(&mut self, folder: &str) {
+        let mut documents: Vec<(PathBuf, Arc<dyn Format>)> = self
+            .unparsed
+            .iter()
+            .filter(|file| {
+                Path::new(file)
+                    .parent()
+                    .is_some_and(|parent| parent.to_string_lossy() == folder)
+            })
+            .map(|file| (PathBuf::from(file), self.format_for(file)))
+            .collect();
+        if documents.is_empty() {
+            return;
+        }
+        documents.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let results = index::parse_documents(&documents, self.parse_index.as_mut(), &|_, _| {});
+        let mut skipped = Vec::new();
+        for ((path, _), result) in documents.iter().zip(results) {
+            let file = path.to_string_lossy().to_string();
+            match result {
+                Ok(parsed) => {
+                    self.sections.extend(parsed.sections);
+                    if let Some(tree) = parsed.tree {
+                        self.trees.insert(file.clone(), tree);
+                    }
+                }
+                Err(e) => skipped.push(format!("{file}: {e}")),
+            }
+            self.unparsed.remove(&file);
+        }
+        if let Some(index) = self.parse_index.as_mut() {
+            if let Err(e) = index.save() {
+                skipped.push(format!("parse index: {e}"));
+            }
+        }
+        if !skipped.is_empty() {
+            self.message = Some(format!("Could not read {}", skipped.join("; ")));
+        }
+    }
+
+    /// Returns the format that parses and displays the given file.
+    #[must_use]
+    pub fn format_for(&self, file_path: &str) -> Arc<dyn Format> {
+        self.formats
+            .get(file_path)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(MarkdownFormat))
+    }
+
+    /// Whether a diffed file was created, deleted or changed, from the kinds of its hunks.
+    ///
+    /// A file is created or deleted when its only hunk adds or removes everything from the top.
+    fn file_status(hunks: &[(usize, &Section)]) -> Option<&'static str> {
+        let whole_file =
+            |section: &Section, kind| section.chunk_type() == Some(kind) && section.line_start == 0;
+        match hunks {
+            [(_, section)] if whole_file(section, ChunkType::Added) => Some("created"),
+            [(_, section)] if whole_file(section, ChunkType::Deleted) => Some("deleted"),
+            _ if hunks.iter().any(|(_, s)| s.hunk.is_some()) => Some("changed"),
+            _ => None,
+        }
+    }
+
+    fn build_tree(
+        files: &[PathBuf],
+        sections: &[Section],
+        formats: &HashMap<String, Arc<dyn Format>>,
+        sort_by_churn: bool,
+        collapsed: &HashSet<String>,
+    ) -> Vec<TreeNode> {
+        let mut nodes = Vec::new();
+
+        let is_difftastic = sections.iter().any(|s| {
+            formats
+                .get(&s.file_path)
+                .is_some_and(|format| format.is_diff())
+        });
 
         if files.len() == 1 && !is_difftastic {
             // Single markdown file mode: use section level for tree indentation
@@ -129,33 +341,26 @@ impl AppState {
         } else if is_difftastic {
             // Difftastic mode: group sections by file, show files as non-navigable nodes
             let mut file_tree: HashMap<String, Vec<(usize, &Section)>> = HashMap::new();
-            let mut file_status: HashMap<String, Option<String>> = HashMap::new();
 
-            // Group sections by file and infer file statuses
+            // Group sections by file
             for (idx, section) in sections.iter().enumerate() {
                 file_tree
                     .entry(section.file_path.clone())
                     .or_default()
                     .push((idx, section));
-
-                // Determine file status from hunk headers
-                if !file_status.contains_key(&section.file_path) {
-                    let status = if section.title.contains("@@ -0,0") {
-                        Some("created".to_string())
-                    } else if section.title.contains("+0,0 @@") {
-                        Some("deleted".to_string())
-                    } else if section.title.contains("@@ ") {
-                        Some("changed".to_string())
-                    } else {
-                        None
-                    };
-                    file_status.insert(section.file_path.clone(), status);
-                }
             }
 
             // Build tree with file nodes and hunk sections
+            let stats: HashMap<&String, DiffStats> = file_tree
+                .iter()
+                .map(|(path, hunks)| (path, DiffStats::of_sections(hunks.iter().map(|h| h.1))))
+                .collect();
             let mut sorted_files: Vec<_> = file_tree.keys().collect();
             sorted_files.sort();
+            if sort_by_churn {
+                // Stable, so files with equal churn stay in path order
+                sorted_files.sort_by_key(|path| std::cmp::Reverse(stats[path].churn()));
+            }
 
             for file_path in sorted_files {
                 // Add file node (non-navigable)
@@ -163,11 +368,18 @@ impl AppState {
                     .file_name()
                     .map_or_else(|| file_path.clone(), |n| n.to_string_lossy().to_string());
 
-                let status_opt = file_status.get(file_path).and_then(|opt| opt.as_deref()); // flatten Option<Option<&str>>
-
-                let label = match status_opt {
-                    Some(status) => format!("{file_name} ({status})"),
-                    None => file_name.clone(),
+                let hunks = &file_tree[file_path];
+                let language = hunks
+                    .iter()
+                    .find_map(|(_, s)| s.hunk.as_ref()?.language.as_deref());
+                let details: Vec<&str> = Self::file_status(hunks)
+                    .into_iter()
+                    .chain(language)
+                    .collect();
+                let label = if details.is_empty() {
+                    format!("{file_name} {}", stats[file_path])
+                } else {
+                    format!("{file_name} ({}) {}", details.join(", "), stats[file_path])
                 };
 
                 nodes.push(TreeNode::file(label, file_path.clone(), 0));
@@ -180,49 +392,234 @@ impl AppState {
                 }
             }
         } else {
-            // Multi-file markdown mode: build directory tree with sections nested under files
-            let mut file_tree: HashMap<String, Vec<(usize, &Section)>> = HashMap::new();
+            nodes = Self::build_folder_tree(files, sections, collapsed);
+        }
 
-            // Group sections by file
-            for (idx, section) in sections.iter().enumerate() {
-                file_tree
-                    .entry(section.file_path.clone())
-                    .or_default()
-                    .push((idx, section));
-            }
+        nodes
+    }
 
-            // Build tree with directory structure
-            let mut sorted_files: Vec<_> = files.iter().collect();
-            sorted_files.sort();
+    /// Tree of multi-file markdown mode: files nested under their folders, sections under files.
+    fn build_folder_tree(
+        files: &[PathBuf],
+        sections: &[Section],
+        collapsed: &HashSet<String>,
+    ) -> Vec<TreeNode> {
+        let mut nodes = Vec::new();
+        let mut file_tree: HashMap<String, Vec<(usize, &Section)>> = HashMap::new();
 
-            for file_path in sorted_files {
-                let path_str = file_path.to_string_lossy().to_string();
+        // Group sections by file
+        for (idx, section) in sections.iter().enumerate() {
+            file_tree
+                .entry(section.file_path.clone())
+                .or_default()
+                .push((idx, section));
+        }
 
-                // Add file node (non-navigable)
-                let file_name = file_path
-                    .file_name()
-                    .map_or_else(|| path_str.clone(), |n| n.to_string_lossy().to_string());
+        let mut sorted_files: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+        sorted_files.sort();
+        sorted_files.dedup();
 
-                nodes.push(TreeNode::file(file_name.clone(), path_str.clone(), 0));
+        // Folders shared by every file are left out, so the tree starts where paths diverge
+        let root = input::common_directory(&sorted_files);
+        let key = |dir: &Path| dir.to_string_lossy().to_string();
+        // Folders enclosing the current file, outermost first
+        let mut open: Vec<PathBuf> = Vec::new();
 
-                // Add sections under this file
-                if let Some(file_sections) = file_tree.get(&path_str) {
-                    for (idx, section) in file_sections {
-                        nodes.push(TreeNode::section((*section).clone(), section.level, *idx));
+        for file_path in &sorted_files {
+            let dir = file_path.parent().unwrap_or(Path::new(""));
+            while open.last().is_some_and(|last| !dir.starts_with(last)) {
+                open.pop();
+            }
+
+            let mut current = open.last().cloned().unwrap_or_else(|| root.clone());
+            let entered: Vec<std::path::Component> = dir
+                .strip_prefix(&current)
+                .map(|rest| rest.components().collect())
+                .unwrap_or_default();
+            for component in entered {
+                current.push(component);
+                let folder = current.clone();
+                if !open.iter().any(|outer| collapsed.contains(&key(outer))) {
+                    let mut name = folder
+                        .file_name()
+                        .map_or_else(|| key(&folder), |n| n.to_string_lossy().to_string());
+                    if collapsed.contains(&key(&folder)) {
+                        let count = sorted_files
+                            .iter()
+                            .filter(|f| f.starts_with(&folder))
+                            .count();
+                        let plural = if count == 1 { "" } else { "s" };
+                        name = format!("{name} ({count} file{plural})");
                     }
+                    nodes.push(TreeNode::directory(name, key(&folder), open.len()));
                 }
+                open.push(folder);
+            }
+            if open.iter().any(|folder| collapsed.contains(&key(folder))) {
+                continue;
             }
-        }
 
+            // Add file node (non-navigable)
+            let path_str = file_path.to_string_lossy().to_string();
+            let file_name = file_path
+                .file_name()
+                .map_or_else(|| path_str.clone(), |n| n.to_string_lossy().to_string());
+            let depth = open.len();
+            nodes.push(TreeNode::file(file_name, path_str.clone(), depth));
+
+            // Add sections under this file
+            if let Some(file_sections) = file_tree.get(&path_str) {
+                for (idx, section) in file_sections {
+                    nodes.push(TreeNode::section(
+                        (*section).clone(),
+                        depth + section.level,
+                        *idx,
+                    ));
+                }
+            }
+        }
         nodes
     }
 
+    /// Summary of the whole diff for the tree header, or `None` outside diff mode.
+    #[must_use]
+    pub fn diff_summary(&self) -> Option<String> {
+        let hunks: Vec<&Section> = self.sections.iter().filter(|s| s.hunk.is_some()).collect();
+        if hunks.is_empty() {
+            return None;
+        }
+        let files: HashSet<&str> = hunks.iter().map(|s| s.file_path.as_str()).collect();
+        let plural = if files.len() == 1 { "" } else { "s" };
+        Some(format!(
+            "{} file{plural} changed, {} hunks, {}",
+            files.len(),
+            hunks.len(),
+            DiffStats::of_sections(hunks)
+        ))
+    }
+
+    /// Switch diffed files between path order and most-changed first.
+    pub fn toggle_churn_sort(&mut self) {
+        self.sort_by_churn = !self.sort_by_churn;
+        self.rebuild_tree();
+        self.message = Some(
+            if self.sort_by_churn {
+                "Files sorted by lines changed"
+            } else {
+                "Files sorted by path"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Re-extract the sections of a file changed on disk, keeping the cursor on the same section.
+    ///
+    /// Returns whether the sections moved, which they do not after asterism's own saves. Staged
+    /// content for the file is dropped, and the status message warns when that or an open
+    /// editor's content was based on the old version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be parsed.
+    pub fn reload_file(&mut self, file_path: &str) -> io::Result<bool> {
+        let format = self.format_for(file_path);
+        if format.is_diff() {
+            return Ok(false);
+        }
+        let path = Path::new(file_path);
+        let fresh = if path.exists() {
+            self.reparse_file(file_path)?
+        } else {
+            self.trees.remove(file_path);
+            Vec::new()
+        };
+
+        let held: Vec<&Section> = self
+            .sections
+            .iter()
+            .filter(|s| s.file_path == file_path)
+            .collect();
+        let unmoved = held.len() == fresh.len()
+            && held.iter().zip(&fresh).all(|(old, new)| {
+                (
+                    &old.title,
+                    old.level,
+                    old.line_start,
+                    old.line_end,
+                    old.byte_start,
+                    old.byte_end,
+                ) == (
+                    &new.title,
+                    new.level,
+                    new.line_start,
+                    new.line_end,
+                    new.byte_start,
+                    new.byte_end,
+                )
+            });
+        if unmoved {
+            return Ok(false);
+        }
+        let staged = held.iter().any(|s| s.section_content.is_some());
+
+        let cursor = self.current_section_id();
+        let editing = self.editor_state.is_some()
+            && self
+                .get_current_section()
+                .is_some_and(|s| s.file_path == file_path);
+
+        self.hunk_decisions
+            .retain(|id, _| id.file_path != file_path);
+        self.conflict_resolutions
+            .retain(|id, _| id.file_path != file_path);
+
+        self.sections.retain(|s| s.file_path != file_path);
+        self.sections.extend(fresh);
+        self.rebuild_tree();
+
+        let found = cursor.and_then(|id| self.section_node(&id));
+        match found {
+            Some(node) => self.current_node_index = node,
+            None if self.current_node_index >= self.tree_nodes.len() => {
+                self.current_node_index = self.navigate_to_first().unwrap_or(0);
+            }
+            None => {}
+        }
+
+        self.message = Some(match (editing, found.is_some(), staged) {
+            (true, true, _) => format!(
+                "{file_path} changed on disk; :w writes the editor's unsaved content over it"
+            ),
+            (true, false, _) => {
+                format!("{file_path} changed on disk and the edited section is gone; :q! discards")
+            }
+            (false, _, true) => format!("{file_path} changed on disk; staged changes discarded"),
+            (false, _, false) => format!("Reloaded {file_path}"),
+        });
+        Ok(true)
+    }
+
     /// Rebuild tree after sections change (e.g., after save)
     pub fn rebuild_tree(&mut self) {
-        self.tree_nodes = Self::build_tree(&self.files, &self.sections);
+        let directory = self.current_directory().map(str::to_string);
+        let current = self.current_section_id();
+        section::assign_ids(&mut self.sections);
+        self.tree_nodes = Self::build_tree(
+            &self.files,
+            &self.sections,
+            &self.formats,
+            self.sort_by_churn,
+            &self.collapsed,
+        );
+        if let Some(node_idx) = directory.and_then(|path| self.directory_node(&path)) {
+            self.current_node_index = node_idx;
+            return;
+        }
 
-        // Try to maintain current position by finding same section
-        if let Some(current_section_idx) = self.get_current_section_index() {
+        // Keep the cursor on the same section, wherever re-parsing has moved it
+        if let Some(node_idx) = current.and_then(|id| self.section_node(&id)) {
+            self.current_node_index = node_idx;
+        } else if let Some(current_section_idx) = self.get_current_section_index() {
             if let Some(node_idx) = self
                 .tree_nodes
                 .iter()
@@ -233,6 +630,27 @@ impl AppState {
         }
     }
 
+    /// ID of the section under the cursor, as shown in the tree even if the sections have since
+    /// been re-parsed.
+    fn current_section_id(&self) -> Option<SectionId> {
+        match &self.tree_nodes.get(self.current_node_index)?.node_type {
+            NodeType::Section(section) => Some(section.id.clone()),
+            _ => None,
+        }
+    }
+
+    /// Index of the section with the given ID.
+    fn section_index(&self, id: &SectionId) -> Option<usize> {
+        self.sections.iter().position(|s| s.id == *id)
+    }
+
+    /// Tree row of the section with the given ID.
+    fn section_node(&self, id: &SectionId) -> Option<usize> {
+        self.tree_nodes
+            .iter()
+            .position(|node| matches!(&node.node_type, NodeType::Section(s) if s.id == *id))
+    }
+
     /// Get the section index for the currently selected node (if it's a section)
     #[must_use]
     pub fn get_current_section_index(&self) -> Option<usize> {
@@ -250,70 +668,44 @@ impl AppState {
             .and_then(|idx| self.sections.get(idx))
     }
 
-    fn rebuild_file_offsets(&mut self) {
-        self.file_offsets.clear();
-
-        if let Some(section_idx) = self.get_current_section_index() {
-            if let Some(section) = self.sections.get(section_idx) {
-                let lines_added = self.editor_state.as_ref().map_or(0, |es| es.lines.len());
-
-                let file_map = self
-                    .file_offsets
-                    .entry(section.file_path.clone())
-                    .or_default();
-
-                file_map.insert(section.line_start, lines_added);
-            }
-        }
-    }
-
-    /// Calculates total lines added before a section to determine correct write position.
-    #[must_use]
-    pub fn cumulative_offset(&self, index: usize) -> usize {
-        let section = &self.sections[index];
-        let target_file = &section.file_path;
-        let target_line = section.line_start;
-
-        if let Some(file_map) = self.file_offsets.get(target_file) {
-            file_map
-                .iter()
-                .filter(|(line, _)| **line < target_line)
-                .map(|(_, offset)| offset)
-                .sum()
-        } else {
-            0
+    /// Parse a file's sections from disk again after it has been written.
+    ///
+    /// Files parsed with tree-sitter keep their syntax tree, which is edited to match the new
+    /// content so only the changed part of the file is reparsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn reparse_file(&mut self, file_path: &str) -> io::Result<Vec<Section>> {
+        let format = self.format_for(file_path);
+        let path = Path::new(file_path);
+        if !format.parses_tree() {
+            return format.extract_sections(path);
         }
+        let content = fs::read_to_string(path)?;
+        let previous = self.trees.remove(file_path);
+        let (sections, tree) = input::reparse_sections(&content, path, format.as_ref(), previous)?;
+        self.trees.insert(file_path.to_string(), tree);
+        Ok(sections)
     }
 
     /// Restores previously edited content from a saved edit plan.
+    ///
+    /// Edits find their section by its ID, so a plan still applies after the file has gained or
+    /// lost lines above the section. Plans written without IDs fall back to the recorded position.
     pub fn load_docs(&mut self, plan: EditPlan) {
-        let mut doc_map: HashMap<String, Vec<String>> = HashMap::new();
         for edit in plan.edits {
-            let key = format!(
-                "{}:{}:{}",
-                edit.file_name, edit.line_start, edit.column_start
-            );
-            let lines: Vec<String> = edit
-                .section_content
-                .lines()
-                .map(std::string::ToString::to_string)
-                .collect();
-            doc_map.insert(key, lines);
-        }
-
-        // Match edits to sections and pre-populate editor content
-        for section in &mut self.sections {
-            let key = format!(
-                "{}:{}:{}",
-                section.file_path, section.line_start, section.column_start
-            );
-            if doc_map.contains_key(&key) {
-                if let Ok(content) = fs::read_to_string(&section.file_path) {
-                    let bytes = content.as_bytes();
-                    if section.byte_start < bytes.len() && section.byte_end <= bytes.len() {
-                        // Section exists and can be loaded
-                    }
+            let target = self.sections.iter_mut().find(|s| match &edit.section_id {
+                Some(id) => s.id == *id,
+                None => {
+                    s.file_path == edit.file_name
+                        && s.line_start == edit.line_start
+                        && s.column_start == edit.column_start
                 }
+            });
+            if let Some(section) = target {
+                let lines = edit.section_content.lines().map(String::from).collect();
+                section.section_content = Some(lines);
             }
         }
     }
@@ -321,25 +713,162 @@ impl AppState {
     /// Creates a serialisable plan capturing current editor modifications.
     #[must_use]
     pub fn generate_edit_plan(&self) -> EditPlan {
-        let mut edits = Vec::new();
+        EditPlan {
+            edits: self
+                .sections
+                .iter()
+                .filter_map(|s| self.edit_for(s))
+                .collect(),
+        }
+    }
 
-        for section in &self.sections {
-            if let Some(ref doc_lines) = section.section_content {
-                let section_content = doc_lines.join("\n");
+    /// Edit replacing a section with its staged content, if it has any.
+    fn edit_for(&self, section: &Section) -> Option<Edit> {
+        let doc_lines = section.section_content.as_ref()?;
+        Some(Edit {
+            file_name: section.file_path.clone(),
+            line_start: section.line_start,
+            line_end: section.line_end,
+            column_start: section.column_start,
+            column_end: section.column_end,
+            section_content: doc_lines.join("\n"),
+            item_name: section.title.clone(),
+            verbatim: section.hunk.is_some()
+                || self.format_for(&section.file_path).verbatim_edits(),
+            section_id: Some(section.id.clone()),
+        })
+    }
 
-                edits.push(Edit {
-                    file_name: section.file_path.clone(),
-                    line_start: section.line_start,
-                    line_end: section.line_end,
-                    column_start: section.column_start,
-                    column_end: section.column_end,
-                    section_content,
-                    item_name: section.title.clone(),
-                });
+    /// Carry out a section list action, returning whether asterism should quit.
+    #[allow(clippy::too_many_lines)]
+    pub fn perform(&mut self, action: Action) -> bool {
+        let moving = self.move_state != MoveState::None;
+        let target = match action {
+            Action::Quit => return !self.guard_quit(),
+            Action::Up => self.find_prev_row(),
+            Action::Down => self.find_next_row(),
+            // On a folder, left folds it and right unfolds it before moving anywhere
+            Action::Parent => match self.current_directory() {
+                Some(path) if !self.collapsed.contains(path) => {
+                    self.toggle_directory();
+                    None
+                }
+                Some(_) => self.navigate_to_enclosing_directory(),
+                None => self
+                    .navigate_to_parent()
+                    .or_else(|| self.navigate_to_enclosing_directory()),
+            },
+            Action::Child => match self.current_directory() {
+                Some(path) if self.collapsed.contains(path) => {
+                    self.toggle_directory();
+                    None
+                }
+                Some(_) => self.find_next_row(),
+                None => self.navigate_to_next_descendant(),
+            },
+            Action::PrevSibling => self.navigate_to_prev_sibling(),
+            Action::NextSibling => self.navigate_to_next_sibling(),
+            Action::First => self.navigate_to_first(),
+            Action::Last => self.navigate_to_last(),
+            Action::FirstAtLevel => self.navigate_to_first_at_level(),
+            Action::LastAtLevel => self.navigate_to_last_at_level(),
+            Action::MoveUp | Action::MoveDown | Action::MoveIn | Action::MoveOut if !moving => {
+                self.start_move();
+                None
+            }
+            Action::MoveUp => {
+                self.move_section_up();
+                None
+            }
+            Action::MoveDown => {
+                self.move_section_down();
+                None
+            }
+            Action::MoveIn => {
+                self.move_section_in();
+                None
+            }
+            Action::MoveOut => {
+                self.move_section_out();
+                None
+            }
+            Action::MoveToTop | Action::MoveToBottom | Action::Cancel if !moving => None,
+            Action::MoveToTop => {
+                self.move_section_to_top();
+                None
+            }
+            Action::MoveToBottom => {
+                self.move_section_to_bottom();
+                None
+            }
+            Action::Cancel => {
+                self.cancel_move();
+                None
+            }
+            Action::Open if self.current_directory().is_some() => {
+                self.toggle_directory();
+                None
             }
+            Action::Open => {
+                // Only navigable nodes open, and not while a section is being moved
+                if !moving
+                    && self
+                        .tree_nodes
+                        .get(self.current_node_index)
+                        .is_some_and(|n| n.navigable)
+                {
+                    self.enter_detail_view();
+                }
+                None
+            }
+            Action::Command => {
+                self.current_view = View::Command;
+                self.command_buffer.clear();
+                self.message = None;
+                None
+            }
+            Action::Accept => {
+                self.decide_hunk(HunkDecision::Accepted);
+                None
+            }
+            Action::Reject => {
+                self.decide_hunk(HunkDecision::Rejected);
+                None
+            }
+            Action::Edit => {
+                self.edit_hunk();
+                None
+            }
+            Action::Undo => {
+                self.undo_hunk_decision();
+                self.undo_conflict_resolution();
+                None
+            }
+            Action::SortByChurn => {
+                self.toggle_churn_sort();
+                None
+            }
+            Action::KeepOurs => {
+                self.resolve_conflict(Resolution::Ours);
+                None
+            }
+            Action::KeepTheirs => {
+                self.resolve_conflict(Resolution::Theirs);
+                None
+            }
+            Action::KeepBoth => {
+                self.resolve_conflict(Resolution::Both);
+                None
+            }
+            Action::KeepBase => {
+                self.resolve_conflict(Resolution::Base);
+                None
+            }
+        };
+        if let Some(node) = target {
+            self.current_node_index = node;
         }
-
-        EditPlan { edits }
+        false
     }
 
     /// Loads selected section content into the editor buffer.
@@ -350,41 +879,22 @@ impl AppState {
 
         let section = &self.sections[section_idx];
 
-        // Handle difftastic chunks differently
-        if let Some(chunk_type) = &section.chunk_type {
-            let content = match chunk_type {
-                ChunkType::Added => {
-                    // Show only RHS for added chunks
-                    section.rhs_content.clone().unwrap_or_default()
-                }
-                ChunkType::Deleted => {
-                    // Show LHS with strikethrough or special formatting
-                    section.lhs_content.clone().unwrap_or_default()
-                }
-                ChunkType::Modified => {
-                    // Show a unified or side-by-side view
-                    let lhs = section.lhs_content.as_deref().unwrap_or("");
-                    let rhs = section.rhs_content.as_deref().unwrap_or("");
-                    format!("- {lhs}\n+ {rhs}")
-                }
-                ChunkType::Unchanged => {
-                    // Show either side (they're the same)
-                    section
-                        .lhs_content
-                        .clone()
-                        .or_else(|| section.rhs_content.clone())
-                        .unwrap_or_default()
-                }
-            };
+        if let Some(hunk) = &section.hunk {
+            let lhs = self.read_lhs(&section.file_path);
+            let rhs = fs::read_to_string(&section.file_path).ok();
+            self.diff_view = Some(DiffView::new(hunk, lhs.as_deref(), rhs.as_deref()));
+            self.current_view = View::Diff;
+            return;
+        }
 
-            let lines = Lines::from(content.as_str());
-            self.editor_state = Some(EditorState::new(lines));
+        // Edits kept in memory reopen as they were left
+        if let Some(lines) = &section.section_content {
+            let text = lines.join("\n");
+            self.open_editor(&text);
         } else if let Ok(content) = fs::read_to_string(&section.file_path) {
-            let bytes = content.as_bytes();
-            let section_bytes =
-                &bytes[section.byte_start.min(bytes.len())..section.byte_end.min(bytes.len())];
-
-            let section_content = String::from_utf8_lossy(section_bytes).to_string();
+            let section_content = self
+                .format_for(&section.file_path)
+                .section_body(&content, section);
 
             let lines_text = if section_content.trim().is_empty() {
                 "\n".to_string()
@@ -392,37 +902,531 @@ impl AppState {
                 format!("\n{}\n", section_content.trim())
             };
 
-            let lines = Lines::from(lines_text.as_str());
-            self.editor_state = Some(EditorState::new(lines));
+            self.open_editor(&lines_text);
         }
 
         self.current_view = View::Detail;
     }
 
+    /// Original text of a compared file, when an LHS source was given.
+    fn read_lhs(&self, file_path: &str) -> Option<String> {
+        match self.lhs_source.as_ref()? {
+            OriginalSource::Path(source) if source.is_dir() => {
+                fs::read_to_string(source.join(Path::new(file_path))).ok()
+            }
+            OriginalSource::Path(source) => fs::read_to_string(source).ok(),
+            OriginalSource::GitIndex(repo) => {
+                let relative = Path::new(file_path).strip_prefix(repo).ok()?;
+                git::index_content(repo, relative)
+            }
+        }
+    }
+
+    /// Repository whose index is being staged into, in git mode.
+    #[must_use]
+    pub fn git_repo(&self) -> Option<&Path> {
+        match self.lhs_source.as_ref()? {
+            OriginalSource::GitIndex(repo) => Some(repo),
+            OriginalSource::Path(_) => None,
+        }
+    }
+
+    /// Patch staging the accepted and edited hunks, for `git apply --cached`.
+    #[must_use]
+    pub fn staged_patch(&self) -> String {
+        let Some(repo) = self.git_repo() else {
+            return String::new();
+        };
+
+        let mut patch = String::new();
+        for file in &self.files {
+            let file_path = file.to_string_lossy();
+            let mut hunks: Vec<PatchHunk> = self
+                .sections
+                .iter()
+                .filter(|s| s.file_path == file_path)
+                .filter_map(|section| {
+                    let hunk = section.hunk.as_deref()?;
+                    match self.hunk_decisions.get(&section.id)? {
+                        HunkDecision::Accepted => Some(PatchHunk { hunk, edited: None }),
+                        HunkDecision::Edited => Some(PatchHunk {
+                            hunk,
+                            edited: section.section_content.as_deref(),
+                        }),
+                        HunkDecision::Rejected => None,
+                    }
+                })
+                .collect();
+            if hunks.is_empty() {
+                continue;
+            }
+
+            let original = self.read_lhs(&file_path).unwrap_or_default();
+            let modified = fs::read_to_string(file).unwrap_or_default();
+            let relative = file.strip_prefix(repo).unwrap_or(file).to_string_lossy();
+            patch.push_str(&file_patch(&relative, &original, &modified, &mut hunks));
+        }
+        patch
+    }
+
+    /// Write the staged patch to the patch file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the patch file cannot be written.
+    pub fn write_patch(&self) -> io::Result<()> {
+        match &self.patch_out {
+            Some(path) => atomic::write_file(path, self.staged_patch().as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a decision on the current hunk, staging the lines it will write.
+    ///
+    /// Accepting keeps the modified file as it is; rejecting stages the original lines, rebuilt
+    /// from the difftastic output when no LHS source was given. In git mode accepting stages the
+    /// hunk and rejecting leaves it unstaged.
+    pub fn decide_hunk(&mut self, decision: HunkDecision) {
+        let Some(section_idx) = self.get_current_section_index() else {
+            return;
+        };
+        let section = &self.sections[section_idx];
+        let Some(hunk) = &section.hunk else {
+            self.message = Some("Not a diff hunk".to_string());
+            return;
+        };
+
+        let staged = match decision {
+            HunkDecision::Accepted => None,
+            // Unstaged hunks stay as they are in the working tree
+            HunkDecision::Rejected if self.git_repo().is_some() => None,
+            HunkDecision::Rejected => {
+                // Difftastic only reports changed tokens, so the original text cannot be rebuilt
+                let Some(lhs) = self.read_lhs(&section.file_path) else {
+                    self.message =
+                        Some("Cannot reject without the original; pass --lhs".to_string());
+                    return;
+                };
+                let rhs = fs::read_to_string(&section.file_path).ok();
+                Some(DiffView::original_lines(hunk, Some(&lhs), rhs.as_deref()))
+            }
+            HunkDecision::Edited => section.section_content.clone(),
+        };
+
+        let id = section.id.clone();
+        self.sections[section_idx].section_content = staged;
+        self.hunk_decisions.insert(id, decision);
+    }
+
+    /// Forget the decision on the current hunk.
+    pub fn undo_hunk_decision(&mut self) {
+        if let Some(section_idx) = self.get_current_section_index() {
+            if self
+                .hunk_decisions
+                .remove(&self.sections[section_idx].id)
+                .is_some()
+            {
+                self.sections[section_idx].section_content = None;
+            }
+        }
+    }
+
+    /// Open the current hunk's modified lines (or its staged edit) in the editor.
+    pub fn edit_hunk(&mut self) {
+        let Some(section_idx) = self.get_current_section_index() else {
+            return;
+        };
+        let section = &self.sections[section_idx];
+        let Some(hunk) = &section.hunk else {
+            return;
+        };
+
+        let lines = section.section_content.clone().unwrap_or_else(|| {
+            let lhs = self.read_lhs(&section.file_path);
+            let rhs = fs::read_to_string(&section.file_path).ok();
+            DiffView::modified_lines(hunk, lhs.as_deref(), rhs.as_deref())
+        });
+
+        self.diff_view = None;
+        self.open_editor(&lines.join("\n"));
+        self.current_view = View::Detail;
+    }
+
+    /// Write rejected and edited hunks into their modified files.
+    ///
+    /// Later hunks in the same file shift by the lines each write adds or removes, so they can be
+    /// applied afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be patched.
+    pub fn apply_hunk_decisions(&mut self) -> io::Result<usize> {
+        let staged = self.staged_hunks();
+        if staged.is_empty() {
+            return Ok(0);
+        }
+
+        let edits = staged
+            .iter()
+            .filter_map(|&idx| self.edit_for(&self.sections[idx]))
+            .collect();
+        EditPlan { edits }.apply_with(self.write_options)?;
+        self.settle_hunks(&staged);
+
+        Ok(staged.len())
+    }
+
+    /// Hunks with replacement lines staged by a reject or edit decision.
+    fn staged_hunks(&self) -> Vec<usize> {
+        self.hunk_decisions
+            .iter()
+            .filter(|&(_, &decision)| decision != HunkDecision::Accepted)
+            .filter_map(|(id, _)| self.section_index(id))
+            .filter(|&idx| self.sections[idx].section_content.is_some())
+            .collect()
+    }
+
+    /// Move written hunks and the sections after them to where their lines now are.
+    fn settle_hunks(&mut self, written: &[usize]) {
+        let mut written = written.to_vec();
+        // Shift from the bottom up so each hunk's own position is still the original one
+        written.sort_by_key(|&idx| std::cmp::Reverse(self.sections[idx].line_start));
+        for &idx in &written {
+            let lines = self.sections[idx]
+                .section_content
+                .take()
+                .unwrap_or_default();
+            let (file_path, start, end) = {
+                let s = &self.sections[idx];
+                (s.file_path.clone(), s.line_start, s.line_end)
+            };
+            let written = i64::try_from(lines.len()).unwrap_or(0);
+            let delta = written - (end - start);
+
+            for (other, section) in self.sections.iter_mut().enumerate() {
+                if other != idx && section.file_path == file_path && section.line_start >= end {
+                    section.line_start += delta;
+                    section.line_end += delta;
+                }
+            }
+            self.sections[idx].line_end = start + written;
+        }
+    }
+
+    /// Index of the merge conflict the cursor is on, or on one of the sides of.
+    ///
+    /// A side's parent index only counts sections of its own file, so the conflict is found as
+    /// the one in the same file whose lines enclose the side's.
+    fn current_conflict(&self) -> Option<usize> {
+        let idx = self.get_current_section_index()?;
+        let section = &self.sections[idx];
+        if self.format_for(&section.file_path).name() != "conflict" {
+            return None;
+        }
+        if section.level <= 1 {
+            return Some(idx);
+        }
+        self.sections.iter().position(|s| {
+            s.level == 1
+                && s.file_path == section.file_path
+                && s.line_start <= section.line_start
+                && section.line_end <= s.line_end
+        })
+    }
+
+    /// Stage the lines the current conflict resolves to, written by `:w`.
+    pub fn resolve_conflict(&mut self, resolution: Resolution) {
+        let Some(idx) = self.current_conflict() else {
+            self.message = Some("Not a merge conflict".to_string());
+            return;
+        };
+        let section = &self.sections[idx];
+        let content = match fs::read_to_string(&section.file_path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.message = Some(format!("Error reading {}: {e}", section.file_path));
+                return;
+            }
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let start = usize::try_from(section.line_start)
+            .unwrap_or(0)
+            .min(lines.len());
+        let end = usize::try_from(section.line_end)
+            .unwrap_or(0)
+            .clamp(start, lines.len());
+
+        let Some(resolved) = conflict::resolve(&lines[start..end], resolution) else {
+            self.message = Some("Conflict has no base (use diff3 conflict style)".to_string());
+            return;
+        };
+        self.message = Some(format!("{}: keep {}", section.title, resolution.label()));
+        let id = section.id.clone();
+        self.sections[idx].section_content = Some(resolved);
+        self.conflict_resolutions.insert(id, resolution);
+    }
+
+    /// Clear the staged resolution of the current conflict.
+    pub fn undo_conflict_resolution(&mut self) {
+        if let Some(idx) = self.current_conflict() {
+            if self
+                .conflict_resolutions
+                .remove(&self.sections[idx].id)
+                .is_some()
+            {
+                self.sections[idx].section_content = None;
+            }
+        }
+    }
+
+    /// Write staged conflict resolutions into their files and reparse them.
+    ///
+    /// Returns the number of conflicts resolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be patched or reparsed.
+    pub fn apply_conflict_resolutions(&mut self) -> io::Result<usize> {
+        let resolved: Vec<usize> = self
+            .conflict_resolutions
+            .keys()
+            .filter_map(|id| self.section_index(id))
+            .collect();
+        if resolved.is_empty() {
+            return Ok(0);
+        }
+
+        let edits: Vec<Edit> = resolved
+            .iter()
+            .filter_map(|&idx| self.edit_for(&self.sections[idx]))
+            .collect();
+        let files: HashSet<String> = edits.iter().map(|e| e.file_name.clone()).collect();
+        EditPlan { edits }.apply_with(self.write_options)?;
+
+        self.conflict_resolutions.clear();
+        for file_path in files {
+            let sections = self.reparse_file(&file_path)?;
+            self.sections.retain(|s| s.file_path != file_path);
+            self.sections.extend(sections);
+        }
+        self.rebuild_tree();
+        self.current_node_index = self.navigate_to_first().unwrap_or(0);
+
+        Ok(resolved.len())
+    }
+
+    /// Returns from the diff view to the section list.
+    pub fn exit_diff_view(&mut self) {
+        self.diff_view = None;
+        self.current_view = View::List;
+    }
+
+    /// Load text into the editor, remembering it to tell whether the buffer has changed.
+    fn open_editor(&mut self, text: &str) {
+        self.editor_state = Some(EditorState::new(Lines::from(text)));
+        self.editor_baseline = Some(text.to_string());
+    }
+
+    /// Lines in the editor buffer, if it is open.
+    fn editor_lines(&self) -> Option<Vec<String>> {
+        let editor_state = self.editor_state.as_ref()?;
+        Some(
+            editor_state
+                .lines
+                .iter_row()
+                .map(|line| line.iter().collect::<String>())
+                .collect(),
+        )
+    }
+
+    /// Whether the editor buffer differs from what was loaded into it or last written.
+    #[must_use]
+    pub fn editor_dirty(&self) -> bool {
+        self.editor_lines()
+            .is_some_and(|lines| self.editor_baseline.as_deref() != Some(lines.join("\n").as_str()))
+    }
+
+    /// Hard-wrap the paragraph under the editor cursor at `wrap_width`.
+    pub fn reflow_paragraph(&mut self) {
+        let row = self.editor_state.as_ref().map(|state| state.cursor.row);
+        if row.is_some() {
+            self.reflow_editor(row);
+        }
+    }
+
+    /// Hard-wrap all prose in the section being edited at `wrap_width`.
+    pub fn reflow_section(&mut self) {
+        self.reflow_editor(None);
+    }
+
+    /// Whether the section being edited is prose rather than code.
+    fn editing_prose(&self) -> bool {
+        self.get_current_section().is_some_and(|section| {
+            section.hunk.is_none() && self.format_for(&section.file_path).reflows_prose()
+        })
+    }
+
+    fn reflow_editor(&mut self, row: Option<usize>) {
+        let Some(lines) = self.editor_lines() else {
+            return;
+        };
+        if !self.editing_prose() {
+            self.message = Some("Only prose sections can be reflowed".to_string());
+            return;
+        }
+
+        let text = lines.join("\n");
+        let reflowed = match row {
+            Some(row) => reflow::reflow_paragraph(&text, row, self.wrap_width),
+            None => Some(reflow::reflow(&text, self.wrap_width)),
+        };
+        let Some(reflowed) = reflowed else {
+            self.message = Some("No paragraph under the cursor".to_string());
+            return;
+        };
+        if reflowed != text {
+            if let Some(state) = self.editor_state.as_mut() {
+                state.lines = Lines::from(reflowed.as_str());
+                let last = state.lines.len().saturating_sub(1);
+                state.cursor = Index2::new(state.cursor.row.min(last), 0);
+                state.selection = None;
+            }
+        }
+    }
+
     /// Returns to section list, optionally persisting editor changes.
     pub fn exit_detail_view(&mut self, save: bool) {
         if save {
-            if let Some(ref editor_state) = self.editor_state {
-                if let Some(section_idx) = self.get_current_section_index() {
-                    let lines = editor_state
-                        .lines
-                        .iter_row()
-                        .map(|line| line.iter().collect::<String>())
-                        .collect();
-                    self.sections[section_idx].section_content = Some(lines);
-                }
+            if let (Some(lines), Some(section_idx)) =
+                (self.editor_lines(), self.get_current_section_index())
+            {
+                self.sections[section_idx].section_content = Some(lines);
             }
         }
         self.editor_state = None;
+        self.editor_baseline = None;
         self.current_view = View::List;
     }
 
+    /// Titles of sections holding edits that have not been written to disk.
+    #[must_use]
+    pub fn unsaved_sections(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .filter(|section| {
+                section.section_content.is_some()
+                    && self.hunk_decisions.get(&section.id) != Some(&HunkDecision::Accepted)
+            })
+            .map(|section| section.title.as_str())
+            .collect()
+    }
+
+    /// Whether any section of a file holds unwritten edits.
+    #[must_use]
+    pub fn file_dirty(&self, file_path: &str) -> bool {
+        self.sections
+            .iter()
+            .any(|s| s.file_path == file_path && s.section_content.is_some())
+    }
+
+    /// Whether quitting now would lose edits; if so the status message lists them.
+    pub fn guard_quit(&mut self) -> bool {
+        let mut unsaved: Vec<String> = self
+            .unsaved_sections()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        if self.move_state == MoveState::Moved {
+            unsaved.push("section order".to_string());
+        }
+        if unsaved.is_empty() {
+            return false;
+        }
+        let listed = if unsaved.len() > 3 {
+            format!("{} and {} more", unsaved[..3].join(", "), unsaved.len() - 3)
+        } else {
+            unsaved.join(", ")
+        };
+        self.message = Some(format!(
+            "Unsaved: {listed} (:wa writes all, :q! quits without saving)"
+        ));
+        true
+    }
+
+    /// Write every pending edit, including the open editor's, in one edit plan.
+    ///
+    /// Diff hunks shift as `apply_hunk_decisions` shifts them; other files are reparsed. In git
+    /// mode hunk decisions go to the staged patch instead of the working tree.
+    ///
+    /// Returns the number of sections written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be patched, reparsed, or the patch cannot be written.
+    pub fn write_all(&mut self) -> io::Result<usize> {
+        if self.reflow_on_save && self.editing_prose() && self.editor_dirty() {
+            self.reflow_section();
+        }
+        if self.editor_dirty() {
+            if let (Some(lines), Some(idx)) =
+                (self.editor_lines(), self.get_current_section_index())
+            {
+                let text = lines.join("\n");
+                if self.sections[idx].hunk.is_some() {
+                    let id = self.sections[idx].id.clone();
+                    self.hunk_decisions.insert(id, HunkDecision::Edited);
+                }
+                self.sections[idx].section_content = Some(lines);
+                self.editor_baseline = Some(text);
+            }
+        }
+
+        let git = self.git_repo().is_some();
+        let hunks = if git { Vec::new() } else { self.staged_hunks() };
+        let documents: Vec<usize> = (0..self.sections.len())
+            .filter(|&idx| {
+                let section = &self.sections[idx];
+                section.section_content.is_some() && section.hunk.is_none()
+            })
+            .collect();
+
+        let edits: Vec<Edit> = hunks
+            .iter()
+            .chain(&documents)
+            .filter_map(|&idx| self.edit_for(&self.sections[idx]))
+            .collect();
+        if !edits.is_empty() {
+            EditPlan { edits }.apply_with(self.write_options)?;
+        }
+        self.settle_hunks(&hunks);
+
+        let files: HashSet<String> = documents
+            .iter()
+            .map(|&idx| self.sections[idx].file_path.clone())
+            .collect();
+        for &idx in &documents {
+            self.sections[idx].section_content = None;
+            self.conflict_resolutions.remove(&self.sections[idx].id);
+        }
+        for file_path in &files {
+            self.reload_file(file_path)?;
+        }
+        if git {
+            self.write_patch()?;
+        }
+
+        Ok(hunks.len() + documents.len())
+    }
+
     /// Save the current section's content to disk.
     ///
     /// # Errors
     ///
     /// Returns an error if writing to disk fails or if file operations cannot complete.
     pub fn save_current(&mut self) -> io::Result<()> {
+        if self.reflow_on_save && self.editing_prose() {
+            self.reflow_section();
+        }
         let editor_lines = if let Some(ref editor_state) = self.editor_state {
             editor_state
                 .lines
@@ -441,63 +1445,124 @@ impl AppState {
 
         let section = &self.sections[section_idx];
 
-        let raw_content = editor_lines.join("\n");
-        let trimmed_content = raw_content.trim();
-        let padded_content = format!("\n{trimmed_content}\n\n");
-
-        let edit = Edit {
-            file_name: section.file_path.clone(),
-            line_start: section.line_start,
-            line_end: section.line_end,
-            column_start: section.column_start,
-            column_end: section.column_end,
-            section_content: padded_content,
-            item_name: section.title.clone(),
-        };
+        if section.hunk.is_some() {
+            self.hunk_decisions
+                .insert(section.id.clone(), HunkDecision::Edited);
+            self.editor_baseline = Some(editor_lines.join("\n"));
+            self.message = Some("Hunk edit staged (:w in the list writes decisions)".to_string());
+            return Ok(());
+        }
 
-        let mut plan = EditPlan { edits: vec![edit] };
-        plan.apply()?;
+        let raw_content = editor_lines.join("\n");
+        let format = self.format_for(&section.file_path);
 
-        // Reload sections
-        let format = MarkdownFormat;
-        if let Ok(new_sections) =
-            input::extract_sections(&PathBuf::from(&section.file_path), &format)
-        {
-            let target_title = section.title.clone();
-            let target_level = section.level;
+        let content = fs::read_to_string(&section.file_path)?;
+        if let Some(spliced) = format.splice_section(&content, section, &raw_content) {
+            let file = (PathBuf::from(&section.file_path), spliced?);
+            atomic::write_files(&[file], self.write_options)?;
+        } else {
+            let trimmed_content = raw_content.trim();
+            let padded_content = format!("\n{trimmed_content}\n\n");
+
+            let edit = Edit {
+                file_name: section.file_path.clone(),
+                line_start: section.line_start,
+                line_end: section.line_end,
+                column_start: section.column_start,
+                column_end: section.column_end,
+                section_content: padded_content,
+                item_name: section.title.clone(),
+                verbatim: false,
+                section_id: Some(section.id.clone()),
+            };
 
-            let file_path = section.file_path.clone();
-            self.sections.retain(|s| s.file_path != file_path);
+            let mut plan = EditPlan { edits: vec![edit] };
+            plan.apply_with(self.write_options)?;
+        }
 
-            if let Some(local_index) = new_sections
+        // Reload sections; rebuilding the tree finds the edited section again by its ID
+        let file_path = section.file_path.clone();
+        if let Ok(new_sections) = self.reparse_file(&file_path) {
+            // Unsaved edits to the file's other sections carry over to their reparsed selves
+            let pending: Vec<(SectionId, Vec<String>)> = self
+                .sections
                 .iter()
-                .position(|s| s.title == target_title && s.level == target_level)
-            {
-                let new_global_index = self.sections.len() + local_index;
-                self.sections.extend(new_sections);
-
-                // Rebuild tree and find the updated section
-                self.rebuild_tree();
-
-                // Find node with this section index
-                if let Some(node_idx) = self
-                    .tree_nodes
-                    .iter()
-                    .position(|n| n.section_index == Some(new_global_index))
-                {
-                    self.current_node_index = node_idx;
+                .enumerate()
+                .filter(|&(idx, s)| idx != section_idx && s.file_path == file_path)
+                .filter_map(|(_, s)| Some((s.id.clone(), s.section_content.clone()?)))
+                .collect();
+            self.sections.retain(|s| s.file_path != file_path);
+            self.sections.extend(new_sections);
+            section::assign_ids(&mut self.sections);
+            for (id, content) in pending {
+                if let Some(section) = self.sections.iter_mut().find(|s| s.id == id) {
+                    section.section_content = Some(content);
                 }
-            } else {
-                self.sections.extend(new_sections);
-                self.rebuild_tree();
             }
+            self.rebuild_tree();
         }
 
-        self.rebuild_file_offsets();
+        self.editor_baseline = Some(raw_content);
         self.message = Some("Saved".to_string());
         Ok(())
     }
 
+    /// Next row the list cursor can rest on: a section, or a folder to fold.
+    #[must_use]
+    pub fn find_next_row(&self) -> Option<usize> {
+        ((self.current_node_index + 1)..self.tree_nodes.len())
+            .find(|&i| self.tree_nodes[i].selectable())
+    }
+
+    /// Previous row the list cursor can rest on: a section, or a folder to fold.
+    #[must_use]
+    pub fn find_prev_row(&self) -> Option<usize> {
+        (0..self.current_node_index)
+            .rev()
+            .find(|&i| self.tree_nodes[i].selectable())
+    }
+
+    /// Path of the folder under the cursor, if the cursor is on one.
+    #[must_use]
+    pub fn current_directory(&self) -> Option<&str> {
+        match &self.tree_nodes.get(self.current_node_index)?.node_type {
+            NodeType::Directory { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    fn directory_node(&self, path: &str) -> Option<usize> {
+        self.tree_nodes.iter().position(
+            |node| matches!(&node.node_type, NodeType::Directory { path: p, .. } if p == path),
+        )
+    }
+
+    /// Nearest folder shown above the node under the cursor.
+    #[must_use]
+    pub fn navigate_to_enclosing_directory(&self) -> Option<usize> {
+        let node = self.tree_nodes.get(self.current_node_index)?;
+        let path = match &node.node_type {
+            NodeType::Directory { path, .. } | NodeType::File { path, .. } => path.as_str(),
+            NodeType::Section(section) => section.file_path.as_str(),
+        };
+        Path::new(path)
+            .ancestors()
+            .skip(1)
+            .find_map(|dir| self.directory_node(&dir.to_string_lossy()))
+    }
+
+    /// Fold or unfold the folder under the cursor.
+    pub fn toggle_directory(&mut self) {
+        if let Some(path) = self.current_directory().map(str::to_string) {
+            if self.collapsed.remove(&path) {
+                self.parse_folder(&path);
+            } else {
+                self.collapsed.insert(path);
+            }
+            self.rebuild_tree();
+        }
+    }
+
     /// Navigate to next navigable node
     #[must_use]
     pub fn find_next_node(&self) -> Option<usize> {
@@ -674,6 +1739,15 @@ impl AppState {
     /// Start moving the current section
     pub fn start_move(&mut self) {
         if let Some(section_idx) = self.get_current_section_index() {
+            let format = self.format_for(&self.sections[section_idx].file_path);
+            if !format.supports_reorder() {
+                self.message = Some(format!(
+                    "Reordering is not supported for {} sections",
+                    format.name()
+                ));
+                return;
+            }
+            self.move_origin = self.section_layout();
             self.moving_section_index = Some(section_idx);
             self.move_state = MoveState::Selected;
         }
@@ -683,6 +1757,19 @@ impl AppState {
     pub fn cancel_move(&mut self) {
         self.moving_section_index = None;
         self.move_state = MoveState::None;
+        self.move_origin.clear();
+    }
+
+    /// Start byte and level of each file's sections in their current order
+    fn section_layout(&self) -> HashMap<String, Vec<(usize, usize)>> {
+        let mut layout: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        for section in &self.sections {
+            layout
+                .entry(section.file_path.clone())
+                .or_default()
+                .push((section.byte_start, section.level));
+        }
+        layout
     }
 
     /// Mark section as moved but not yet saved
@@ -822,26 +1909,53 @@ impl AppState {
             return Ok(());
         }
 
-        // Group sections by file
-        let mut file_sections: HashMap<String, Vec<&Section>> = HashMap::new();
-        for section in &self.sections {
-            file_sections
-                .entry(section.file_path.clone())
-                .or_default()
-                .push(section);
+        // Only files whose sections changed order or level are rewritten
+        let layout = self.section_layout();
+        let mut changed: Vec<&String> = layout
+            .iter()
+            .filter(|(file, order)| self.move_origin.get(*file) != Some(*order))
+            .map(|(file, _)| file)
+            .collect();
+        changed.sort();
+        if let Some(file) = changed
+            .iter()
+            .find(|file| !self.format_for(file).supports_reorder())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "Reordering is not supported for {} sections in {file}",
+                    self.format_for(file).name()
+                ),
+            ));
         }
 
-        // Process each file
-        for (file_path, sections) in file_sections {
-            Self::rewrite_file_sections(&file_path, &sections)?;
+        let rewritten = changed
+            .iter()
+            .map(|file| {
+                let sections: Vec<&Section> = self
+                    .sections
+                    .iter()
+                    .filter(|s| &s.file_path == *file)
+                    .collect();
+                let content = Self::rewrite_file_sections(file, &sections)?;
+                Ok((PathBuf::from(file.as_str()), content))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        atomic::write_files(&rewritten, self.write_options)?;
+
+        // Reload the rewritten files to get updated positions, keeping the others' sections
+        let changed: Vec<String> = changed.into_iter().cloned().collect();
+        let mut reparsed = HashMap::new();
+        for file in &changed {
+            reparsed.insert(file.clone(), self.reparse_file(file)?);
         }
-
-        // Reload sections to get updated positions
-        let format = MarkdownFormat;
-        let mut new_sections = Vec::new();
-        for file in &self.files {
-            if let Ok(secs) = input::extract_sections(file, &format) {
-                new_sections.extend(secs);
+        let mut new_sections = Vec::with_capacity(self.sections.len());
+        for section in std::mem::take(&mut self.sections) {
+            match reparsed.remove(&section.file_path) {
+                Some(secs) => new_sections.extend(secs),
+                None if changed.contains(&section.file_path) => {}
+                None => new_sections.push(section),
             }
         }
 
@@ -853,8 +1967,8 @@ impl AppState {
         Ok(())
     }
 
-    /// Rewrite an entire file with reordered sections
-    fn rewrite_file_sections(file_path: &str, sections: &[&Section]) -> io::Result<()> {
+    /// Contents of an entire file rewritten with reordered sections
+    fn rewrite_file_sections(file_path: &str, sections: &[&Section]) -> io::Result<String> {
         let content = fs::read_to_string(file_path)?;
         let mut new_content = String::new();
 
@@ -881,8 +1995,7 @@ impl AppState {
             }
         }
 
-        fs::write(file_path, new_content)?;
-        Ok(())
+        Ok(new_content)
     }
 }
 
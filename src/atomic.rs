@@ -0,0 +1,186 @@
+//! Crash-safe file writes, so an interrupted save never leaves a document half-written.
+//!
+//! Each file is written to a temporary file beside it and renamed into place, which replaces it
+//! in one step. Saves touching several files first read every original, so when one write fails
+//! the files already written can be put back and the document set stays consistent.
+//!
+//! Backups never replace an existing file: when `<file>.orig` is taken, the next free
+//! `<file>.orig.<n>` is used. A backup only goes away once its file has been put back.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// How files are written to disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Keep each file's previous contents beside it as `<file>.orig`.
+    pub backup: bool,
+}
+
+/// Path of the `n`th backup of a file: `<file>.orig`, then `<file>.orig.1` and so on.
+#[must_use]
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".orig");
+    if n > 0 {
+        name.push(format!(".{n}"));
+    }
+    path.with_file_name(name)
+}
+
+/// Write a file's previous contents to its first backup path not already taken.
+fn write_backup(path: &Path, original: &[u8]) -> io::Result<PathBuf> {
+    for n in 0.. {
+        let backup = backup_path(path, n);
+        let mut file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let written = file.write_all(original).and_then(|()| file.sync_all());
+        if let Err(e) = written {
+            let _ = fs::remove_file(&backup);
+            return Err(e);
+        }
+        return Ok(backup);
+    }
+    unreachable!("backup names are unbounded")
+}
+
+/// Replace a file's contents through a temporary file and a rename.
+///
+/// Symlinks are followed so the link stays in place, and an existing file keeps its permissions.
+///
+/// # Errors
+///
+/// Returns an error if the temporary file cannot be written or renamed over the target.
+pub fn write_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    let temp = dir.join(temp_name);
+
+    let written = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        fs::rename(&temp, &target)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written?;
+
+    // Persist the rename itself; not every platform can open a directory, so this is best effort
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Write several files so that either all of them change or, on failure, none do.
+///
+/// When a write fails, the files already written are restored to their previous contents (or
+/// removed, if they did not exist). Each backup made is removed once its file is back as it was,
+/// and the error names the file that failed, those restored, and any backups kept.
+///
+/// # Errors
+///
+/// Returns an error if an original cannot be read or a file cannot be written.
+pub fn write_files(files: &[(PathBuf, String)], options: WriteOptions) -> io::Result<()> {
+    let originals = files
+        .iter()
+        .map(|(path, _)| match fs::read(path) {
+            Ok(original) => Ok(Some(original)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut backups = Vec::new();
+    for (done, ((path, contents), original)) in files.iter().zip(&originals).enumerate() {
+        let written = match original {
+            Some(original) if options.backup => write_backup(path, original).map(Some),
+            _ => Ok(None),
+        }
+        .and_then(|backup| {
+            backups.push(backup);
+            write_file(path, contents.as_bytes())
+        });
+
+        if let Err(e) = written {
+            let restored = rollback(&files[..done], &originals[..done], &backups);
+            return Err(io::Error::new(
+                e.kind(),
+                format!("Could not write {}: {e}{restored}", path.display()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Put written files back as they were, describing the outcome for an error message.
+///
+/// `backups` holds the backup made for each file, if any; entries past `files` belong to files
+/// that were never replaced. A backup is removed only once its file is as it was, so a file that
+/// cannot be restored keeps its previous contents in the backup.
+fn rollback(
+    files: &[(PathBuf, String)],
+    originals: &[Option<Vec<u8>>],
+    backups: &[Option<PathBuf>],
+) -> String {
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    let mut kept = Vec::new();
+    for (i, backup) in backups.iter().enumerate() {
+        let result = match (files.get(i), originals.get(i)) {
+            (Some((path, _)), Some(original)) => {
+                let result = match original {
+                    Some(original) => write_file(path, original),
+                    None => fs::remove_file(path),
+                };
+                match &result {
+                    Ok(()) => restored.push(path.display().to_string()),
+                    Err(e) => failed.push(format!("{} ({e})", path.display())),
+                }
+                result
+            }
+            _ => Ok(()),
+        };
+        if let Some(backup) = backup {
+            if result.is_err() || fs::remove_file(backup).is_err() {
+                kept.push(backup.display().to_string());
+            }
+        }
+    }
+
+    let mut outcome = String::new();
+    if !restored.is_empty() {
+        outcome = format!("; restored {}", restored.join(", "));
+    }
+    if !failed.is_empty() {
+        outcome = format!("{outcome}; could not restore {}", failed.join(", "));
+    }
+    if !kept.is_empty() {
+        outcome = format!("{outcome}; backups kept at {}", kept.join(", "));
+    }
+    outcome
+}
+
+#[cfg(test)]
+#[path = "tests/atomic.rs"]
+mod tests;
@@ -1,35 +1,311 @@
 //! Configuration to acknowledge developer preferences as well as set defaults.
 //!
-//! Specifically, we try to find an asterism.toml, and if present we load settings from there.
-//! This provides wrapping width and file extension preferences.
+//! Settings come from asterism.toml files in the user's config directory, the repository root and
+//! the working directory, each overriding the last, or from a single file passed with `--config`.
+//! They cover wrapping width and reflow, file extensions and discovery, formats, backups,
+//! keybindings and the theme.
 
+use crate::input::Discovery;
 use facet::Facet;
-use std::fs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs, io};
+use toml_edit::{value, Array, ArrayOfTables, DocumentMut, Item, Table, TableLike};
+
+/// Name of the configuration file looked for in each location.
+pub const CONFIG_FILE: &str = "asterism.toml";
 
 /// User preferences loaded from asterism.toml or falling back to defaults.
 #[derive(Facet, Clone)]
 pub struct Config {
-    /// Maximum line width for editor text wrapping.
+    /// Width prose is hard-wrapped at by `:gq`, `:reflow` and `reflow_on_save`.
     #[facet(default = 100)]
     pub wrap_width: usize,
+    /// Reflow prose in a section whenever it is saved from the editor.
+    #[facet(default)]
+    pub reflow_on_save: bool,
     #[facet(default = vec!["md".to_string()])]
     /// File suffixes to match when scanning directories.
     pub file_extensions: Vec<String>,
+    /// Include and exclude globs, depth, ignore files and symlinks for directory scans.
+    #[facet(default)]
+    pub discovery: Discovery,
+    /// Extension-to-format assignments overriding the built-in registry (e.g. `mdx = "markdown"`).
+    #[facet(default)]
+    pub formats: HashMap<String, String>,
+    /// Formats declared through tree-sitter queries, one `[[custom_formats]]` table each.
+    #[facet(default)]
+    pub custom_formats: Vec<FormatDefinition>,
+    /// Remember parsed sections between launches so unchanged documents are not parsed again.
+    #[facet(default = true)]
+    pub parse_cache: bool,
+    /// Number of documents past which those in subfolders are parsed only when first unfolded.
+    #[facet(default = 1000)]
+    pub lazy_threshold: usize,
+    /// Keep each file's contents from before a save as `<file>.orig`.
+    #[facet(default)]
+    pub backup: bool,
+    /// Keymap preset the `[keys]` table binds over: "default" or "vim".
+    #[facet(default)]
+    pub keymap: Option<String>,
+    /// Key sequences bound to section list actions (e.g. `"ctrl+k" = "move-up"`).
+    #[facet(default)]
+    pub keys: HashMap<String, String>,
+    /// Colours, glyphs and syntax theme, from the `[theme]` table.
+    #[facet(default)]
+    pub theme: ThemeConfig,
+}
+
+/// Theme choices from the `[theme]` table of asterism.toml; unset values come from the base theme.
+#[derive(Facet, Clone, Default)]
+pub struct ThemeConfig {
+    /// Bundled theme to start from: "dark" (the default), "light" or "mono".
+    #[facet(default)]
+    pub name: Option<String>,
+    /// Heading colours by level, e.g. `["cyan", "#88c0d0"]`.
+    #[facet(default)]
+    pub headings: Option<Vec<String>>,
+    /// Colour of directories and diffed files.
+    #[facet(default)]
+    pub accent: Option<String>,
+    /// Colour of file names.
+    #[facet(default)]
+    pub file: Option<String>,
+    /// Colour of added lines and accepted hunks.
+    #[facet(default)]
+    pub added: Option<String>,
+    /// Colour of removed lines and rejected hunks.
+    #[facet(default)]
+    pub removed: Option<String>,
+    /// Colour of modified lines and unsaved edits.
+    #[facet(default)]
+    pub modified: Option<String>,
+    /// Background of the selected entry, instead of reversed text.
+    #[facet(default)]
+    pub selection: Option<String>,
+    /// Colour of a section being moved.
+    #[facet(default)]
+    pub moving: Option<String>,
+    /// Syntect theme name, e.g. "base16-ocean.dark", or "none" to turn highlighting off.
+    #[facet(default)]
+    pub syntax: Option<String>,
+    /// Tree characters: "unicode" (the default) or "ascii".
+    #[facet(default)]
+    pub glyphs: Option<String>,
+}
+
+/// A document format declared in asterism.toml instead of Rust.
+///
+/// Lets a team navigate any structure a bundled grammar can parse by writing the queries that
+/// locate headings and their titles.
+#[derive(Facet, Clone)]
+pub struct FormatDefinition {
+    /// Registry name, usable with `--format` and in the `[formats]` table.
+    pub name: String,
+    /// File suffixes this format claims.
+    #[facet(default)]
+    pub extensions: Vec<String>,
+    /// Bundled tree-sitter grammar: "markdown" or "`markdown_inline`", the only two available.
+    pub grammar: String,
+    /// Query whose `@heading` capture (or first capture) marks where each section begins.
+    pub section_query: String,
+    /// Query run within each heading whose `@title` capture supplies the section title.
+    pub title_query: String,
+    /// Capture in `section_query` whose node determines the heading level.
+    #[facet(default)]
+    pub level_capture: Option<String>,
+    /// How the level is derived: "kind" (digit in the node kind, the default), "length" (run of
+    /// marker characters such as `==`), "depth" (nesting of the heading kind) or a fixed number.
+    #[facet(default)]
+    pub level_rule: Option<String>,
 }
 
 impl Config {
-    /// Load configuration from asterism.toml if present.
+    /// Load the configuration from `explicit` alone, or else from every asterism.toml found.
+    ///
+    /// Returns the configuration with the files it was read from, lowest precedence first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the file that cannot be read or does not parse.
+    pub fn load(explicit: Option<&Path>) -> io::Result<(Self, Vec<PathBuf>)> {
+        let sources = match explicit {
+            Some(path) => vec![path.to_path_buf()],
+            None => discover(&env::current_dir()?),
+        };
+        let config = Self::from_files(&sources)?;
+        Ok((config, sources))
+    }
+
+    /// Merge configuration files, each overriding those before it.
+    ///
+    /// Tables merge key by key and `[[custom_formats]]` accumulate; any other value is replaced.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the default configuration cannot be parsed.
+    /// Returns an error naming the file that cannot be read or does not parse.
+    pub fn from_files(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut merged = DocumentMut::new();
+        for path in paths {
+            let in_file = |e: &dyn fmt::Display| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {e}", path.display()),
+                )
+            };
+            let text = fs::read_to_string(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+            // Each file is checked on its own so that errors name the file they are in
+            facet_toml::from_str::<Self>(&text).map_err(|e| in_file(&e))?;
+            let document: DocumentMut = text.parse().map_err(|e| in_file(&e))?;
+            merge(merged.as_table_mut(), document.as_table());
+        }
+        facet_toml::from_str::<Self>(&merged.to_string())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// The configuration as TOML, as `asterism config show` prints it.
     #[must_use]
-    pub fn load() -> Self {
-        if let Ok(contents) = fs::read_to_string("asterism.toml") {
-            if let Ok(config) = facet_toml::from_str::<Self>(&contents) {
-                return config;
+    pub fn to_toml(&self) -> String {
+        let mut document = DocumentMut::new();
+        document["wrap_width"] = value(i64::try_from(self.wrap_width).unwrap_or(i64::MAX));
+        document["reflow_on_save"] = value(self.reflow_on_save);
+        document["file_extensions"] = value(Array::from_iter(&self.file_extensions));
+        document["parse_cache"] = value(self.parse_cache);
+        document["lazy_threshold"] = value(i64::try_from(self.lazy_threshold).unwrap_or(i64::MAX));
+        document["backup"] = value(self.backup);
+        if let Some(keymap) = &self.keymap {
+            document["keymap"] = value(keymap);
+        }
+
+        let discovery = &self.discovery;
+        let mut table = Table::new();
+        table["include"] = value(Array::from_iter(&discovery.include));
+        table["exclude"] = value(Array::from_iter(&discovery.exclude));
+        if let Some(depth) = discovery.max_depth {
+            table["max_depth"] = value(i64::try_from(depth).unwrap_or(i64::MAX));
+        }
+        table["respect_ignore"] = value(discovery.respect_ignore);
+        table["follow_symlinks"] = value(discovery.follow_symlinks);
+        document["discovery"] = Item::Table(table);
+
+        document["formats"] = Item::Table(sorted_table(&self.formats));
+        document["keys"] = Item::Table(sorted_table(&self.keys));
+
+        let theme = &self.theme;
+        let mut table = Table::new();
+        for (key, setting) in [
+            ("name", &theme.name),
+            ("accent", &theme.accent),
+            ("file", &theme.file),
+            ("added", &theme.added),
+            ("removed", &theme.removed),
+            ("modified", &theme.modified),
+            ("selection", &theme.selection),
+            ("moving", &theme.moving),
+            ("syntax", &theme.syntax),
+            ("glyphs", &theme.glyphs),
+        ] {
+            if let Some(setting) = setting {
+                table[key] = value(setting);
             }
         }
-        facet_toml::from_str::<Self>("").unwrap()
+        if let Some(headings) = &theme.headings {
+            table["headings"] = value(Array::from_iter(headings));
+        }
+        document["theme"] = Item::Table(table);
+
+        let mut formats = ArrayOfTables::new();
+        for format in &self.custom_formats {
+            let mut table = Table::new();
+            table["name"] = value(&format.name);
+            table["extensions"] = value(Array::from_iter(&format.extensions));
+            table["grammar"] = value(&format.grammar);
+            table["section_query"] = value(&format.section_query);
+            table["title_query"] = value(&format.title_query);
+            if let Some(capture) = &format.level_capture {
+                table["level_capture"] = value(capture);
+            }
+            if let Some(rule) = &format.level_rule {
+                table["level_rule"] = value(rule);
+            }
+            formats.push(table);
+        }
+        if !formats.is_empty() {
+            document["custom_formats"] = Item::ArrayOfTables(formats);
+        }
+        document.to_string()
+    }
+}
+
+/// String map as a TOML table with its keys in order.
+fn sorted_table(map: &HashMap<String, String>) -> Table {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort();
+    let mut table = Table::new();
+    for (key, setting) in entries {
+        table[key.as_str()] = value(setting);
+    }
+    table
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        facet_toml::from_str::<Self>("").expect("an empty configuration takes every default")
     }
 }
+
+/// Configuration files that apply in `dir`, lowest precedence first.
+///
+/// These are the user's (under `$XDG_CONFIG_HOME/asterism`, or `~/.config/asterism`), the one at
+/// the root of the enclosing git repository and the one in `dir` itself.
+#[must_use]
+pub fn discover(dir: &Path) -> Vec<PathBuf> {
+    let user = user_config_dir().map(|config| config.join("asterism").join(CONFIG_FILE));
+    let repo = dir
+        .ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(|root| root.join(CONFIG_FILE));
+    let local = Some(dir.join(CONFIG_FILE));
+
+    let mut found: Vec<PathBuf> = Vec::new();
+    for path in [user, repo, local].into_iter().flatten() {
+        if path.is_file() && !found.contains(&path) {
+            found.push(path);
+        }
+    }
+    found
+}
+
+fn user_config_dir() -> Option<PathBuf> {
+    let nonempty = |name| env::var_os(name).filter(|value| !value.is_empty());
+    nonempty("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| nonempty("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| nonempty("APPDATA").map(PathBuf::from))
+}
+
+/// Lay `overlay` over `base`.
+fn merge(base: &mut dyn TableLike, overlay: &dyn TableLike) {
+    for (key, item) in overlay.iter() {
+        match (base.get_mut(key), item) {
+            (Some(Item::ArrayOfTables(existing)), Item::ArrayOfTables(more)) => {
+                existing.extend(more.iter().cloned());
+            }
+            (Some(existing), _) if existing.is_table_like() && item.is_table_like() => {
+                if let (Some(existing), Some(table)) =
+                    (existing.as_table_like_mut(), item.as_table_like())
+                {
+                    merge(existing, table);
+                }
+            }
+            _ => {
+                base.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/config.rs"]
+mod tests;
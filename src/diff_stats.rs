@@ -0,0 +1,79 @@
+//! Line counts summarising diff hunks, per file and across the whole diff.
+//!
+//! A changed line with both an original and a modified side counts as modified; lines with only
+//! one side count as added or removed.
+
+use crate::formats::difftastic::Hunk;
+use crate::section::Section;
+use std::fmt;
+use std::ops::AddAssign;
+
+/// Added, removed and modified line counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// Lines present only on the modified side.
+    pub added: usize,
+    /// Lines present only on the original side.
+    pub removed: usize,
+    /// Lines changed in place.
+    pub modified: usize,
+}
+
+impl DiffStats {
+    /// Count the changed lines of a hunk.
+    ///
+    /// Hunks without typed lines, such as whole created files, are counted from their range.
+    #[must_use]
+    pub fn from_hunk(hunk: &Hunk) -> Self {
+        if hunk.lines.is_empty() {
+            return Self {
+                added: hunk.range.rhs_count as usize,
+                removed: hunk.range.lhs_count as usize,
+                modified: 0,
+            };
+        }
+        let mut stats = Self::default();
+        for line in &hunk.lines {
+            match (&line.lhs, &line.rhs) {
+                (Some(_), Some(_)) => stats.modified += 1,
+                (Some(_), None) => stats.removed += 1,
+                (None, Some(_)) => stats.added += 1,
+                (None, None) => {}
+            }
+        }
+        stats
+    }
+
+    /// Totals over the hunks among the given sections.
+    pub fn of_sections<'a>(sections: impl IntoIterator<Item = &'a Section>) -> Self {
+        let mut stats = Self::default();
+        for hunk in sections.into_iter().filter_map(|s| s.hunk.as_deref()) {
+            stats += Self::from_hunk(hunk);
+        }
+        stats
+    }
+
+    /// Total lines touched, used to rank files by how much they changed.
+    #[must_use]
+    pub fn churn(&self) -> usize {
+        self.added + self.removed + self.modified
+    }
+}
+
+impl AddAssign for DiffStats {
+    fn add_assign(&mut self, other: Self) {
+        self.added += other.added;
+        self.removed += other.removed;
+        self.modified += other.modified;
+    }
+}
+
+impl fmt::Display for DiffStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} -{} ~{}", self.added, self.removed, self.modified)
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/diff_stats.rs"]
+mod tests;
@@ -0,0 +1,372 @@
+//! Side-by-side model of a diff hunk, aligning original (LHS) and modified (RHS) lines.
+//!
+//! Difftastic reports only the lines that changed and the spans within them, so the rows between
+//! and around those lines are filled in from the compared files. Without the original file, LHS
+//! lines are rebuilt from the RHS: text difftastic leaves unmarked is common to both sides.
+
+use crate::formats::difftastic::{ContextLine, DifftChange, DifftSide, Hunk};
+
+/// Unchanged lines shown before and after a hunk.
+pub const CONTEXT_LINES: usize = 3;
+
+/// A changed span within a line, with the syntax category difftastic gave it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedSpan {
+    /// Byte offset where the span begins in the cell text.
+    pub start: usize,
+    /// Byte offset where the span ends (exclusive).
+    pub end: usize,
+    /// Syntax category, such as "keyword", "string" or "normal".
+    pub highlight: String,
+}
+
+/// One side of a row: a file line and the spans changed in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffCell {
+    /// Line position in its file (0-indexed).
+    pub line_number: usize,
+    /// Full text of the line.
+    pub text: String,
+    /// Whether difftastic reported this line as part of the change.
+    pub changed: bool,
+    /// Changed spans ordered by position.
+    pub changes: Vec<ChangedSpan>,
+}
+
+/// A pair of aligned lines; one side is absent where the other added or removed a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffRow {
+    /// Original line.
+    pub lhs: Option<DiffCell>,
+    /// Modified line.
+    pub rhs: Option<DiffCell>,
+}
+
+/// Aligned rows of one hunk and the scroll position of the view showing them.
+#[derive(Clone, Debug, Default)]
+pub struct DiffView {
+    /// Rows in display order, including context.
+    pub rows: Vec<DiffRow>,
+    /// First row shown.
+    pub scroll: usize,
+}
+
+impl DiffView {
+    /// Align a hunk against the text of the files it compares, where available.
+    #[must_use]
+    pub fn new(hunk: &Hunk, lhs_source: Option<&str>, rhs_source: Option<&str>) -> Self {
+        Self {
+            rows: build_rows(hunk, lhs_source, rhs_source, CONTEXT_LINES),
+            scroll: 0,
+        }
+    }
+
+    /// Original lines the hunk replaced, as restored when it is rejected.
+    #[must_use]
+    pub fn original_lines(
+        hunk: &Hunk,
+        lhs_source: Option<&str>,
+        rhs_source: Option<&str>,
+    ) -> Vec<String> {
+        build_rows(hunk, lhs_source, rhs_source, 0)
+            .into_iter()
+            .filter_map(|row| row.lhs.map(|cell| cell.text))
+            .collect()
+    }
+
+    /// Modified lines the hunk introduced, as presented for editing.
+    #[must_use]
+    pub fn modified_lines(
+        hunk: &Hunk,
+        lhs_source: Option<&str>,
+        rhs_source: Option<&str>,
+    ) -> Vec<String> {
+        build_rows(hunk, lhs_source, rhs_source, 0)
+            .into_iter()
+            .filter_map(|row| row.rhs.map(|cell| cell.text))
+            .collect()
+    }
+
+    /// Scroll towards the end, stopping at the last row.
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll = (self.scroll + rows).min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Scroll towards the start.
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+}
+
+/// Rows of a hunk with up to `context` unchanged lines either side.
+pub(crate) fn build_rows(
+    hunk: &Hunk,
+    lhs_source: Option<&str>,
+    rhs_source: Option<&str>,
+    context: usize,
+) -> Vec<DiffRow> {
+    let mut builder = RowBuilder {
+        lhs: lhs_source.map(|s| s.lines().collect()),
+        rhs: rhs_source.map(|s| s.lines().collect()),
+        context: &hunk.context,
+        l: first_line(hunk.range.lhs_start, hunk.range.lhs_count),
+        r: first_line(hunk.range.rhs_start, hunk.range.rhs_count),
+        rows: Vec::new(),
+    };
+    let l_end = builder.l + hunk.range.lhs_count as usize;
+    let r_end = builder.r + hunk.range.rhs_count as usize;
+
+    builder.leading_context(context);
+    for line in &hunk.lines {
+        match (&line.lhs, &line.rhs) {
+            (Some(lhs), Some(rhs)) => {
+                builder.catch_up(
+                    Some(lhs.line_number as usize),
+                    Some(rhs.line_number as usize),
+                );
+                builder.changed(Some(lhs), Some(rhs));
+            }
+            (Some(lhs), None) => {
+                builder.catch_up(Some(lhs.line_number as usize), None);
+                builder.changed(Some(lhs), None);
+            }
+            (None, Some(rhs)) => {
+                builder.catch_up(None, Some(rhs.line_number as usize));
+                builder.changed(None, Some(rhs));
+            }
+            (None, None) => {}
+        }
+    }
+    builder.catch_up(Some(l_end), Some(r_end));
+    builder.trailing_context(context);
+    builder.rows
+}
+
+/// 0-indexed first line of a unified range, whose start names the preceding line when empty.
+pub(crate) fn first_line(start: u32, count: u32) -> usize {
+    if count == 0 {
+        start as usize
+    } else {
+        start.saturating_sub(1) as usize
+    }
+}
+
+struct RowBuilder<'a> {
+    lhs: Option<Vec<&'a str>>,
+    rhs: Option<Vec<&'a str>>,
+    /// Unchanged lines carried by the diff, used where a side's file is unavailable.
+    context: &'a [ContextLine],
+    /// Next LHS line to place.
+    l: usize,
+    /// Next RHS line to place.
+    r: usize,
+    rows: Vec<DiffRow>,
+}
+
+impl RowBuilder<'_> {
+    fn rhs_text(&self, line: usize) -> Option<&str> {
+        match &self.rhs {
+            Some(rhs) => rhs.get(line).copied(),
+            None => self.context_text(|c| c.rhs_line as usize == line),
+        }
+    }
+
+    fn lhs_text(&self, line: usize) -> Option<&str> {
+        match &self.lhs {
+            Some(lhs) => lhs.get(line).copied(),
+            None => self.context_text(|c| c.lhs_line as usize == line),
+        }
+    }
+
+    fn context_text(&self, at: impl Fn(&ContextLine) -> bool) -> Option<&str> {
+        self.context.iter().find(|c| at(c)).map(|c| c.text.as_str())
+    }
+
+    fn has_source(&self) -> bool {
+        self.lhs.is_some() || self.rhs.is_some()
+    }
+
+    fn unchanged_cell(line: usize, text: Option<&str>) -> DiffCell {
+        DiffCell {
+            line_number: line,
+            text: text.unwrap_or_default().to_string(),
+            changed: false,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Push an unchanged pair, taking the LHS text from the RHS when the original is unavailable.
+    fn unchanged(&mut self, l: usize, r: usize) {
+        let rhs = self.rhs_text(r);
+        let lhs = self.lhs_text(l).or(rhs);
+        let row = DiffRow {
+            lhs: Some(Self::unchanged_cell(l, lhs)),
+            rhs: Some(Self::unchanged_cell(r, rhs)),
+        };
+        self.rows.push(row);
+    }
+
+    /// Place unchanged lines before the given positions.
+    ///
+    /// Lines difftastic leaves out exist on both sides, so both advance together until either
+    /// target is reached; a side without a target simply follows the other.
+    fn catch_up(&mut self, l: Option<usize>, r: Option<usize>) {
+        let before = |pos: usize, target: Option<usize>| target.is_none_or(|t| pos < t);
+        while (l.is_some() || r.is_some()) && before(self.l, l) && before(self.r, r) {
+            self.unchanged(self.l, self.r);
+            self.l += 1;
+            self.r += 1;
+        }
+        let (l, r) = (l.unwrap_or(self.l), r.unwrap_or(self.r));
+        while self.l < l {
+            let cell = Self::unchanged_cell(self.l, self.lhs_text(self.l));
+            self.rows.push(DiffRow {
+                lhs: Some(cell),
+                rhs: None,
+            });
+            self.l += 1;
+        }
+        while self.r < r {
+            let cell = Self::unchanged_cell(self.r, self.rhs_text(self.r));
+            self.rows.push(DiffRow {
+                lhs: None,
+                rhs: Some(cell),
+            });
+            self.r += 1;
+        }
+    }
+
+    fn changed(&mut self, lhs: Option<&DifftSide>, rhs: Option<&DifftSide>) {
+        let rhs_cell = rhs.map(|side| {
+            let line = side.line_number as usize;
+            let text = self
+                .rhs_text(line)
+                .map_or_else(|| place_changes(&side.changes), str::to_string);
+            changed_cell(side, text)
+        });
+        let lhs_cell = lhs.map(|side| {
+            let line = side.line_number as usize;
+            let text = match (self.lhs_text(line), rhs, &rhs_cell) {
+                (Some(text), _, _) => text.to_string(),
+                (None, Some(rhs), Some(cell)) => {
+                    rebuild_lhs(&side.changes, &cell.text, &rhs.changes)
+                }
+                _ => place_changes(&side.changes),
+            };
+            changed_cell(side, text)
+        });
+
+        if let Some(side) = lhs {
+            self.l = self.l.max(side.line_number as usize + 1);
+        }
+        if let Some(side) = rhs {
+            self.r = self.r.max(side.line_number as usize + 1);
+        }
+        self.rows.push(DiffRow {
+            lhs: lhs_cell,
+            rhs: rhs_cell,
+        });
+    }
+
+    fn leading_context(&mut self, context: usize) {
+        if !self.has_source() {
+            return;
+        }
+        let before = context.min(self.l).min(self.r);
+        for back in (1..=before).rev() {
+            self.unchanged(self.l - back, self.r - back);
+        }
+    }
+
+    fn trailing_context(&mut self, context: usize) {
+        if !self.has_source() {
+            return;
+        }
+        for ahead in 0..context {
+            let (l, r) = (self.l + ahead, self.r + ahead);
+            let past_end = match (&self.rhs, &self.lhs) {
+                (Some(rhs), _) => r >= rhs.len(),
+                (None, Some(lhs)) => l >= lhs.len(),
+                (None, None) => true,
+            };
+            if past_end {
+                break;
+            }
+            self.unchanged(l, r);
+        }
+    }
+}
+
+fn changed_cell(side: &DifftSide, text: String) -> DiffCell {
+    let mut changes: Vec<ChangedSpan> = side
+        .changes
+        .iter()
+        .map(|change| ChangedSpan {
+            start: change.start as usize,
+            end: change.end as usize,
+            highlight: change.highlight.clone(),
+        })
+        .collect();
+    changes.sort_by_key(|span| span.start);
+    DiffCell {
+        line_number: side.line_number as usize,
+        text,
+        changed: true,
+        changes,
+    }
+}
+
+/// Lay out change contents at their columns, padding the gaps with spaces.
+fn place_changes(changes: &[DifftChange]) -> String {
+    let mut sorted: Vec<_> = changes.iter().collect();
+    sorted.sort_by_key(|change| change.start);
+
+    let mut text = String::new();
+    for change in sorted {
+        let start = change.start as usize;
+        if text.len() < start {
+            text.push_str(&" ".repeat(start - text.len()));
+        }
+        text.push_str(&change.content);
+    }
+    text
+}
+
+/// Rebuild an original line by filling the gaps between its changes with the unmarked RHS text.
+fn rebuild_lhs(lhs_changes: &[DifftChange], rhs_text: &str, rhs_changes: &[DifftChange]) -> String {
+    let in_rhs_change = |offset: usize| {
+        rhs_changes
+            .iter()
+            .any(|c| (c.start as usize..c.end as usize).contains(&offset))
+    };
+    let mut shared = rhs_text
+        .char_indices()
+        .filter(|&(offset, _)| !in_rhs_change(offset))
+        .map(|(_, c)| c)
+        .peekable();
+
+    let mut sorted: Vec<_> = lhs_changes.iter().collect();
+    sorted.sort_by_key(|change| change.start);
+
+    let mut text = String::new();
+    let mut column = 0;
+    for change in sorted {
+        let gap_end = change.start as usize;
+        while column < gap_end {
+            let Some(c) = shared.next() else { break };
+            text.push(c);
+            column += c.len_utf8();
+        }
+        if text.len() < gap_end {
+            text.push_str(&" ".repeat(gap_end - text.len()));
+        }
+        text.push_str(&change.content);
+        column = change.end as usize;
+    }
+    text.extend(shared);
+    text
+}
+
+#[cfg(test)]
+#[path = "tests/diff_view.rs"]
+mod tests;
@@ -0,0 +1,385 @@
+//! Section-addressed reading and rewriting of documents, for use from other programs.
+//!
+//! A [`Document`] holds a file's text and its sections as a [`Format`] parses them. Sections are
+//! addressed by the titles leading down to them, outermost first, so a generator can fill in
+//! `["Usage", "Examples"]` without knowing line numbers. Text outside the sections an operation
+//! touches is kept byte for byte, and the sections are parsed again after every change.
+//!
+//! Nothing here depends on the terminal interface, so the crate can be used for this with its
+//! default features turned off.
+
+use crate::atomic;
+use crate::formats::Format;
+use crate::section::{self, Section};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Deepest heading level that can be written, as markdown allows.
+const MAX_LEVEL: usize = 6;
+
+/// A document parsed into sections that can be looked up and rewritten by heading path.
+pub struct Document {
+    path: Option<PathBuf>,
+    format: Arc<dyn Format>,
+    content: String,
+    sections: Vec<Section>,
+}
+
+/// A section's heading and body as they appear in the text.
+struct Part {
+    level: usize,
+    title: String,
+    heading: String,
+    body: String,
+}
+
+impl Document {
+    /// Read and parse the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or the format's sections cannot be
+    /// rewritten as headings (as with diffs, conflicts and notebooks).
+    pub fn open(path: impl AsRef<Path>, format: Arc<dyn Format>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let mut document = Self::parse(&content, format)?;
+        document.path = Some(path.to_path_buf());
+        Ok(document)
+    }
+
+    /// Parse text that is not (yet) a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text cannot be parsed, or the format's sections cannot be
+    /// rewritten as headings.
+    pub fn parse(content: &str, format: Arc<dyn Format>) -> io::Result<Self> {
+        if !format.supports_reorder() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} sections cannot be edited as a document", format.name()),
+            ));
+        }
+        let mut document = Self {
+            path: None,
+            format,
+            content: String::new(),
+            sections: Vec::new(),
+        };
+        document.set_content(content.to_string())?;
+        Ok(document)
+    }
+
+    /// Write the document back to the file it was opened from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document was not opened from a file or cannot be written.
+    pub fn save(&self) -> io::Result<()> {
+        let path = self.path.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Document was not opened from a file",
+            )
+        })?;
+        atomic::write_file(path, self.content.as_bytes())
+    }
+
+    /// Sections in document order, each with the [`section::SectionId`] it is addressed by.
+    #[must_use]
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The first section reached through these titles, outermost first.
+    #[must_use]
+    pub fn find(&self, path: &[&str]) -> Option<&Section> {
+        self.position(path).map(|i| &self.sections[i])
+    }
+
+    /// Body of a section without the blank lines around it.
+    ///
+    /// The body runs from the end of the heading to the next heading of any level, so it does not
+    /// include subsections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no section has this path.
+    pub fn get_body(&self, path: &[&str]) -> io::Result<&str> {
+        let section = &self.sections[self.index(path)?];
+        Ok(self.content[section.byte_start..section.byte_end].trim_matches(['\n', '\r']))
+    }
+
+    /// Replace the body of a section, keeping its heading and subsections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no section has this path or the new text does not parse.
+    pub fn set_body(&mut self, path: &[&str], body: &str) -> io::Result<()> {
+        let i = self.index(path)?;
+        let (preamble, mut parts) = self.parts();
+        parts[i].body = padded(body);
+        if i + 1 == parts.len() {
+            end_file(&mut parts[i].body);
+        }
+        self.rebuild(&preamble, &parts)
+    }
+
+    /// Add a section after the existing subsections of `parent`, or at the end of the document
+    /// when `parent` is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent is missing, the title is not a single line, or the section
+    /// would be nested deeper than headings allow.
+    pub fn insert_child(&mut self, parent: &[&str], title: &str, body: &str) -> io::Result<()> {
+        check_title(title)?;
+        let (mut preamble, mut parts) = self.parts();
+        let (level, at) = if parent.is_empty() {
+            (1, parts.len())
+        } else {
+            let i = self.index(parent)?;
+            (parts[i].level + 1, subtree_end(&parts, i))
+        };
+        check_level(level)?;
+
+        match at.checked_sub(1) {
+            Some(before) => separate(&mut parts[before].body),
+            None => separate(&mut preamble),
+        }
+        let mut part = Part {
+            level,
+            title: title.to_string(),
+            heading: heading_line(level, title),
+            body: padded(body),
+        };
+        if at == parts.len() {
+            end_file(&mut part.body);
+        }
+        parts.insert(at, part);
+        self.rebuild(&preamble, &parts)
+    }
+
+    /// Move a section with its subsections to the end of `new_parent`'s subsections, or to the end
+    /// of the document when `new_parent` is empty, changing heading levels to suit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either section is missing, the new parent lies within the moved
+    /// subtree, or the move would nest headings deeper than allowed.
+    pub fn move_subtree(&mut self, path: &[&str], new_parent: &[&str]) -> io::Result<()> {
+        let i = self.index(path)?;
+        let target = if new_parent.is_empty() {
+            None
+        } else {
+            Some(self.index(new_parent)?)
+        };
+        let (mut preamble, mut parts) = self.parts();
+        let end = subtree_end(&parts, i);
+        if target.is_some_and(|t| (i..end).contains(&t)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot move a section into itself",
+            ));
+        }
+
+        let root = parts[i].level;
+        let level = target.map_or(1, |t| parts[t].level + 1);
+        let deepest = parts[i..end]
+            .iter()
+            .map(|part| part.level)
+            .max()
+            .unwrap_or(root);
+        check_level(deepest - root + level)?;
+
+        let mut moved: Vec<Part> = parts.drain(i..end).collect();
+        if level != root {
+            for part in &mut moved {
+                part.level = part.level - root + level;
+                part.heading = relevel(part);
+            }
+        }
+
+        let at = match target {
+            None => parts.len(),
+            Some(t) if t >= end => subtree_end(&parts, t - (end - i)),
+            Some(t) => subtree_end(&parts, t),
+        };
+        match at.checked_sub(1) {
+            Some(before) => separate(&mut parts[before].body),
+            None => separate(&mut preamble),
+        }
+        if let Some(last) = moved.last_mut() {
+            if at < parts.len() {
+                separate(&mut last.body);
+            } else {
+                end_file(&mut last.body);
+            }
+        }
+        parts.splice(at..at, moved);
+        self.rebuild(&preamble, &parts)
+    }
+
+    /// Change the title of a section, keeping the rest of its heading as written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no section has this path or the title is not a single line.
+    pub fn rename(&mut self, path: &[&str], title: &str) -> io::Result<()> {
+        check_title(title)?;
+        let i = self.index(path)?;
+        let (preamble, mut parts) = self.parts();
+        let part = &mut parts[i];
+        part.heading = if part.title.is_empty() || !part.heading.contains(&part.title) {
+            heading_line(part.level, title)
+        } else {
+            part.heading.replacen(&part.title, title, 1)
+        };
+        self.rebuild(&preamble, &parts)
+    }
+
+    fn position(&self, path: &[&str]) -> Option<usize> {
+        if path.is_empty() {
+            return None;
+        }
+        self.sections.iter().position(|section| {
+            section
+                .id
+                .titles
+                .iter()
+                .map(String::as_str)
+                .eq(path.iter().copied())
+        })
+    }
+
+    fn index(&self, path: &[&str]) -> io::Result<usize> {
+        self.position(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No section at '{}'", path.join(" > ")),
+            )
+        })
+    }
+
+    /// Split the text into what precedes the first heading and one part per section.
+    fn parts(&self) -> (String, Vec<Part>) {
+        // Each section's body ends where the next heading starts
+        let first = self.sections.first().map_or(self.content.len(), |section| {
+            let heading = self.content[..section.byte_start].trim_end_matches(['\n', '\r']);
+            heading.rfind('\n').map_or(0, |newline| newline + 1)
+        });
+        let starts = std::iter::once(first).chain(self.sections.iter().map(|s| s.byte_end));
+        let parts = self
+            .sections
+            .iter()
+            .zip(starts)
+            .map(|(section, start)| Part {
+                level: section.level,
+                title: section.title.clone(),
+                heading: self.content[start..section.byte_start].to_string(),
+                body: self.content[section.byte_start..section.byte_end].to_string(),
+            })
+            .collect();
+        (self.content[..first].to_string(), parts)
+    }
+
+    fn rebuild(&mut self, preamble: &str, parts: &[Part]) -> io::Result<()> {
+        let mut content = preamble.to_string();
+        for part in parts {
+            content.push_str(&part.heading);
+            content.push_str(&part.body);
+        }
+        self.set_content(content)
+    }
+
+    fn set_content(&mut self, content: String) -> io::Result<()> {
+        let source = self.path.as_deref().unwrap_or(Path::new("-"));
+        let mut sections = self.format.parse(&content, source)?;
+        section::assign_ids(&mut sections);
+        self.sections = sections;
+        self.content = content;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.content)
+    }
+}
+
+/// Index just past the subsections of the part at `i`.
+fn subtree_end(parts: &[Part], i: usize) -> usize {
+    parts[i + 1..]
+        .iter()
+        .position(|part| part.level <= parts[i].level)
+        .map_or(parts.len(), |offset| i + 1 + offset)
+}
+
+/// A body set off by blank lines, as the editor writes sections.
+fn padded(body: &str) -> String {
+    let body = body.trim_matches(['\n', '\r']);
+    if body.is_empty() {
+        "\n".to_string()
+    } else {
+        format!("\n{body}\n\n")
+    }
+}
+
+/// End text with a blank line so a heading can follow it.
+fn separate(text: &mut String) {
+    if text.is_empty() || text.ends_with("\n\n") {
+        return;
+    }
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push('\n');
+}
+
+/// Leave a single newline at the end of the document's last body.
+fn end_file(body: &mut String) {
+    body.truncate(body.trim_end_matches('\n').len());
+    if !body.is_empty() {
+        body.push('\n');
+    }
+}
+
+fn heading_line(level: usize, title: &str) -> String {
+    format!("{} {title}\n", "#".repeat(level))
+}
+
+/// The part's heading at its new level, keeping whatever follows the `#` markers.
+fn relevel(part: &Part) -> String {
+    match part.heading.strip_prefix('#') {
+        Some(rest) => format!("{}{}", "#".repeat(part.level), rest.trim_start_matches('#')),
+        None => heading_line(part.level, &part.title),
+    }
+}
+
+fn check_title(title: &str) -> io::Result<()> {
+    if title.trim().is_empty() || title.contains(['\n', '\r']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Section titles must be a single non-empty line",
+        ));
+    }
+    Ok(())
+}
+
+fn check_level(level: usize) -> io::Result<()> {
+    if level > MAX_LEVEL {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Headings nest at most {MAX_LEVEL} levels deep"),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/document.rs"]
+mod tests;
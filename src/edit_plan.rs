@@ -3,9 +3,12 @@
 //! This module defines the transformation that work in the TUI manifests as actual edits on disk.
 //! asterism uses textum for generic line-based patching that works with any text format.
 
+use crate::atomic::{self, WriteOptions};
+use crate::section::SectionId;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io;
+use std::path::PathBuf;
 use textum::{Boundary, BoundaryMode, Patch, PatchSet, Snippet, Target};
 
 /// Serialisable collection of file modifications for atomic application.
@@ -32,6 +35,12 @@ pub struct Edit {
     pub section_content: String,
     /// Section title for tracking and debugging edits.
     pub item_name: String,
+    /// Replace the lines exactly as given, without the blank lines padding section bodies.
+    #[serde(default)]
+    pub verbatim: bool,
+    /// Identity of the replaced section, letting a saved plan find it again after the file moves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section_id: Option<SectionId>,
 }
 
 impl EditPlan {
@@ -45,7 +54,17 @@ impl EditPlan {
     ///
     /// Returns an error if file operations, patching, or line number conversion fails.
     pub fn apply(&mut self) -> io::Result<()> {
-        let mut file_groups: HashMap<String, Vec<&Edit>> = HashMap::new();
+        self.apply_with(WriteOptions::default())
+    }
+
+    /// Apply all edits, writing every patched file or, if any write fails, none of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if file operations, patching, or line number conversion fails; files
+    /// already written are restored first.
+    pub fn apply_with(&mut self, options: WriteOptions) -> io::Result<()> {
+        let mut file_groups: BTreeMap<String, Vec<&Edit>> = BTreeMap::new();
 
         for edit in &self.edits {
             file_groups
@@ -54,6 +73,8 @@ impl EditPlan {
                 .push(edit);
         }
 
+        // Every file is patched in memory before any is written
+        let mut patched = Vec::new();
         for (file_name, edits) in file_groups {
             let mut patchset = PatchSet::new();
 
@@ -69,7 +90,14 @@ impl EditPlan {
                 let end = Boundary::new(Target::Line(line_end), BoundaryMode::Exclude);
                 let snippet = Snippet::Between { start, end };
 
-                let replacement = format!("\n{}\n\n", edit.section_content.trim());
+                let replacement = if edit.verbatim {
+                    edit.section_content
+                        .lines()
+                        .flat_map(|line| [line, "\n"])
+                        .collect()
+                } else {
+                    format!("\n{}\n\n", edit.section_content.trim())
+                };
 
                 let patch = Patch {
                     file: file_name.clone(),
@@ -85,11 +113,11 @@ impl EditPlan {
                 .map_err(|e| io::Error::other(e.to_string()))?;
 
             if let Some(new_content) = results.get(&file_name) {
-                std::fs::write(&file_name, new_content)?;
+                patched.push((PathBuf::from(&file_name), new_content.clone()));
             }
         }
 
-        Ok(())
+        atomic::write_files(&patched, options)
     }
 }
 
@@ -4,22 +4,140 @@
 //! document formats (markdown, org-mode, restructuredtext, etc.) by providing
 //! tree-sitter queries specific to each format.
 
+pub mod conflict;
 pub mod difftastic;
+pub mod jupyter;
 pub mod markdown;
+pub mod query;
+pub mod registry;
+pub mod unified;
+
+use crate::input;
+use crate::section::Section;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use tree_sitter::Query;
 
 /// Abstracts document type differences through tree-sitter queries.
 ///
 /// Enables support for markdown and other structured formats by providing format-specific parsing
 /// queries (tree-sitter uses SCM lisp queries).
-pub trait Format {
+pub trait Format: Send + Sync {
+    /// Registry key used by `--format` and the `[formats]` table in asterism.toml.
+    fn name(&self) -> &str;
     /// File extension for syntax highlighting (e.g., "md", "rs")
-    fn file_extension(&self) -> &'static str;
+    fn file_extension(&self) -> &str;
+    /// File suffixes claimed by this format when no override applies.
+    ///
+    /// Formats recognised only by content (such as JSON diff output) claim none, leaving generic
+    /// suffixes free for other formats.
+    fn extensions(&self) -> Vec<String> {
+        vec![self.file_extension().to_string()]
+    }
     /// Returns the tree-sitter language parser for this format.
     fn language(&self) -> tree_sitter::Language;
     /// Tree-sitter query matching section boundaries in this format.
     fn section_query(&self) -> &str;
     /// Tree-sitter query extracting section titles in this format.
     fn title_query(&self) -> &str;
+    /// Section and title queries already compiled, for formats that compile them up front.
+    ///
+    /// The default `None` has them compiled on first use and cached for the process.
+    fn compiled_queries(&self) -> Option<(Arc<Query>, Arc<Query>)> {
+        None
+    }
+    /// Capture in the section query whose node determines the heading level.
+    fn level_capture(&self) -> Option<&str> {
+        None
+    }
+    /// Nesting depth of a matched heading (1 for top-level).
+    ///
+    /// The default reads markdown-style numbered markers, so formats whose grammar names its
+    /// heading markers `atx_hN_marker` need no override.
+    fn heading_level(
+        &self,
+        heading: tree_sitter::Node,
+        _level_node: Option<tree_sitter::Node>,
+        _source: &str,
+    ) -> usize {
+        input::marker_level(heading)
+    }
+    #[cfg(feature = "tui")]
     /// Format a section heading for display with syntax highlighting
     fn format_section_display(&self, level: usize, title: &str) -> ratatui::text::Line<'static>;
+    /// Recognises this format from the start of a file when its extension is ambiguous.
+    fn sniff(&self, _head: &str) -> bool {
+        false
+    }
+    /// Whether sniffing picks this format ahead of the markdown fallback for extensions no format
+    /// claims, for content that can turn up in any kind of file.
+    fn sniff_first(&self) -> bool {
+        false
+    }
+    /// Diff formats group hunks under per-file nodes instead of nesting them by heading level.
+    fn is_diff(&self) -> bool {
+        false
+    }
+    /// Parse document content into sections attributed to `file_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content cannot be parsed in this format.
+    fn parse(&self, content: &str, file_path: &Path) -> io::Result<Vec<Section>> {
+        input::parse_sections(content, file_path, self)
+    }
+    /// Section body as presented in the editor, taken from the whole file content.
+    fn section_body(&self, content: &str, section: &Section) -> String {
+        let bytes = content.as_bytes();
+        let start = section.byte_start.min(bytes.len());
+        let end = section.byte_end.min(bytes.len()).max(start);
+        String::from_utf8_lossy(&bytes[start..end]).to_string()
+    }
+    /// Whole-file content with one section body replaced, for formats whose sections do not map
+    /// onto line ranges of the file.
+    ///
+    /// The default `None` saves through the line-based `EditPlan` instead.
+    fn splice_section(
+        &self,
+        _content: &str,
+        _section: &Section,
+        _body: &str,
+    ) -> Option<io::Result<String>> {
+        None
+    }
+    /// Whether edited sections replace their lines exactly as written, rather than as heading
+    /// bodies padded with blank lines.
+    fn verbatim_edits(&self) -> bool {
+        false
+    }
+    /// Whether sections can be reordered and re-levelled from the outline.
+    ///
+    /// Reordering rewrites files with markdown headings, which would corrupt formats that store
+    /// sections any other way.
+    fn supports_reorder(&self) -> bool {
+        true
+    }
+    /// Whether section bodies are prose that reflowing may hard-wrap.
+    ///
+    /// Diffs, conflicts and notebook cells hold code, where line breaks carry meaning.
+    fn reflows_prose(&self) -> bool {
+        true
+    }
+    /// Whether sections come from the default tree-sitter parse, so a file's syntax tree can be
+    /// kept and reparsed incrementally after each save.
+    ///
+    /// Formats that override [`Format::parse`] build sections some other way and return false.
+    fn parses_tree(&self) -> bool {
+        true
+    }
+    /// Read a file from disk and parse it into sections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    fn extract_sections(&self, file_path: &Path) -> io::Result<Vec<Section>> {
+        let content = std::fs::read_to_string(file_path)?;
+        self.parse(&content, file_path)
+    }
 }
@@ -0,0 +1,300 @@
+//! Merge conflict format, navigating the `<<<<<<<`/`=======`/`>>>>>>>` regions git leaves behind.
+//!
+//! Each conflict becomes a section spanning its markers, with its ours, base (in diff3 style) and
+//! theirs sides nested beneath. Sections are addressed by file lines, so resolving a conflict
+//! replaces its lines through the edit plan like any other verbatim edit.
+
+use crate::formats::Format;
+use crate::input;
+use crate::section::{Section, SectionId};
+#[cfg(feature = "tui")]
+use crate::theme;
+#[cfg(feature = "tui")]
+use ratatui::text::{Line, Span};
+use std::io;
+use std::path::Path;
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SPLIT_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// Parses files containing merge conflicts into one section per conflict.
+pub struct ConflictFormat;
+
+/// Which lines a conflict is resolved to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the current branch's side.
+    Ours,
+    /// Keep the merged branch's side.
+    Theirs,
+    /// Keep both sides, ours first.
+    Both,
+    /// Keep the common ancestor, available in diff3-style conflicts.
+    Base,
+}
+
+impl Resolution {
+    /// Short name shown against resolved conflicts.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ours => "ours",
+            Self::Theirs => "theirs",
+            Self::Both => "both",
+            Self::Base => "base",
+        }
+    }
+}
+
+impl Format for ConflictFormat {
+    fn name(&self) -> &'static str {
+        "conflict"
+    }
+
+    fn file_extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        // Conflicts occur in any kind of file, so they are recognised by their markers
+        Vec::new()
+    }
+
+    fn language(&self) -> tree_sitter::Language {
+        // Conflict markers are parsed line by line rather than with tree-sitter
+        tree_sitter_md::LANGUAGE.into()
+    }
+
+    fn section_query(&self) -> &'static str {
+        ""
+    }
+
+    fn title_query(&self) -> &'static str {
+        ""
+    }
+
+    #[cfg(feature = "tui")]
+    fn format_section_display(&self, level: usize, title: &str) -> Line<'static> {
+        let theme = theme::current();
+        let color = if level <= 1 {
+            theme.removed
+        } else if title.starts_with("Ours") {
+            theme.added
+        } else if title.starts_with("Theirs") {
+            theme.accent
+        } else {
+            theme.dimmed
+        };
+        Line::from(vec![
+            Span::styled("● ", theme.fg(color)),
+            Span::raw(title.to_string()),
+        ])
+    }
+
+    fn sniff(&self, head: &str) -> bool {
+        let has_marker = |marker: &str| {
+            head.lines()
+                .any(|line| marker_label(line, marker).is_some())
+        };
+        has_marker(OURS_MARKER) && has_marker(SPLIT_MARKER)
+    }
+
+    fn sniff_first(&self) -> bool {
+        true
+    }
+
+    fn supports_reorder(&self) -> bool {
+        false
+    }
+
+    fn reflows_prose(&self) -> bool {
+        false
+    }
+
+    fn parses_tree(&self) -> bool {
+        false
+    }
+
+    fn verbatim_edits(&self) -> bool {
+        true
+    }
+
+    fn parse(&self, content: &str, file_path: &Path) -> io::Result<Vec<Section>> {
+        parse_conflicts(content, &file_path.to_string_lossy())
+    }
+
+    fn splice_section(
+        &self,
+        content: &str,
+        section: &Section,
+        body: &str,
+    ) -> Option<io::Result<String>> {
+        let start = section.byte_start.min(content.len());
+        let end = section.byte_end.min(content.len()).max(start);
+        // The editor opens sides padded with a blank line either end
+        let body = body.trim_matches('\n');
+        let mut spliced = content[..start].to_string();
+        if !body.is_empty() {
+            spliced.push_str(body);
+            spliced.push('\n');
+        }
+        spliced.push_str(&content[end..]);
+        Some(Ok(spliced))
+    }
+}
+
+/// Label following a conflict marker, when `line` is that marker.
+fn marker_label<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.trim_end_matches(['\n', '\r']).strip_prefix(marker)?;
+    (rest.is_empty() || rest.starts_with(' ')).then(|| rest.trim())
+}
+
+/// One side of a conflict while its lines are being read.
+struct Side {
+    name: &'static str,
+    label: String,
+    line_start: usize,
+    byte_start: usize,
+}
+
+/// Parse the conflicts in a file into sections, each with its sides as subsections.
+///
+/// # Errors
+///
+/// Returns an error if a conflict is not closed by a `>>>>>>>` marker.
+pub fn parse_conflicts(content: &str, file_path: &str) -> io::Result<Vec<Section>> {
+    let mut sections = Vec::new();
+    // Index of the open conflict's section and the side being read
+    let mut open: Option<(usize, Side)> = None;
+    let mut byte = 0;
+
+    let section = |title: String, level, lines: (usize, usize), bytes: (usize, usize)| Section {
+        title,
+        level,
+        line_start: i64::try_from(lines.0).unwrap_or(0),
+        line_end: i64::try_from(lines.1).unwrap_or(0),
+        column_start: 0,
+        column_end: 0,
+        byte_start: bytes.0,
+        byte_end: bytes.1,
+        file_path: file_path.to_string(),
+        id: SectionId::default(),
+        parent_index: None,
+        children_indices: Vec::new(),
+        section_content: None,
+        hunk: None,
+    };
+    let side_title = |side: &Side| {
+        if side.label.is_empty() {
+            side.name.to_string()
+        } else {
+            format!("{} ({})", side.name, side.label)
+        }
+    };
+
+    for (number, line) in content.split_inclusive('\n').enumerate() {
+        let next_byte = byte + line.len();
+        let next_side = |name, label: &str| Side {
+            name,
+            label: label.to_string(),
+            line_start: number + 1,
+            byte_start: next_byte,
+        };
+
+        match open.take() {
+            None => {
+                if let Some(label) = marker_label(line, OURS_MARKER) {
+                    let conflicts = sections.iter().filter(|s: &&Section| s.level == 1).count();
+                    sections.push(section(
+                        format!("Conflict {} (line {})", conflicts + 1, number + 1),
+                        1,
+                        (number, number),
+                        (byte, byte),
+                    ));
+                    open = Some((sections.len() - 1, next_side("Ours", label)));
+                }
+            }
+            Some((conflict, side)) => {
+                let base = marker_label(line, BASE_MARKER).map(|label| ("Base", label));
+                let split = marker_label(line, SPLIT_MARKER).map(|_| ("Theirs", ""));
+                let end = marker_label(line, THEIRS_MARKER);
+
+                match (base.or(split), end) {
+                    (Some((name, label)), _) if side.name != "Theirs" => {
+                        sections.push(section(
+                            side_title(&side),
+                            2,
+                            (side.line_start, number),
+                            (side.byte_start, byte),
+                        ));
+                        open = Some((conflict, next_side(name, label)));
+                    }
+                    (_, Some(label)) if side.name == "Theirs" => {
+                        let theirs = Side {
+                            label: label.to_string(),
+                            ..side
+                        };
+                        sections.push(section(
+                            side_title(&theirs),
+                            2,
+                            (theirs.line_start, number),
+                            (theirs.byte_start, byte),
+                        ));
+                        sections[conflict].line_end = i64::try_from(number + 1).unwrap_or(0);
+                        sections[conflict].byte_end = next_byte;
+                    }
+                    _ => open = Some((conflict, side)),
+                }
+            }
+        }
+        byte = next_byte;
+    }
+
+    if let Some((conflict, _)) = open {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Unterminated merge conflict starting at line {}",
+                sections[conflict].line_start + 1
+            ),
+        ));
+    }
+
+    input::build_hierarchy(&mut sections);
+    Ok(sections)
+}
+
+/// Lines a conflict resolves to, given the lines of the conflict including its markers.
+///
+/// Returns `None` for [`Resolution::Base`] when the conflict does not record its base.
+#[must_use]
+pub fn resolve(conflict: &[&str], resolution: Resolution) -> Option<Vec<String>> {
+    let (mut ours, mut base, mut theirs) = (Vec::new(), None, Vec::new());
+    let mut current = &mut ours;
+    for line in conflict {
+        if marker_label(line, OURS_MARKER).is_some() || marker_label(line, THEIRS_MARKER).is_some()
+        {
+            continue;
+        }
+        if marker_label(line, BASE_MARKER).is_some() {
+            current = base.insert(Vec::new());
+        } else if marker_label(line, SPLIT_MARKER).is_some() {
+            current = &mut theirs;
+        } else {
+            current.push((*line).to_string());
+        }
+    }
+
+    match resolution {
+        Resolution::Ours => Some(ours),
+        Resolution::Theirs => Some(theirs),
+        Resolution::Both => Some([ours, theirs].concat()),
+        Resolution::Base => base,
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/conflict.rs"]
+mod tests;
@@ -4,19 +4,21 @@
 //! converting it into sections that can be navigated and edited in asterism.
 
 use crate::formats::Format;
-use crate::section::{ChunkType, Section};
+use crate::section::{ChunkType, Section, SectionId};
+#[cfg(feature = "tui")]
+use crate::theme;
+#[cfg(feature = "tui")]
 use ratatui::{
-    style::{Color, Style},
+    style::Color,
     text::{Line, Span},
 };
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::fmt::Write;
+use std::io;
 use std::path::Path;
-use std::{fs, io};
+use std::str::FromStr;
 
 /// Represents a file in difftastic output
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DifftFile {
     /// Programming language identified by difftastic for syntax highlighting.
     pub language: String,
@@ -30,7 +32,7 @@ pub struct DifftFile {
 }
 
 /// Represents a line in a diff chunk
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DifftLine {
     /// Left-hand (original) side of the comparison, absent for pure additions.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -41,16 +43,16 @@ pub struct DifftLine {
 }
 
 /// Represents one side (left or right) of a diff line
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DifftSide {
-    /// Original line position in the source file (1-indexed).
+    /// Line position in the source file (0-indexed, as difftastic reports it).
     pub line_number: u32,
     /// Structural changes within this line, ordered by column position.
     pub changes: Vec<DifftChange>,
 }
 
 /// Represents a change within a line
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DifftChange {
     /// Column offset where this change begins (0-indexed).
     pub start: u32,
@@ -67,10 +69,19 @@ pub struct DifftChange {
 pub struct DifftasticFormat;
 
 impl Format for DifftasticFormat {
+    fn name(&self) -> &'static str {
+        "difftastic"
+    }
+
     fn file_extension(&self) -> &'static str {
         "diff"
     }
 
+    fn extensions(&self) -> Vec<String> {
+        // Difftastic writes plain `.json`, so it is recognised by content rather than suffix
+        Vec::new()
+    }
+
     fn language(&self) -> tree_sitter::Language {
         // Difftastic doesn't use tree-sitter parsing
         tree_sitter_md::LANGUAGE.into()
@@ -84,112 +95,68 @@ impl Format for DifftasticFormat {
         ""
     }
 
+    #[cfg(feature = "tui")]
     fn format_section_display(&self, level: usize, title: &str) -> Line<'static> {
-        // Check if this is a hunk header with format: (N) @@ -X,Y +A,B @@
-        if title.contains("@@") && title.starts_with('(') {
-            if let Some(close_paren) = title.find(')') {
-                let hunk_num = &title[..=close_paren];
-                let rest = &title[close_paren + 1..].trim();
-
-                // Determine color based on the diff header
-                let color = Self::determine_hunk_color_from_header(rest);
-
-                let spans = vec![
-                    Span::styled(hunk_num.to_string(), Style::default().fg(color)),
-                    Span::raw(" "),
-                    Span::raw((*rest).to_string()),
-                ];
-
-                return Line::from(spans);
-            }
+        let theme = theme::current();
+        // Hunk titles read "Hunk N @@ -X,Y +A,B @@" (or "File created @@ ... @@")
+        if let Some(at) = title.find("@@") {
+            let label = title[..at].trim();
+            let header = &title[at..];
+
+            let color = header
+                .parse::<HunkRange>()
+                .map_or(theme.modified, |range| chunk_color(range.chunk_type()));
+
+            let spans = vec![
+                Span::styled(label.to_string(), theme.fg(color)),
+                Span::raw(" "),
+                Span::raw(header.to_string()),
+            ];
+
+            return Line::from(spans);
         }
 
         // For file nodes or other sections
         let color = if level == 0 {
-            Color::Cyan // Files
+            theme.accent // Files
         } else {
-            Color::LightYellow // Hunks
+            theme.modified // Hunks
         };
 
         let spans = vec![
-            Span::styled("● ", Style::default().fg(color)),
+            Span::styled("● ", theme.fg(color)),
             Span::raw(title.to_string()),
         ];
 
         Line::from(spans)
     }
-}
 
-impl DifftasticFormat {
-    /// Determine hunk color from the header string itself
-    fn determine_hunk_color_from_header(header: &str) -> Color {
-        // Parse @@ -X,Y +A,B @@
-        if let Some(hunk_part) = header.strip_prefix("@@").and_then(|s| s.split("@@").next()) {
-            let parts: Vec<&str> = hunk_part.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let lhs = parts[0].trim_start_matches('-');
-                let rhs = parts[1].trim_start_matches('+');
-
-                let lhs_count = lhs
-                    .split(',')
-                    .nth(1)
-                    .and_then(|s| s.parse::<u32>().ok())
-                    .unwrap_or(1);
-                let rhs_count = rhs
-                    .split(',')
-                    .nth(1)
-                    .and_then(|s| s.parse::<u32>().ok())
-                    .unwrap_or(1);
-
-                // Parse the line numbers too to detect -0,0 +1,N
-                let lhs_start = lhs
-                    .split(',')
-                    .next()
-                    .and_then(|s| s.parse::<u32>().ok())
-                    .unwrap_or(0);
-
-                if lhs_start == 0 && lhs_count == 0 && rhs_count > 0 {
-                    return Color::Green; // Addition
-                } else if lhs_count > 0 && rhs_count == 0 && rhs.starts_with("0,") {
-                    return Color::Red; // Deletion
-                } else if lhs_count == 0 && rhs_count > 0 {
-                    return Color::Green; // Pure addition
-                } else if lhs_count > 0 && rhs_count == 0 {
-                    return Color::Red; // Pure deletion
-                }
-            }
-        }
-        Color::LightYellow // Modification
+    fn sniff(&self, head: &str) -> bool {
+        // Chunk arrays precede the path and status keys, so any one of them marks the output
+        let head = head.trim_start();
+        (head.starts_with('{') || head.starts_with('['))
+            && (head.contains("\"chunks\"")
+                || (head.contains("\"path\"") && head.contains("\"status\"")))
     }
-}
 
-#[allow(clippy::too_many_arguments)]
-fn create_chunk_section(
-    file_path: &str,
-    title: String,
-    line_num: i64,
-    column_start: i64,
-    column_end: i64,
-    chunk_type: ChunkType,
-    lhs_text: Option<String>,
-    rhs_text: Option<String>,
-) -> Section {
-    Section {
-        title,
-        level: 2,
-        line_start: line_num,
-        line_end: line_num + 1,
-        column_start,
-        column_end,
-        byte_start: 0,
-        byte_end: 0,
-        file_path: file_path.to_string(),
-        parent_index: None,
-        children_indices: Vec::new(),
-        section_content: None,
-        chunk_type: Some(chunk_type),
-        lhs_content: lhs_text,
-        rhs_content: rhs_text,
+    fn is_diff(&self) -> bool {
+        true
+    }
+
+    fn supports_reorder(&self) -> bool {
+        false
+    }
+
+    fn reflows_prose(&self) -> bool {
+        false
+    }
+
+    fn parses_tree(&self) -> bool {
+        false
+    }
+
+    fn parse(&self, content: &str, _file_path: &Path) -> io::Result<Vec<Section>> {
+        parse_difftastic_json(content)
     }
 }
 
@@ -228,7 +195,6 @@ pub fn parse_difftastic_json(json_str: &str) -> io::Result<Vec<Section>> {
     };
 
     let mut sections = Vec::new();
-    let mut global_line = 0i64;
 
     for file in &files {
         // Skip unchanged files
@@ -240,248 +206,323 @@ pub fn parse_difftastic_json(json_str: &str) -> io::Result<Vec<Section>> {
 
         // Create hunk sections directly (no file section)
         if let Some(chunks) = &file.chunks {
-            let mut hunk_counter = 0;
-            for chunk in chunks {
-                // Each element in the chunk array is a separate hunk
-                for change in chunk {
-                    hunk_counter += 1;
-
-                    // Format this individual change as a proper git diff hunk
-                    let hunk_title = format_hunk_header(change, hunk_counter);
-                    let hunk_content = format_change_content(change);
-
-                    let hunk_start_line = global_line;
-                    let hunk_end_line =
-                        global_line + i64::try_from(hunk_content.lines().count()).unwrap_or(0);
-
-                    // Create section for this hunk
-                    sections.push(Section {
-                        title: hunk_title,
-                        level: 1,
-                        line_start: hunk_start_line,
-                        line_end: hunk_end_line,
-                        column_start: 0,
-                        column_end: 0,
-                        byte_start: 0,
-                        byte_end: 0,
-                        file_path: file_path.clone(),
-                        parent_index: None,
-                        children_indices: Vec::new(),
-                        section_content: Some(vec![hunk_content]),
-                        chunk_type: None,
-                        lhs_content: None,
-                        rhs_content: None,
-                    });
-
-                    global_line = hunk_end_line + 1;
-                }
+            // RHS minus LHS line position, carried between chunks to anchor one-sided hunks
+            let mut offset = 0i64;
+            for (i, chunk) in chunks.iter().enumerate() {
+                let range = HunkRange::from_chunk(chunk, &mut offset);
+
+                sections.push(hunk_section(
+                    file_path,
+                    format!("Hunk {} {range}", i + 1),
+                    Hunk {
+                        range,
+                        lines: chunk.clone(),
+                        language: Some(file.language.clone()),
+                        context: Vec::new(),
+                    },
+                ));
             }
-        } else if file.status == "created" || file.status == "deleted" {
-            // For files with no chunks (created/deleted without detailed hunks),
-            // create a proper hunk header
-
-            // Try to read the file to get line count
-            let line_count = if file.status == "created" {
-                // For created files, try to read from filesystem
-                std::fs::read_to_string(file_path)
-                    .ok()
-                    .map_or(0, |content| content.lines().count())
-            } else {
-                0 // Deleted files
-            };
-
-            let hunk_title = if file.status == "created" {
-                format!("(1) @@ -0,0 +1,{line_count} @@")
-            } else {
-                format!("(1) @@ -1,{line_count} +0,0 @@")
-            };
-
-            let hunk_content = if file.status == "created" {
-                std::fs::read_to_string(file_path)
-                    .ok()
-                    .unwrap_or_else(|| format!("File was {}", file.status))
-            } else {
-                format!("File was {}", file.status)
-            };
-
-            sections.push(Section {
-                title: hunk_title,
-                level: 1,
-                line_start: global_line,
-                line_end: global_line + i64::try_from(line_count).unwrap_or(0),
-                column_start: 0,
-                column_end: 0,
-                byte_start: 0,
-                byte_end: 0,
-                file_path: file_path.clone(),
-                parent_index: None,
-                children_indices: Vec::new(),
-                section_content: Some(hunk_content.lines().map(String::from).collect()),
-                chunk_type: None,
-                lhs_content: None,
-                rhs_content: None,
-            });
-
-            global_line += i64::try_from(line_count).unwrap_or(0) + 1;
+        } else if file.status == "created" {
+            // Without chunks the whole file is the addition, its length filled in from the RHS
+            sections.push(whole_file_section(
+                file_path,
+                ChunkType::Added,
+                0,
+                Some(file.language.clone()),
+            ));
+        } else if file.status == "deleted" {
+            // The whole original is the removal, its length filled in from the LHS when given
+            sections.push(whole_file_section(
+                file_path,
+                ChunkType::Deleted,
+                0,
+                Some(file.language.clone()),
+            ));
         }
     }
 
     Ok(sections)
 }
 
-/// Format a change as a proper git diff hunk header
-fn format_hunk_header(change: &DifftLine, hunk_num: usize) -> String {
-    let (lhs_line, rhs_line) = match (&change.lhs, &change.rhs) {
-        (Some(lhs), Some(rhs)) => (lhs.line_number, rhs.line_number),
-        (Some(lhs), None) => (lhs.line_number, 0),
-        (None, Some(rhs)) => (0, rhs.line_number),
-        _ => (0, 0),
+/// Section for one hunk, classified by its range and carrying each side's changed text.
+///
+/// Both diff parsers build hunks through this, so the tree, diff view and file status all read
+/// the same fields.
+#[must_use]
+pub fn hunk_section(file_path: &str, title: String, hunk: Hunk) -> Section {
+    let range = hunk.range;
+    Section {
+        title,
+        level: 1,
+        line_start: range.rhs_line_start(),
+        line_end: range.rhs_line_start() + i64::from(range.rhs_count),
+        column_start: 0,
+        column_end: 0,
+        byte_start: 0,
+        byte_end: 0,
+        file_path: file_path.to_string(),
+        id: SectionId::default(),
+        parent_index: None,
+        children_indices: Vec::new(),
+        section_content: None,
+        hunk: Some(Box::new(hunk)),
+    }
+}
+
+/// Section for a file difftastic reports as created or deleted, which come without chunks.
+///
+/// The hunk spans all `lines` lines of the side the file exists on. Difftastic does not report
+/// that length, so the parser passes 0 and [`size_whole_file`] fills it in from the compared text.
+#[must_use]
+pub fn whole_file_section(
+    file_path: &str,
+    kind: ChunkType,
+    lines: u32,
+    language: Option<String>,
+) -> Section {
+    let (range, label) = if kind == ChunkType::Deleted {
+        let range = HunkRange {
+            lhs_start: 1,
+            lhs_count: lines,
+            rhs_start: 0,
+            rhs_count: 0,
+        };
+        (range, "File deleted")
+    } else {
+        let range = HunkRange {
+            lhs_start: 0,
+            lhs_count: 0,
+            rhs_start: 1,
+            rhs_count: lines,
+        };
+        (range, "File created")
     };
+    hunk_section(
+        file_path,
+        format!("{label} {range}"),
+        Hunk {
+            range,
+            lines: Vec::new(),
+            language,
+            context: Vec::new(),
+        },
+    )
+}
 
-    // Determine chunk size (for now, single line changes)
-    let lhs_count = i32::from(change.lhs.is_some());
-    let rhs_count = i32::from(change.rhs.is_some());
+/// Give a created or deleted file's hunk the length of `text`, the side the file exists on.
+pub fn size_whole_file(section: &mut Section, text: &str) {
+    let Some(hunk) = section.hunk.as_deref() else {
+        return;
+    };
+    let Some(kind) = hunk.whole_file() else {
+        return;
+    };
+    let lines = u32::try_from(text.lines().count()).unwrap_or(u32::MAX);
+    *section = whole_file_section(&section.file_path, kind, lines, hunk.language.clone());
+}
 
-    format!("({hunk_num}) @@ -{lhs_line},{lhs_count} +{rhs_line},{rhs_count} @@")
+#[cfg(feature = "tui")]
+/// Tree colour for a kind of change.
+fn chunk_color(chunk_type: ChunkType) -> Color {
+    let theme = theme::current();
+    match chunk_type {
+        ChunkType::Added => theme.added,
+        ChunkType::Deleted => theme.removed,
+        ChunkType::Modified => theme.modified,
+        ChunkType::Unchanged => theme.dimmed,
+    }
 }
 
-/// Format a single change for display
-fn format_change_content(change: &DifftLine) -> String {
-    let mut output = String::new();
+/// One difftastic chunk with the line ranges it spans, kept on its section for diff views.
+#[derive(Clone, Debug)]
+pub struct Hunk {
+    /// Lines spanned on each side.
+    pub range: HunkRange,
+    /// Changed lines in the order difftastic reports them.
+    pub lines: Vec<DifftLine>,
+    /// Language of the file, when the diff names one.
+    pub language: Option<String>,
+    /// Unchanged lines the diff itself carries, shown when the compared files are unavailable.
+    pub context: Vec<ContextLine>,
+}
 
-    match (&change.lhs, &change.rhs) {
-        (Some(lhs), Some(rhs)) => {
-            // Modified line - show both sides
-            write!(output, "-{}: ", lhs.line_number).unwrap();
-            for ch in &lhs.changes {
-                output.push_str(&ch.content);
-            }
-            output.push('\n');
+/// An unchanged line shown around the changes of a unified diff hunk.
+#[derive(Clone, Debug)]
+pub struct ContextLine {
+    /// Line position in the original (0-indexed).
+    pub lhs_line: u32,
+    /// Line position in the modified file (0-indexed).
+    pub rhs_line: u32,
+    /// Text of the line.
+    pub text: String,
+}
 
-            write!(output, "+{}: ", rhs.line_number).unwrap();
-            for ch in &rhs.changes {
-                output.push_str(&ch.content);
-            }
-            output.push('\n');
-        }
-        (Some(lhs), None) => {
-            // Deleted line
-            write!(output, "-{}: ", lhs.line_number).unwrap();
-            for ch in &lhs.changes {
-                output.push_str(&ch.content);
-            }
-            output.push('\n');
+impl Hunk {
+    /// Whether this hunk stands for a whole created or deleted file, which has no typed lines.
+    #[must_use]
+    pub fn whole_file(&self) -> Option<ChunkType> {
+        if !self.lines.is_empty() {
+            return None;
         }
-        (None, Some(rhs)) => {
-            // Added line
-            write!(output, "+{}: ", rhs.line_number).unwrap();
-            for ch in &rhs.changes {
-                output.push_str(&ch.content);
-            }
-            output.push('\n');
-        }
-        (None, None) => {
-            output.push_str(" \n");
+        match self.range.chunk_type() {
+            kind @ (ChunkType::Added | ChunkType::Deleted) => Some(kind),
+            _ => None,
         }
     }
-
-    output
 }
 
-fn extract_chunk_text(side: &Value) -> Option<String> {
-    side.get("changes")
-        .and_then(|c| c.as_array())
-        .map(|changes| {
-            changes
-                .iter()
-                .filter_map(|change| change.get("content").and_then(|c| c.as_str()))
-                .collect::<String>()
-        })
+/// Line ranges a hunk covers on each side, in unified diff (`@@ -a,b +c,d @@`) convention.
+///
+/// Starts are 1-indexed when the side has lines; an empty side instead records the number of
+/// lines preceding the change, as `diff -u` does, so the header can anchor a patch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HunkRange {
+    /// First LHS line (1-indexed), or the preceding line when `lhs_count` is 0.
+    pub lhs_start: u32,
+    /// Number of LHS lines spanned.
+    pub lhs_count: u32,
+    /// First RHS line (1-indexed), or the preceding line when `rhs_count` is 0.
+    pub rhs_start: u32,
+    /// Number of RHS lines spanned.
+    pub rhs_count: u32,
 }
 
-fn extract_column_range(side: &Value) -> (i64, i64) {
-    let changes = side.get("changes").and_then(|c| c.as_array());
+impl HunkRange {
+    /// Span a difftastic chunk from its first to its last changed line on each side.
+    ///
+    /// Difftastic omits unchanged lines between changes, and those lines exist on both sides, so
+    /// each side is extended to stay aligned with the other, matching how difftastic displays the
+    /// chunk. `offset` tracks how far RHS lines have drifted from LHS lines across earlier chunks,
+    /// to place a side that has no lines in this chunk.
+    #[must_use]
+    pub fn from_chunk(chunk: &[DifftLine], offset: &mut i64) -> Self {
+        let lhs_min = chunk
+            .iter()
+            .filter_map(|l| l.lhs.as_ref())
+            .map(|s| s.line_number)
+            .min();
+        let rhs_min = chunk
+            .iter()
+            .filter_map(|l| l.rhs.as_ref())
+            .map(|s| s.line_number)
+            .min();
+        let shift = |line: u32, by: i64| u32::try_from((i64::from(line) + by).max(0)).unwrap_or(0);
+
+        let (Some(l_start), Some(r_start)) = (
+            lhs_min.or_else(|| rhs_min.map(|r| shift(r, -*offset))),
+            rhs_min.or_else(|| lhs_min.map(|l| shift(l, *offset))),
+        ) else {
+            return Self {
+                lhs_start: 0,
+                lhs_count: 0,
+                rhs_start: 0,
+                rhs_count: 0,
+            };
+        };
 
-    let start = changes
-        .and_then(|arr| arr.first())
-        .and_then(|first| first.get("start"))
-        .and_then(serde_json::Value::as_i64)
-        .unwrap_or(0);
+        // Walk the changes, advancing both sides together over the unchanged lines between them
+        let (mut l, mut r) = (l_start, r_start);
+        for line in chunk {
+            match (&line.lhs, &line.rhs) {
+                (Some(lhs), Some(rhs)) => {
+                    l = l.max(lhs.line_number + 1);
+                    r = r.max(rhs.line_number + 1);
+                }
+                (Some(lhs), None) => {
+                    r += lhs.line_number.saturating_sub(l);
+                    l = l.max(lhs.line_number + 1);
+                }
+                (None, Some(rhs)) => {
+                    l += rhs.line_number.saturating_sub(r);
+                    r = r.max(rhs.line_number + 1);
+                }
+                (None, None) => {}
+            }
+        }
+        *offset = i64::from(r) - i64::from(l);
+
+        // Unified headers name the preceding line for an empty side
+        let start = |first: u32, count: u32| if count == 0 { first } else { first + 1 };
+        Self {
+            lhs_start: start(l_start, l - l_start),
+            lhs_count: l - l_start,
+            rhs_start: start(r_start, r - r_start),
+            rhs_count: r - r_start,
+        }
+    }
 
-    let end = changes
-        .and_then(|arr| arr.last())
-        .and_then(|last| last.get("end"))
-        .and_then(serde_json::Value::as_i64)
-        .unwrap_or(0);
+    /// Kind of change, from which sides the hunk has lines on.
+    ///
+    /// A range empty on both sides and anchored before the first line of one side is a deleted
+    /// (or created) file whose length is not yet known.
+    #[must_use]
+    pub fn chunk_type(&self) -> ChunkType {
+        match (self.lhs_count, self.rhs_count) {
+            (0, 0) if self.rhs_start == 0 => ChunkType::Deleted,
+            (0, 0) if self.lhs_start == 0 => ChunkType::Added,
+            (0, 0) => ChunkType::Unchanged,
+            (0, _) => ChunkType::Added,
+            (_, 0) => ChunkType::Deleted,
+            _ => ChunkType::Modified,
+        }
+    }
 
-    (start, end)
+    /// First RHS line as a 0-indexed row, the convention section coordinates use.
+    #[must_use]
+    pub fn rhs_line_start(&self) -> i64 {
+        if self.rhs_count == 0 {
+            i64::from(self.rhs_start)
+        } else {
+            i64::from(self.rhs_start) - 1
+        }
+    }
 }
 
-/// Extract the difftastic hunks as sections (same as sections in a markdown etc)
-///
-/// # Errors
-///
-/// Returns an error if the JSON file cannot be read from disk.
-pub fn extract_difftastic_sections(json_path: &Path) -> io::Result<Vec<Section>> {
-    let content = fs::read_to_string(json_path)?;
-    let lines: Vec<Value> = content
-        .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
-        .collect();
+impl std::fmt::Display for HunkRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "@@ -{},{} +{},{} @@",
+            self.lhs_start, self.lhs_count, self.rhs_start, self.rhs_count
+        )
+    }
+}
 
-    let mut sections = Vec::new();
+impl FromStr for HunkRange {
+    type Err = io::Error;
 
-    for value in lines {
-        let file_path = value
-            .get("path")
-            .and_then(|p| p.as_str())
-            .unwrap_or("unknown");
-
-        if let Some(chunks) = value.get("chunks").and_then(|c| c.as_array()) {
-            for chunk_array in chunks {
-                if let Some(chunk_list) = chunk_array.as_array() {
-                    for chunk in chunk_list {
-                        let lhs = chunk.get("lhs");
-                        let rhs = chunk.get("rhs");
-
-                        let chunk_type = match (lhs, rhs) {
-                            (Some(_), None) => ChunkType::Deleted,
-                            (None, Some(_)) => ChunkType::Added,
-                            (Some(l), Some(r)) if l != r => ChunkType::Modified,
-                            (Some(_), Some(_)) => ChunkType::Unchanged,
-                            _ => continue,
-                        };
-
-                        let line_num = lhs
-                            .or(rhs)
-                            .and_then(|v| v.get("line_number"))
-                            .and_then(serde_json::Value::as_i64)
-                            .unwrap_or(0);
-
-                        let (column_start, column_end) =
-                            lhs.or(rhs).map_or((0, 0), extract_column_range);
-
-                        let title = format!("Chunk @@ {file_path}:{line_num} @@");
-                        let lhs_text = lhs.and_then(extract_chunk_text);
-                        let rhs_text = rhs.and_then(extract_chunk_text);
-
-                        sections.push(create_chunk_section(
-                            file_path,
-                            title,
-                            line_num,
-                            column_start,
-                            column_end,
-                            chunk_type,
-                            lhs_text,
-                            rhs_text,
-                        ));
-                    }
-                }
-            }
-        }
+    /// Parse `@@ -a,b +c,d @@`, where an omitted count means one line.
+    fn from_str(header: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed hunk header: {header}"),
+            )
+        };
+        let mut parts = header
+            .strip_prefix("@@")
+            .ok_or_else(invalid)?
+            .split_whitespace();
+        let mut side = |sign: char| -> io::Result<(u32, u32)> {
+            let spec = parts
+                .next()
+                .and_then(|p| p.strip_prefix(sign))
+                .ok_or_else(invalid)?;
+            let (start, count) = spec.split_once(',').unwrap_or((spec, "1"));
+            Ok((
+                start.parse().map_err(|_| invalid())?,
+                count.parse().map_err(|_| invalid())?,
+            ))
+        };
+        let (lhs_start, lhs_count) = side('-')?;
+        let (rhs_start, rhs_count) = side('+')?;
+        Ok(Self {
+            lhs_start,
+            lhs_count,
+            rhs_start,
+            rhs_count,
+        })
     }
-
-    Ok(sections)
 }
 
 #[cfg(test)]
@@ -0,0 +1,267 @@
+//! Jupyter notebook format, navigating a notebook by the headings in its markdown cells.
+//!
+//! A notebook is JSON, so its sections cannot be addressed by file lines. Each section instead
+//! records the index of the cell it lives in as `line_start` (with `line_end` one past it), and its
+//! byte range indexes that cell's joined source. Code cells (and markdown cells without headings)
+//! nest beneath the heading that precedes them. Saving rewrites only the edited cell's `source`,
+//! so outputs, execution counts and metadata survive round trips.
+
+#[cfg(feature = "tui")]
+use crate::formats::markdown::heading_display;
+use crate::formats::markdown::MarkdownFormat;
+use crate::formats::Format;
+use crate::input;
+use crate::section::{Section, SectionId};
+#[cfg(feature = "tui")]
+use crate::theme;
+#[cfg(feature = "tui")]
+use ratatui::text::{Line, Span};
+use serde::Serialize;
+use serde_json::Value;
+use std::io;
+use std::path::Path;
+
+/// Title prefix marking a code cell section.
+pub const CODE_PREFIX: &str = "[code]";
+/// Title prefix marking a markdown cell section without a heading.
+pub const MARKDOWN_PREFIX: &str = "[markdown]";
+
+/// Parses `.ipynb` notebooks into heading sections with their cells beneath.
+pub struct NotebookFormat;
+
+impl Format for NotebookFormat {
+    fn name(&self) -> &'static str {
+        "jupyter"
+    }
+
+    fn file_extension(&self) -> &'static str {
+        "ipynb"
+    }
+
+    fn language(&self) -> tree_sitter::Language {
+        // Headings inside markdown cells are parsed as markdown
+        tree_sitter_md::LANGUAGE.into()
+    }
+
+    fn section_query(&self) -> &'static str {
+        MarkdownFormat.section_query()
+    }
+
+    fn title_query(&self) -> &'static str {
+        MarkdownFormat.title_query()
+    }
+
+    #[cfg(feature = "tui")]
+    fn format_section_display(&self, level: usize, title: &str) -> Line<'static> {
+        if title.starts_with(CODE_PREFIX) || title.starts_with(MARKDOWN_PREFIX) {
+            let theme = theme::current();
+            return Line::from(vec![
+                Span::styled("▸ ", theme.fg(theme.dimmed)),
+                Span::raw(title.to_string()),
+            ]);
+        }
+        heading_display(level, title)
+    }
+
+    fn sniff(&self, head: &str) -> bool {
+        head.trim_start().starts_with('{')
+            && head.contains("\"cells\"")
+            && head.contains("\"cell_type\"")
+    }
+
+    fn parse(&self, content: &str, file_path: &Path) -> io::Result<Vec<Section>> {
+        let notebook = parse_notebook(content)?;
+        let cells = cells(&notebook)?;
+        let path = file_path.to_string_lossy().to_string();
+
+        let mut sections = Vec::new();
+        // Level of the most recent heading, which cells without headings nest beneath
+        let mut heading_level = 0;
+
+        for (index, cell) in cells.iter().enumerate() {
+            let source = cell_source(cell);
+            let cell_line = i64::try_from(index).unwrap_or(0);
+            let cell_section = |title: String, level: usize, byte_start, byte_end| Section {
+                title,
+                level,
+                line_start: cell_line,
+                line_end: cell_line + 1,
+                column_start: 0,
+                column_end: 0,
+                byte_start,
+                byte_end,
+                file_path: path.clone(),
+                id: SectionId::default(),
+                parent_index: None,
+                children_indices: Vec::new(),
+                section_content: None,
+                hunk: None,
+            };
+
+            if cell.get("cell_type").and_then(Value::as_str) == Some("markdown") {
+                let headings = input::parse_sections(&source, file_path, &MarkdownFormat)?;
+
+                // Prose before the first heading keeps its place under the previous heading
+                let preamble_end = headings
+                    .first()
+                    .map_or(source.len(), |h| heading_line_start(&source, h.byte_start));
+                if !source[..preamble_end].trim().is_empty() {
+                    sections.push(cell_section(
+                        summary(MARKDOWN_PREFIX, &source[..preamble_end]),
+                        heading_level + 1,
+                        0,
+                        preamble_end,
+                    ));
+                }
+
+                for heading in headings {
+                    heading_level = heading.level;
+                    sections.push(cell_section(
+                        heading.title,
+                        heading.level,
+                        heading.byte_start,
+                        heading.byte_end,
+                    ));
+                }
+            } else {
+                sections.push(cell_section(
+                    summary(CODE_PREFIX, &source),
+                    heading_level + 1,
+                    0,
+                    source.len(),
+                ));
+            }
+        }
+
+        input::build_hierarchy(&mut sections);
+        Ok(sections)
+    }
+
+    fn section_body(&self, content: &str, section: &Section) -> String {
+        let Ok(notebook) = parse_notebook(content) else {
+            return String::new();
+        };
+        let source = cells(&notebook)
+            .ok()
+            .and_then(|cells| cells.get(cell_index(section)))
+            .map(cell_source)
+            .unwrap_or_default();
+        let start = section.byte_start.min(source.len());
+        let end = section.byte_end.min(source.len()).max(start);
+        source[start..end].to_string()
+    }
+
+    fn splice_section(
+        &self,
+        content: &str,
+        section: &Section,
+        body: &str,
+    ) -> Option<io::Result<String>> {
+        Some(splice_cell(content, section, body))
+    }
+
+    fn supports_reorder(&self) -> bool {
+        false
+    }
+
+    fn reflows_prose(&self) -> bool {
+        false
+    }
+
+    fn parses_tree(&self) -> bool {
+        false
+    }
+}
+
+fn parse_notebook(content: &str) -> io::Result<Value> {
+    serde_json::from_str(content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid notebook: {e}")))
+}
+
+fn cells(notebook: &Value) -> io::Result<&Vec<Value>> {
+    notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Notebook has no cells"))
+}
+
+fn cell_index(section: &Section) -> usize {
+    usize::try_from(section.line_start).unwrap_or(0)
+}
+
+/// Join a cell's source, which nbformat stores either as one string or as a list of lines.
+fn cell_source(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Split source back into the list-of-lines form nbformat writes, each keeping its newline.
+fn source_lines(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Byte offset where the heading line ending just before `byte_start` begins.
+fn heading_line_start(source: &str, byte_start: usize) -> usize {
+    source[..byte_start.min(source.len())]
+        .trim_end_matches('\n')
+        .rfind('\n')
+        .map_or(0, |i| i + 1)
+}
+
+fn summary(prefix: &str, source: &str) -> String {
+    let first = source.lines().map(str::trim).find(|l| !l.is_empty());
+    match first {
+        Some(line) => format!("{prefix} {line}"),
+        None => format!("{prefix} (empty)"),
+    }
+}
+
+fn splice_cell(content: &str, section: &Section, body: &str) -> io::Result<String> {
+    let mut notebook = parse_notebook(content)?;
+    let index = cell_index(section);
+    let cell = notebook
+        .get_mut("cells")
+        .and_then(Value::as_array_mut)
+        .and_then(|cells| cells.get_mut(index))
+        .ok_or_else(|| io::Error::other(format!("Notebook has no cell {index}")))?;
+
+    let source = cell_source(cell);
+    let start = section.byte_start.min(source.len());
+    let end = section.byte_end.min(source.len()).max(start);
+    let before = source[..start].trim_end();
+    let after = source[end..].trim_start();
+    let body = body.trim_matches('\n');
+
+    let new_source = [before, body, after]
+        .into_iter()
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    cell["source"] = source_lines(&new_source);
+    to_notebook_json(&notebook)
+}
+
+/// Serialise as Jupyter does: sorted keys, one-space indent and a trailing newline.
+fn to_notebook_json(notebook: &Value) -> io::Result<String> {
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    notebook
+        .serialize(&mut serializer)
+        .map_err(io::Error::other)?;
+    out.push(b'\n');
+    String::from_utf8(out).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+#[path = "../tests/jupyter.rs"]
+mod tests;
@@ -4,19 +4,27 @@
 //! and extracting section structure from ATX-style headings (# syntax).
 
 use crate::formats::Format;
-use ratatui::{
-    style::{Color, Style},
-    text::{Line, Span},
-};
+#[cfg(feature = "tui")]
+use crate::theme;
+#[cfg(feature = "tui")]
+use ratatui::text::{Line, Span};
 
 /// Tree-sitter queries for ATX-style markdown headings (# syntax).
 pub struct MarkdownFormat;
 
 impl Format for MarkdownFormat {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
     fn file_extension(&self) -> &'static str {
         "md"
     }
 
+    fn extensions(&self) -> Vec<String> {
+        vec!["md".to_string(), "markdown".to_string()]
+    }
+
     fn language(&self) -> tree_sitter::Language {
         tree_sitter_md::LANGUAGE.into()
     }
@@ -29,26 +37,26 @@ impl Format for MarkdownFormat {
         "(atx_heading heading_content: (inline) @title)"
     }
 
+    #[cfg(feature = "tui")]
     fn format_section_display(&self, level: usize, title: &str) -> Line<'static> {
-        // Cycle through colors for different heading levels
-        let colors = [
-            Color::Cyan,
-            Color::Green,
-            Color::LightYellow,
-            Color::Magenta,
-            Color::Blue,
-            Color::Red,
-        ];
-
-        let color = colors[(level - 1) % colors.len()];
-        let prefix = "#".repeat(level);
-
-        let spans = vec![
-            Span::styled(prefix, Style::default().fg(color)),
-            Span::raw(" "),
-            Span::raw(title.to_string()),
-        ];
-
-        Line::from(spans)
+        heading_display(level, title)
     }
 }
+
+#[cfg(feature = "tui")]
+/// Render a heading as `#`-prefixed text, colouring the prefix by level.
+///
+/// Shared with query-defined formats so every heading-based tree reads the same way.
+#[must_use]
+pub fn heading_display(level: usize, title: &str) -> Line<'static> {
+    let theme = theme::current();
+    let prefix = "#".repeat(level);
+
+    let spans = vec![
+        Span::styled(prefix, theme.fg(theme.heading(level))),
+        Span::raw(" "),
+        Span::raw(title.to_string()),
+    ];
+
+    Line::from(spans)
+}
@@ -0,0 +1,212 @@
+//! Formats declared in asterism.toml through tree-sitter queries.
+//!
+//! Adding a built-in format means writing Rust; a query format only needs a bundled grammar, a
+//! query locating headings, a query locating titles and a rule turning a capture into a level.
+//! Everything is validated when the configuration loads so a typo surfaces before the TUI starts.
+
+use crate::config::FormatDefinition;
+#[cfg(feature = "tui")]
+use crate::formats::markdown::heading_display;
+use crate::formats::Format;
+use crate::input;
+#[cfg(feature = "tui")]
+use ratatui::text::Line;
+use std::io;
+use std::sync::Arc;
+use tree_sitter::{Language, Node, Query};
+
+/// Names of the grammars compiled into asterism, usable in `[[custom_formats]]`.
+pub const BUNDLED_GRAMMARS: &[&str] = &["markdown", "markdown_inline"];
+
+/// Look up a bundled tree-sitter grammar by name.
+#[must_use]
+pub fn bundled_grammar(name: &str) -> Option<Language> {
+    match name {
+        "markdown" => Some(tree_sitter_md::LANGUAGE.into()),
+        "markdown_inline" => Some(tree_sitter_md::INLINE_LANGUAGE.into()),
+        _ => None,
+    }
+}
+
+/// Turns the level capture of a heading into its nesting depth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LevelRule {
+    /// First digit in the node kind, as in `atx_h2_marker` or `setext_h1_underline`.
+    Kind,
+    /// Length of the leading run of marker characters, as in `AsciiDoc` `==` or org-mode `**`.
+    Length,
+    /// One plus the number of enclosing nodes of the heading's own kind.
+    Depth,
+    /// Every heading sits at the same level.
+    Fixed(usize),
+}
+
+impl LevelRule {
+    /// Parse a rule name from configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for names other than "kind", "length", "depth" or a positive number.
+    pub fn parse(rule: &str) -> io::Result<Self> {
+        match rule {
+            "kind" => Ok(Self::Kind),
+            "length" => Ok(Self::Length),
+            "depth" => Ok(Self::Depth),
+            _ => match rule.parse::<usize>() {
+                Ok(level) if level > 0 => Ok(Self::Fixed(level)),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown level rule '{rule}' (use kind, length, depth or a number)"),
+                )),
+            },
+        }
+    }
+}
+
+/// A format whose parsing is driven entirely by configured queries.
+pub struct QueryFormat {
+    name: String,
+    extensions: Vec<String>,
+    language: Language,
+    section_query: String,
+    title_query: String,
+    /// Queries compiled while validating, reused for every parse.
+    compiled: (Arc<Query>, Arc<Query>),
+    level_capture: Option<String>,
+    level_rule: LevelRule,
+}
+
+impl QueryFormat {
+    /// Validate a definition from asterism.toml and build the format it describes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the grammar is not bundled, a query does not compile against it, the
+    /// level capture is absent from the section query, or the level rule is unknown.
+    pub fn new(def: &FormatDefinition) -> io::Result<Self> {
+        let invalid = |msg: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Custom format '{}': {msg}", def.name),
+            )
+        };
+
+        let language = bundled_grammar(&def.grammar).ok_or_else(|| {
+            invalid(format!(
+                "unknown grammar '{}' (only {} are bundled; other grammars such as AsciiDoc or \
+                 LaTeX cannot be used yet)",
+                def.grammar,
+                BUNDLED_GRAMMARS.join(", ")
+            ))
+        })?;
+
+        let section_query = Query::new(&language, &def.section_query)
+            .map_err(|e| invalid(format!("section query error: {e}")))?;
+        let title_query = Query::new(&language, &def.title_query)
+            .map_err(|e| invalid(format!("title query error: {e}")))?;
+
+        if !title_query.capture_names().contains(&"title") {
+            return Err(invalid("title query has no @title capture".to_string()));
+        }
+        if let Some(capture) = &def.level_capture {
+            if !section_query.capture_names().contains(&capture.as_str()) {
+                return Err(invalid(format!(
+                    "section query has no @{capture} capture for the level"
+                )));
+            }
+        }
+
+        let level_rule = LevelRule::parse(def.level_rule.as_deref().unwrap_or("kind"))
+            .map_err(|e| invalid(e.to_string()))?;
+
+        Ok(Self {
+            name: def.name.clone(),
+            extensions: def
+                .extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_string())
+                .collect(),
+            language,
+            section_query: def.section_query.clone(),
+            title_query: def.title_query.clone(),
+            compiled: (Arc::new(section_query), Arc::new(title_query)),
+            level_capture: def.level_capture.clone(),
+            level_rule,
+        })
+    }
+}
+
+impl Format for QueryFormat {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn file_extension(&self) -> &str {
+        self.extensions.first().map_or("txt", String::as_str)
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        self.extensions.clone()
+    }
+
+    fn language(&self) -> Language {
+        self.language.clone()
+    }
+
+    fn section_query(&self) -> &str {
+        &self.section_query
+    }
+
+    fn title_query(&self) -> &str {
+        &self.title_query
+    }
+
+    fn compiled_queries(&self) -> Option<(Arc<Query>, Arc<Query>)> {
+        Some(self.compiled.clone())
+    }
+
+    fn level_capture(&self) -> Option<&str> {
+        self.level_capture.as_deref()
+    }
+
+    fn heading_level(&self, heading: Node, level_node: Option<Node>, source: &str) -> usize {
+        let node = level_node.unwrap_or(heading);
+        match self.level_rule {
+            LevelRule::Kind => node
+                .kind()
+                .chars()
+                .find_map(|c| c.to_digit(10))
+                .map_or_else(|| input::marker_level(heading), |d| d as usize),
+            LevelRule::Length => {
+                let text = source[node.byte_range()].trim_start();
+                text.chars()
+                    .next()
+                    .map_or(1, |marker| {
+                        text.chars().take_while(|&c| c == marker).count()
+                    })
+                    .max(1)
+            }
+            LevelRule::Depth => {
+                let mut depth = 1;
+                let mut ancestor = heading.parent();
+                while let Some(parent) = ancestor {
+                    if parent.kind() == heading.kind() {
+                        depth += 1;
+                    }
+                    ancestor = parent.parent();
+                }
+                depth
+            }
+            LevelRule::Fixed(level) => level,
+        }
+    }
+
+    #[cfg(feature = "tui")]
+    fn format_section_display(&self, level: usize, title: &str) -> Line<'static> {
+        heading_display(level, title)
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/query.rs"]
+mod tests;
@@ -0,0 +1,190 @@
+//! Format registry resolving which `Format` handles a given file.
+//!
+//! Without a registry every document would be parsed as markdown and the UI would have to guess
+//! the format back from section titles. The registry maps extensions to formats, falls back to
+//! content sniffing for ambiguous files (such as difftastic JSON), and lets asterism.toml and the
+//! `--format` flag override both.
+
+use crate::config::Config;
+use crate::formats::conflict::ConflictFormat;
+use crate::formats::difftastic::DifftasticFormat;
+use crate::formats::jupyter::NotebookFormat;
+use crate::formats::markdown::MarkdownFormat;
+use crate::formats::query::QueryFormat;
+use crate::formats::unified::UnifiedDiffFormat;
+use crate::formats::Format;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Bytes read from the head of a file when its extension does not identify a format.
+const SNIFF_BYTES: u64 = 8192;
+
+/// Resolves files to the format that parses and displays them.
+#[derive(Clone)]
+pub struct FormatRegistry {
+    /// Registered formats in precedence order for content sniffing.
+    formats: Vec<Arc<dyn Format>>,
+    /// Lowercased extension (without dot) to format name.
+    extensions: HashMap<String, String>,
+    /// Extensions parsed as markdown only because no format claims them, whose files are sniffed.
+    fallback: HashSet<String>,
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(MarkdownFormat));
+        registry.register(Arc::new(DifftasticFormat));
+        registry.register(Arc::new(NotebookFormat));
+        registry.register(Arc::new(UnifiedDiffFormat));
+        registry.register(Arc::new(ConflictFormat));
+        registry
+    }
+}
+
+impl FormatRegistry {
+    /// Create an empty registry with no formats.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+            extensions: HashMap::new(),
+            fallback: HashSet::new(),
+        }
+    }
+
+    /// Build the built-in registry with custom formats and extension overrides from configuration.
+    ///
+    /// Custom formats take the extensions they list even from built-in formats. Discovery
+    /// extensions that no format claims parse as markdown, so `-e txt` keeps opening plain text
+    /// notes as documents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a custom format is invalid or the configuration assigns an extension to
+    /// an unknown format.
+    pub fn from_config(cfg: &Config) -> io::Result<Self> {
+        let mut registry = Self::default();
+        for def in &cfg.custom_formats {
+            let format = QueryFormat::new(def)?;
+            let name = format.name().to_string();
+            let extensions = format.extensions();
+            registry.register(Arc::new(format));
+            for ext in extensions {
+                registry.map_extension(&ext, &name)?;
+            }
+        }
+        for ext in &cfg.file_extensions {
+            if registry.by_extension(ext).is_none() {
+                registry.map_extension(ext, "markdown")?;
+                registry
+                    .fallback
+                    .insert(ext.trim_start_matches('.').to_lowercase());
+            }
+        }
+        let mut overrides: Vec<_> = cfg.formats.iter().collect();
+        overrides.sort();
+        for (ext, name) in overrides {
+            registry.map_extension(ext, name)?;
+        }
+        Ok(registry)
+    }
+
+    /// Add a format, claiming its default extensions unless another format already holds them.
+    pub fn register(&mut self, format: Arc<dyn Format>) {
+        for ext in format.extensions() {
+            self.extensions
+                .entry(ext.to_lowercase())
+                .or_insert_with(|| format.name().to_string());
+        }
+        self.formats.retain(|f| f.name() != format.name());
+        self.formats.push(format);
+    }
+
+    /// Assign an extension to a registered format, replacing any existing assignment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no format is registered under `name`.
+    pub fn map_extension(&mut self, ext: &str, name: &str) -> io::Result<()> {
+        if self.by_name(name).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown format '{name}' for extension '{ext}' (available: {})",
+                    self.names().join(", ")
+                ),
+            ));
+        }
+        let ext = ext.trim_start_matches('.').to_lowercase();
+        self.fallback.remove(&ext);
+        self.extensions.insert(ext, name.to_string());
+        Ok(())
+    }
+
+    /// Names of all registered formats, in registration order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.formats.iter().map(|f| f.name()).collect()
+    }
+
+    /// Look up a format by its registry name.
+    #[must_use]
+    pub fn by_name(&self, name: &str) -> Option<Arc<dyn Format>> {
+        self.formats.iter().find(|f| f.name() == name).cloned()
+    }
+
+    /// Look up the format assigned to an extension.
+    #[must_use]
+    pub fn by_extension(&self, ext: &str) -> Option<Arc<dyn Format>> {
+        self.extensions
+            .get(&ext.to_lowercase())
+            .and_then(|name| self.by_name(name))
+    }
+
+    /// Find the first format that recognises the given file head.
+    #[must_use]
+    pub fn sniff(&self, head: &str) -> Option<Arc<dyn Format>> {
+        self.formats.iter().find(|f| f.sniff(head)).cloned()
+    }
+
+    /// Resolve a file to its format by extension, then by sniffing its first few kilobytes.
+    ///
+    /// Only files whose extension no format claims are read: formats that sniff first (such as
+    /// merge conflicts) are checked before the markdown fallback, and files with unknown
+    /// extensions are sniffed by every format.
+    #[must_use]
+    pub fn for_path(&self, path: &Path) -> Option<Arc<dyn Format>> {
+        let head = || {
+            let mut head = Vec::new();
+            File::open(path)
+                .and_then(|f| f.take(SNIFF_BYTES).read_to_end(&mut head))
+                .ok()?;
+            Some(String::from_utf8_lossy(&head).to_string())
+        };
+
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let Some(format) = ext.as_deref().and_then(|ext| self.by_extension(ext)) else {
+            return self.sniff(&head()?);
+        };
+        if !ext.is_some_and(|ext| self.fallback.contains(&ext)) {
+            return Some(format);
+        }
+        let sniffed = head().and_then(|head| {
+            self.formats
+                .iter()
+                .find(|f| f.sniff_first() && f.sniff(&head))
+                .cloned()
+        });
+        Some(sniffed.unwrap_or(format))
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/registry.rs"]
+mod tests;
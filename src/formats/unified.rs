@@ -0,0 +1,263 @@
+//! Unified diff format, reading ordinary `diff -u` and `git diff` output.
+//!
+//! Hunks become the same sections difftastic output produces, carrying each changed line as a
+//! `DifftLine` whose single change spans the whole line, so the tree, diff view, hunk review and
+//! patch output work without difftastic installed. Context lines are kept on the hunk, so the diff
+//! view can show them even when the patched files are not on disk.
+
+#[cfg(feature = "tui")]
+use crate::formats::difftastic::DifftasticFormat;
+use crate::formats::difftastic::{
+    hunk_section, ContextLine, DifftChange, DifftLine, DifftSide, Hunk, HunkRange,
+};
+use crate::formats::Format;
+use crate::section::Section;
+#[cfg(feature = "tui")]
+use ratatui::text::Line;
+use std::io;
+use std::path::Path;
+use std::str::Lines;
+
+/// Parses unified diffs into one section per hunk, grouped under the files they change.
+pub struct UnifiedDiffFormat;
+
+impl Format for UnifiedDiffFormat {
+    fn name(&self) -> &'static str {
+        "unified"
+    }
+
+    fn file_extension(&self) -> &'static str {
+        "diff"
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        vec!["diff".to_string(), "patch".to_string()]
+    }
+
+    fn language(&self) -> tree_sitter::Language {
+        // Unified diffs are parsed line by line rather than with tree-sitter
+        tree_sitter_md::LANGUAGE.into()
+    }
+
+    fn section_query(&self) -> &'static str {
+        ""
+    }
+
+    fn title_query(&self) -> &'static str {
+        ""
+    }
+
+    #[cfg(feature = "tui")]
+    fn format_section_display(&self, level: usize, title: &str) -> Line<'static> {
+        DifftasticFormat.format_section_display(level, title)
+    }
+
+    fn sniff(&self, head: &str) -> bool {
+        // `git format-patch` output opens with a mail header before its first diff
+        head.starts_with("diff ")
+            || head.contains("\ndiff --git ")
+            || (head.starts_with("--- ") && head.contains("\n+++ "))
+    }
+
+    fn is_diff(&self) -> bool {
+        true
+    }
+
+    fn supports_reorder(&self) -> bool {
+        false
+    }
+
+    fn reflows_prose(&self) -> bool {
+        false
+    }
+
+    fn parses_tree(&self) -> bool {
+        false
+    }
+
+    fn parse(&self, content: &str, _file_path: &Path) -> io::Result<Vec<Section>> {
+        parse_unified_diff(content)
+    }
+}
+
+/// Parse unified diff text into hunk sections.
+///
+/// # Errors
+///
+/// Returns an error if a hunk header is malformed or a hunk body is shorter than its header.
+pub fn parse_unified_diff(content: &str) -> io::Result<Vec<Section>> {
+    let mut sections = Vec::new();
+    let mut old_path = String::new();
+    let mut file_path = String::new();
+    let mut hunk_number = 0;
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // Fallback for diffs without ---/+++ lines, such as pure renames or mode changes
+            file_path = paths
+                .rsplit_once(" b/")
+                .map_or(paths, |(_, new)| new)
+                .to_string();
+            hunk_number = 0;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            old_path = diff_path(path, "a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            let new_path = diff_path(path, "b/");
+            // Deleted files name /dev/null as their new side
+            file_path = if new_path == "/dev/null" {
+                old_path.clone()
+            } else {
+                new_path
+            };
+            hunk_number = 0;
+        } else if line.starts_with("@@ ") {
+            let range: HunkRange = line.parse()?;
+            let (body, unchanged) = read_hunk(&mut lines, &range)?;
+            hunk_number += 1;
+            sections.push(hunk_section(
+                &file_path,
+                format!("Hunk {hunk_number} {range}"),
+                Hunk {
+                    range,
+                    lines: body,
+                    language: None,
+                    context: unchanged,
+                },
+            ));
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Path from a ---/+++ line, without its timestamp or git's side prefix.
+fn diff_path(path: &str, prefix: &str) -> String {
+    let path = path.split('\t').next().unwrap_or(path).trim_end();
+    path.strip_prefix(prefix).unwrap_or(path).to_string()
+}
+
+/// Read a hunk body, pairing each run of removed lines with the added lines that follow it, and
+/// keeping its context lines apart.
+///
+/// Lines are consumed by the header's counts rather than by prefix, since a removed line may
+/// itself begin with `--`. The body still ends at the next hunk or file header, so a header whose
+/// counts overrun its body cannot swallow the hunks after it.
+///
+/// # Errors
+///
+/// Returns an error if the body ends before the header's counts are met.
+fn read_hunk(
+    lines: &mut Lines<'_>,
+    range: &HunkRange,
+) -> io::Result<(Vec<DifftLine>, Vec<ContextLine>)> {
+    let first = |start: u32, count: u32| {
+        if count == 0 {
+            start
+        } else {
+            start.saturating_sub(1)
+        }
+    };
+    let mut l = first(range.lhs_start, range.lhs_count);
+    let mut r = first(range.rhs_start, range.rhs_count);
+    let (mut old_left, mut new_left) = (range.lhs_count, range.rhs_count);
+
+    let mut body = Vec::new();
+    let mut context = Vec::new();
+    let mut removed: Vec<DifftSide> = Vec::new();
+    let mut added: Vec<DifftSide> = Vec::new();
+
+    while old_left > 0 || new_left > 0 {
+        if lines
+            .clone()
+            .next()
+            .is_none_or(|line| starts_header(line, lines))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Hunk {range} ends after {} of {} old and {} of {} new lines",
+                    range.lhs_count - old_left,
+                    range.lhs_count,
+                    range.rhs_count - new_left,
+                    range.rhs_count
+                ),
+            ));
+        }
+        let Some(line) = lines.next() else { break };
+        match line.chars().next() {
+            Some('-') if old_left > 0 => {
+                removed.push(whole_line(l, &line[1..]));
+                l += 1;
+                old_left -= 1;
+            }
+            Some('+') if new_left > 0 => {
+                added.push(whole_line(r, &line[1..]));
+                r += 1;
+                new_left -= 1;
+            }
+            // "\ No newline at end of file"
+            Some('\\') => {}
+            _ => {
+                pair_runs(&mut body, &mut removed, &mut added);
+                context.push(ContextLine {
+                    lhs_line: l,
+                    rhs_line: r,
+                    text: line.get(1..).unwrap_or_default().to_string(),
+                });
+                l += 1;
+                r += 1;
+                old_left = old_left.saturating_sub(1);
+                new_left = new_left.saturating_sub(1);
+            }
+        }
+    }
+    pair_runs(&mut body, &mut removed, &mut added);
+    Ok((body, context))
+}
+
+/// Whether `line`, the next one to read, begins another hunk or file rather than this hunk's body.
+///
+/// A `--- ` line only counts when a `+++ ` line follows it, since a removed line reading `-- ...`
+/// (such as a SQL comment) looks the same on its own.
+fn starts_header(line: &str, lines: &Lines<'_>) -> bool {
+    let next_is_new_path = || {
+        lines
+            .clone()
+            .nth(1)
+            .is_some_and(|next| next.starts_with("+++ "))
+    };
+    line.starts_with("diff ")
+        || line.starts_with("@@ ")
+        || (line.starts_with("--- ") && next_is_new_path())
+}
+
+fn whole_line(line_number: u32, text: &str) -> DifftSide {
+    DifftSide {
+        line_number,
+        changes: vec![DifftChange {
+            start: 0,
+            end: u32::try_from(text.len()).unwrap_or(u32::MAX),
+            content: text.to_string(),
+            highlight: "normal".to_string(),
+        }],
+    }
+}
+
+fn pair_runs(body: &mut Vec<DifftLine>, removed: &mut Vec<DifftSide>, added: &mut Vec<DifftSide>) {
+    let mut added = added.drain(..);
+    for lhs in removed.drain(..) {
+        body.push(DifftLine {
+            lhs: Some(lhs),
+            rhs: added.next(),
+        });
+    }
+    body.extend(added.map(|rhs| DifftLine {
+        lhs: None,
+        rhs: Some(rhs),
+    }));
+}
+
+#[cfg(test)]
+#[path = "../tests/unified.rs"]
+mod tests;
@@ -0,0 +1,59 @@
+//! Git plumbing for reviewing a working tree's changes as difftastic hunks.
+//!
+//! `git diff` compares the index with the working tree, so hunks read their original side from the
+//! index and staging them produces a patch for `git apply --cached`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn git(repo: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo);
+    command
+}
+
+fn run(command: &mut Command, what: &str) -> io::Result<String> {
+    let output = command
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run {what}: {e}")))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{what} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Top-level directory of the repository containing `path`.
+///
+/// # Errors
+///
+/// Returns an error if git is unavailable or `path` is not inside a repository.
+pub fn repo_root(path: &Path) -> io::Result<PathBuf> {
+    let root = run(git(path).args(["rev-parse", "--show-toplevel"]), "git")?;
+    Ok(PathBuf::from(root.trim_end()))
+}
+
+/// Difftastic JSON for unstaged changes, one object per changed file.
+///
+/// # Errors
+///
+/// Returns an error if git or difft cannot run.
+pub fn difftastic_diff(repo: &Path) -> io::Result<String> {
+    run(
+        git(repo)
+            .args(["-c", "diff.external=difft", "diff"])
+            .env("DFT_DISPLAY", "json")
+            .env("DFT_UNSTABLE", "yes"),
+        "git diff with difft",
+    )
+}
+
+/// Staged content of a file, given relative to the repository root.
+#[must_use]
+pub fn index_content(repo: &Path, file: &Path) -> Option<String> {
+    let spec = format!(":{}", file.to_string_lossy());
+    run(git(repo).args(["show", &spec]), "git show").ok()
+}
@@ -3,15 +3,94 @@ use ratatui::{
     style::{Color, Style},
     text::{Line, Span},
 };
+use std::path::Path;
 use syntect::easy::HighlightLines;
-use syntect::highlighting::ThemeSet;
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 
 static SYNTAX_SET: std::sync::LazyLock<SyntaxSet> =
     std::sync::LazyLock::new(SyntaxSet::load_defaults_newlines);
 static THEME_SET: std::sync::LazyLock<ThemeSet> = std::sync::LazyLock::new(ThemeSet::load_defaults);
 
-/// Highlight source lines with syntax coloring.
+/// Theme used when the installed theme turns highlighting off but a caller highlights anyway.
+const FALLBACK_THEME: &str = "base16-eighties.dark";
+
+/// Whether syntect bundles a theme by this name.
+#[must_use]
+pub fn has_theme(name: &str) -> bool {
+    THEME_SET.themes.contains_key(name)
+}
+
+/// Syntect theme chosen by the installed theme, or `None` when highlighting is off.
+#[must_use]
+pub fn syntax_theme() -> Option<&'static Theme> {
+    let name = crate::theme::current().syntax.as_deref()?;
+    THEME_SET.themes.get(name)
+}
+
+fn to_style(style: syntect::highlighting::Style) -> Style {
+    Style::default().fg(Color::Rgb(
+        style.foreground.r,
+        style.foreground.g,
+        style.foreground.b,
+    ))
+}
+
+/// Syntax for a file, by the language a diff names for it or else by its extension.
+///
+/// Returns `None` when syntect has no definition beyond plain text, so callers can fall back to
+/// their own colouring.
+#[must_use]
+pub fn find_syntax(language: Option<&str>, file_path: &str) -> Option<&'static SyntaxReference> {
+    let set: &'static SyntaxSet = &SYNTAX_SET;
+    language
+        .and_then(|name| {
+            set.find_syntax_by_name(name)
+                .or_else(|| set.find_syntax_by_token(name))
+        })
+        .or_else(|| {
+            let extension = Path::new(file_path).extension()?.to_str()?;
+            set.find_syntax_by_extension(extension)
+        })
+        .filter(|syntax| syntax.name != set.find_syntax_plain_text().name)
+}
+
+/// File extension that selects `syntax`, for highlighters keyed by extension.
+#[must_use]
+pub fn syntax_extension(syntax: &SyntaxReference) -> &str {
+    syntax.file_extensions.first().map_or("txt", String::as_str)
+}
+
+/// Highlights successive lines of one file, carrying parse state from line to line.
+pub struct LineHighlighter {
+    lines: HighlightLines<'static>,
+}
+
+impl LineHighlighter {
+    /// Start highlighting a file with the given syntax.
+    #[must_use]
+    pub fn new(syntax: &'static SyntaxReference) -> Self {
+        Self {
+            lines: HighlightLines::new(
+                syntax,
+                syntax_theme().unwrap_or(&THEME_SET.themes[FALLBACK_THEME]),
+            ),
+        }
+    }
+
+    /// Styled pieces of the next line, or the whole line unstyled if highlighting fails.
+    pub fn styles(&mut self, line: &str) -> Vec<(Style, String)> {
+        match self.lines.highlight_line(line, &SYNTAX_SET) {
+            Ok(highlighted) => highlighted
+                .into_iter()
+                .map(|(style, text)| (to_style(style), text.to_string()))
+                .collect(),
+            Err(_) => vec![(Style::default(), line.to_string())],
+        }
+    }
+}
+
+/// Highlight source lines with syntax coloring for a given file extension.
 ///
 /// # Panics
 ///
@@ -21,13 +100,13 @@ pub fn highlight_source_lines(
     start: usize,
     end: usize,
     target_line: usize,
+    extension: &str,
 ) -> Vec<Line<'static>> {
-    let theme = &THEME_SET.themes["base16-eighties.dark"];
     let syntax_ref = SYNTAX_SET
-        .find_syntax_by_extension("rs")
+        .find_syntax_by_extension(extension)
         .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
 
-    let mut highlight_lines = HighlightLines::new(syntax_ref, theme);
+    let mut highlight_lines = syntax_theme().map(|theme| HighlightLines::new(syntax_ref, theme));
     let mut display_lines: Vec<Line<'static>> = Vec::new();
 
     for (i, line_text) in lines[start..end].iter().enumerate() {
@@ -39,20 +118,17 @@ pub fn highlight_source_lines(
         };
         let line_num_text = format!("{marker} {line_num:4} | ");
 
-        let highlighted = highlight_lines
-            .highlight_line(line_text, &SYNTAX_SET)
-            .unwrap();
-
         let mut spans = vec![Span::raw(line_num_text)];
-        for (style, text) in highlighted {
-            spans.push(Span::styled(
-                text.to_string(),
-                Style::default().fg(Color::Rgb(
-                    style.foreground.r,
-                    style.foreground.g,
-                    style.foreground.b,
-                )),
-            ));
+        match highlight_lines.as_mut() {
+            Some(highlight_lines) => {
+                let highlighted = highlight_lines
+                    .highlight_line(line_text, &SYNTAX_SET)
+                    .unwrap();
+                for (style, text) in highlighted {
+                    spans.push(Span::styled(text.to_string(), to_style(style)));
+                }
+            }
+            None => spans.push(Span::raw((*line_text).to_string())),
         }
 
         display_lines.push(Line::from(spans));
@@ -65,7 +141,9 @@ pub fn highlight_source_lines(
 ///
 /// Falls back to plain text rendering if syntax highlighting fails.
 pub fn highlight_line_with_extension(line: &str, extension: &str) -> Line<'static> {
-    let theme = &THEME_SET.themes["base16-eighties.dark"];
+    let Some(theme) = syntax_theme() else {
+        return Line::from(line.to_string());
+    };
     let syntax_ref = SYNTAX_SET
         .find_syntax_by_extension(extension)
         .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
@@ -75,17 +153,10 @@ pub fn highlight_line_with_extension(line: &str, extension: &str) -> Line<'stati
     // Try to highlight, fall back to plain text if it fails
     match highlight_lines.highlight_line(line, &SYNTAX_SET) {
         Ok(highlighted) => {
-            let mut spans = Vec::new();
-            for (style, text) in highlighted {
-                spans.push(Span::styled(
-                    text.to_string(),
-                    Style::default().fg(Color::Rgb(
-                        style.foreground.r,
-                        style.foreground.g,
-                        style.foreground.b,
-                    )),
-                ));
-            }
+            let spans: Vec<Span> = highlighted
+                .into_iter()
+                .map(|(style, text)| Span::styled(text.to_string(), to_style(style)))
+                .collect();
             Line::from(spans)
         }
         Err(_) => {
@@ -94,3 +165,7 @@ pub fn highlight_line_with_extension(line: &str, extension: &str) -> Line<'stati
         }
     }
 }
+
+#[cfg(test)]
+#[path = "tests/highlight.rs"]
+mod tests;
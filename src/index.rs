@@ -0,0 +1,285 @@
+//! Parallel document parsing with an on-disk index of earlier results.
+//!
+//! Large document sets spend most of their start-up time reading files and running tree-sitter.
+//! Files are parsed across threads, and each result is remembered against the file's size and
+//! modification time, with a hash of its contents as a fallback when only the time has changed.
+//! A later launch takes unchanged files from the index without reading them at all.
+
+use crate::atomic;
+use crate::formats::Format;
+use crate::input::{self, SyntaxTree};
+use crate::section::{Section, SectionId};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+/// Parsed sections of previously seen files, stored as JSON in the user's cache directory.
+#[derive(Default, Serialize, Deserialize)]
+pub struct ParseIndex {
+    /// Version of asterism that wrote the index; any other version's entries are discarded.
+    version: String,
+    entries: HashMap<String, Entry>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    format: String,
+    len: u64,
+    modified: u128,
+    hash: u64,
+    sections: Vec<CachedSection>,
+}
+
+/// The parts of a section that parsing a document produces.
+#[derive(Serialize, Deserialize)]
+struct CachedSection {
+    title: String,
+    level: usize,
+    line_start: i64,
+    line_end: i64,
+    column_start: i64,
+    column_end: i64,
+    byte_start: usize,
+    byte_end: usize,
+    parent_index: Option<usize>,
+    children_indices: Vec<usize>,
+}
+
+/// What is known about a file on disk before its contents are read.
+struct Stamp {
+    len: u64,
+    modified: u128,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: modified.as_nanos(),
+        })
+    }
+}
+
+fn hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+impl ParseIndex {
+    /// Where the index is kept: `asterism/index.json` under `$XDG_CACHE_HOME` or `~/.cache`.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        let nonempty = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        nonempty("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| nonempty("HOME").map(|home| Path::new(&home).join(".cache")))
+            .or_else(|| nonempty("LOCALAPPDATA").map(PathBuf::from))
+            .map(|cache| cache.join("asterism").join("index.json"))
+    }
+
+    /// Read the index at `path`, starting afresh if it is missing, unreadable or out of date.
+    #[must_use]
+    pub fn load(path: PathBuf) -> Self {
+        let version = env!("CARGO_PKG_VERSION");
+        let mut index = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|index| index.version == version)
+            .unwrap_or_default();
+        index.version = version.to_string();
+        index.path = Some(path);
+        index
+    }
+
+    /// Write the index back if parsing added to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory or file cannot be written.
+    pub fn save(&mut self) -> io::Result<()> {
+        let Some(path) = self.path.as_ref().filter(|_| self.changed) else {
+            return Ok(());
+        };
+        // Forget files that no longer exist, so the index does not grow without bound
+        self.entries.retain(|file, _| Path::new(file).exists());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(self).map_err(io::Error::other)?;
+        atomic::write_file(path, json.as_bytes())?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Sections remembered for a file, if it has not changed since they were parsed.
+    ///
+    /// The file is read only when its size matches but its modification time does not.
+    fn lookup(&mut self, path: &Path, format: &dyn Format) -> Option<Vec<Section>> {
+        let stamp = Stamp::of(path)?;
+        let entry = self.entries.get_mut(&key(path))?;
+        if entry.format != format.name() || entry.len != stamp.len {
+            return None;
+        }
+        if entry.modified != stamp.modified {
+            let content = fs::read_to_string(path).ok()?;
+            if hash(&content) != entry.hash {
+                return None;
+            }
+            entry.modified = stamp.modified;
+            self.changed = true;
+        }
+
+        let file_path = path.to_string_lossy().to_string();
+        Some(
+            entry
+                .sections
+                .iter()
+                .map(|cached| Section {
+                    title: cached.title.clone(),
+                    level: cached.level,
+                    line_start: cached.line_start,
+                    line_end: cached.line_end,
+                    column_start: cached.column_start,
+                    column_end: cached.column_end,
+                    byte_start: cached.byte_start,
+                    byte_end: cached.byte_end,
+                    file_path: file_path.clone(),
+                    id: SectionId::default(),
+                    parent_index: cached.parent_index,
+                    children_indices: cached.children_indices.clone(),
+                    section_content: None,
+                    hunk: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Remember freshly parsed sections; those carrying diff or conflict details are not kept.
+    fn insert(&mut self, path: &Path, format: &dyn Format, content: &str, sections: &[Section]) {
+        let plain = sections.iter().all(|section| section.hunk.is_none());
+        let Some(stamp) = Stamp::of(path).filter(|_| plain) else {
+            return;
+        };
+        let sections = sections
+            .iter()
+            .map(|section| CachedSection {
+                title: section.title.clone(),
+                level: section.level,
+                line_start: section.line_start,
+                line_end: section.line_end,
+                column_start: section.column_start,
+                column_end: section.column_end,
+                byte_start: section.byte_start,
+                byte_end: section.byte_end,
+                parent_index: section.parent_index,
+                children_indices: section.children_indices.clone(),
+            })
+            .collect();
+        self.entries.insert(
+            key(path),
+            Entry {
+                format: format.name().to_string(),
+                len: stamp.len,
+                modified: stamp.modified,
+                hash: hash(content),
+                sections,
+            },
+        );
+        self.changed = true;
+    }
+}
+
+/// What parsing a document produced.
+pub struct Parsed {
+    /// Sections found in the document.
+    pub sections: Vec<Section>,
+    /// Syntax tree the sections came from, kept so the first save can reparse incrementally.
+    /// Absent for documents taken from the index and formats that parse without a tree.
+    pub tree: Option<SyntaxTree>,
+}
+
+/// Parse documents across threads, taking unchanged files from `index` when one is given.
+///
+/// `progress` is called with the number of documents done and the total as each finishes.
+/// Results come back in the order the documents were given.
+pub fn parse_documents(
+    documents: &[(PathBuf, Arc<dyn Format>)],
+    mut index: Option<&mut ParseIndex>,
+    progress: &(dyn Fn(usize, usize) + Sync),
+) -> Vec<io::Result<Parsed>> {
+    let total = documents.len();
+    let mut results: Vec<Option<io::Result<Parsed>>> = documents
+        .iter()
+        .map(|(path, format)| {
+            let index = index.as_deref_mut()?;
+            let sections = index.lookup(path, format.as_ref())?;
+            Some(Ok(Parsed {
+                sections,
+                tree: None,
+            }))
+        })
+        .collect();
+
+    let done = AtomicUsize::new(results.iter().filter(|result| result.is_some()).count());
+    let missing: Vec<usize> = (0..total).filter(|&i| results[i].is_none()).collect();
+    let parsed: Vec<_> = missing
+        .into_par_iter()
+        .map(|i| {
+            let (path, format) = &documents[i];
+            let parsed = fs::read_to_string(path).and_then(|content| {
+                if !format.parses_tree() {
+                    let sections = format.parse(&content, path)?;
+                    return Ok((
+                        content,
+                        Parsed {
+                            sections,
+                            tree: None,
+                        },
+                    ));
+                }
+                let (sections, tree) =
+                    input::reparse_sections(&content, path, format.as_ref(), None)?;
+                let tree = Some(tree);
+                Ok((content, Parsed { sections, tree }))
+            });
+            progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+            (i, parsed)
+        })
+        .collect();
+
+    for (i, parsed) in parsed {
+        let (path, format) = &documents[i];
+        results[i] = Some(parsed.map(|(content, parsed)| {
+            if let Some(index) = index.as_deref_mut() {
+                index.insert(path, format.as_ref(), &content, &parsed.sections);
+            }
+            parsed
+        }));
+    }
+    results.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+#[path = "tests/index.rs"]
+mod tests;
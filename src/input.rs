@@ -1,71 +1,159 @@
 //! Document discovery and section extraction using tree-sitter.
 //!
-//! This module handles finding markdown files in the filesystem and parsing
-//! them with tree-sitter queries to extract section hierarchies.
+//! This module handles finding markdown files in the filesystem, honouring ignore files and
+//! discovery globs, and parsing them with tree-sitter queries to extract section hierarchies.
 
 use crate::formats::Format;
-use crate::section::Section;
+use crate::section::{Section, SectionId};
+use facet::Facet;
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
 use streaming_iterator::StreamingIterator;
-use tree_sitter::{Parser, Query, QueryCursor};
+use tree_sitter::{InputEdit, Parser, Point, Query, QueryCursor, Tree};
+
+/// Directories never scanned, whether or not an ignore file lists them.
+const SKIPPED_DIRS: [&str; 4] = ["target", "dist", ".git", "node_modules"];
+
+/// Which files directory scans pick up, from the `[discovery]` table of asterism.toml.
+#[derive(Facet, Clone, Debug)]
+pub struct Discovery {
+    /// Globs a file must match one of to be opened (e.g. `"docs/**"`); none admits every file.
+    #[facet(default)]
+    pub include: Vec<String>,
+    /// Globs of files and directories to skip, on top of those ignore files list.
+    #[facet(default)]
+    pub exclude: Vec<String>,
+    /// Levels of subdirectories to descend into; 0 reads only the directory's own files.
+    #[facet(default)]
+    pub max_depth: Option<usize>,
+    /// Skip paths listed in `.gitignore`, `.ignore` and git's exclude files.
+    #[facet(default = true)]
+    pub respect_ignore: bool,
+    /// Descend into symlinked directories, skipping any link that loops back on itself.
+    #[facet(default = true)]
+    pub follow_symlinks: bool,
+}
+
+impl Default for Discovery {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: None,
+            respect_ignore: true,
+            follow_symlinks: true,
+        }
+    }
+}
 
 /// Find documents matching the given extensions.
 ///
-/// If paths is empty, scans the current directory recursively.
-/// Skips common build/dependency directories.
+/// If paths is empty, scans the current directory recursively. Files named explicitly are
+/// taken as they are; directories are scanned under the `discovery` rules, and each document
+/// is returned once however many symlinks lead to it.
 ///
 /// # Errors
 ///
-/// Returns an error if directory traversal fails.
-pub fn find_documents(paths: Vec<PathBuf>, extensions: &[String]) -> io::Result<Vec<PathBuf>> {
+/// Returns an error if an include or exclude glob is invalid.
+pub fn find_documents(
+    paths: Vec<PathBuf>,
+    extensions: &[String],
+    discovery: &Discovery,
+) -> io::Result<Vec<PathBuf>> {
     if paths.is_empty() {
-        find_in_directory(Path::new("."), extensions)
+        find_in_directory(Path::new("."), extensions, discovery)
     } else {
         let mut results = Vec::new();
         for path in paths {
             if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if extensions
-                        .iter()
-                        .any(|e| e == ext.to_string_lossy().as_ref())
-                    {
-                        results.push(path);
-                    }
+                if has_extension(&path, extensions) {
+                    results.push(path);
                 }
             } else if path.is_dir() {
-                results.extend(find_in_directory(&path, extensions)?);
+                results.extend(find_in_directory(&path, extensions, discovery)?);
             }
         }
         Ok(results)
     }
 }
 
-fn find_in_directory(dir: &Path, extensions: &[String]) -> io::Result<Vec<PathBuf>> {
-    let mut results = Vec::new();
+/// Deepest folder containing every file.
+#[must_use]
+pub fn common_directory(files: &[&Path]) -> PathBuf {
+    let mut dirs = files
+        .iter()
+        .map(|file| file.parent().unwrap_or(Path::new("")));
+    let mut common = dirs.next().map(Path::to_path_buf).unwrap_or_default();
+    for dir in dirs {
+        while !dir.starts_with(&common) && common.pop() {}
+    }
+    common
+}
 
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension().is_some_and(|ext| {
+        extensions
+            .iter()
+            .any(|e| e == ext.to_string_lossy().as_ref())
+    })
+}
 
-            if path.is_dir() {
-                if let Some(name) = path.file_name() {
-                    let name_str = name.to_string_lossy();
-                    if ["target", "dist", ".git", "node_modules"].contains(&name_str.as_ref()) {
-                        continue;
-                    }
-                }
-                results.extend(find_in_directory(&path, extensions)?);
-            } else if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if extensions
-                        .iter()
-                        .any(|e| e == ext.to_string_lossy().as_ref())
-                    {
-                        results.push(path);
-                    }
-                }
+fn find_in_directory(
+    dir: &Path,
+    extensions: &[String],
+    discovery: &Discovery,
+) -> io::Result<Vec<PathBuf>> {
+    let invalid = |glob: &str, e: ignore::Error| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid glob {glob:?}: {e}"),
+        )
+    };
+    let mut overrides = OverrideBuilder::new(dir);
+    for glob in &discovery.include {
+        overrides.add(glob).map_err(|e| invalid(glob, e))?;
+    }
+    for glob in &discovery.exclude {
+        overrides
+            .add(&format!("!{glob}"))
+            .map_err(|e| invalid(glob, e))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    let respect = discovery.respect_ignore;
+    let walker = WalkBuilder::new(dir)
+        .hidden(false)
+        .parents(respect)
+        .ignore(respect)
+        .git_ignore(respect)
+        .git_global(respect)
+        .git_exclude(respect)
+        .max_depth(discovery.max_depth.map(|depth| depth + 1))
+        .follow_links(discovery.follow_symlinks)
+        .overrides(overrides)
+        .sort_by_file_name(Ord::cmp)
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !(is_dir && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        })
+        .build();
+
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    // Unreadable directories and symlink loops are reported as errors and skipped
+    for entry in walker.flatten() {
+        let path = entry.into_path();
+        if path.is_file() && has_extension(&path, extensions) {
+            let target = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if seen.insert(target) {
+                results.push(path);
             }
         }
     }
@@ -81,57 +169,180 @@ fn find_in_directory(dir: &Path, extensions: &[String]) -> io::Result<Vec<PathBu
 /// # Errors
 ///
 /// Returns an error if file reading or parsing fails.
-pub fn extract_sections<F: Format>(file_path: &Path, format: &F) -> io::Result<Vec<Section>> {
+pub fn extract_sections<F: Format + ?Sized>(
+    file_path: &Path,
+    format: &F,
+) -> io::Result<Vec<Section>> {
     let content = fs::read_to_string(file_path)?;
+    parse_sections(&content, file_path, format)
+}
+
+/// Extract sections from in-memory content using tree-sitter.
+///
+/// Separating parsing from reading lets content arrive from stdin or an editor buffer, with
+/// `file_path` recorded on each section as the write target.
+///
+/// # Errors
+///
+/// Returns an error if the format's language or queries fail to load, or parsing fails.
+pub fn parse_sections<F: Format + ?Sized>(
+    content: &str,
+    file_path: &Path,
+    format: &F,
+) -> io::Result<Vec<Section>> {
+    let tree = parse_tree(content, format, None)?;
+    sections_from_tree(content, file_path, format, &tree)
+}
+
+/// A document's syntax tree kept with the text it was parsed from.
+///
+/// Handing it back to [`reparse_sections`] after the file changes lets tree-sitter reuse every
+/// part of the tree the change did not touch.
+pub struct SyntaxTree {
+    tree: Tree,
+    source: String,
+}
+
+/// Parse `content` into sections, incrementally from the file's previous tree if it has one.
+///
+/// Returns the sections along with the tree to pass in on the next change.
+///
+/// # Errors
+///
+/// Returns an error if the format's language or queries fail to load, or parsing fails.
+pub fn reparse_sections<F: Format + ?Sized>(
+    content: &str,
+    file_path: &Path,
+    format: &F,
+    previous: Option<SyntaxTree>,
+) -> io::Result<(Vec<Section>, SyntaxTree)> {
+    let old = previous.map(|SyntaxTree { mut tree, source }| {
+        tree.edit(&input_edit(&source, content));
+        tree
+    });
+    let tree = parse_tree(content, format, old.as_ref())?;
+    let sections = sections_from_tree(content, file_path, format, &tree)?;
+    let source = content.to_string();
+    Ok((sections, SyntaxTree { tree, source }))
+}
+
+/// The edit turning `old` into `new`: the span between their common prefix and common suffix.
+fn input_edit(old: &str, new: &str) -> InputEdit {
+    let (old, new) = (old.as_bytes(), new.as_bytes());
+    let start = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[start..]
+        .iter()
+        .rev()
+        .zip(new[start..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    InputEdit {
+        start_byte: start,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point(old, start),
+        old_end_position: point(old, old_end),
+        new_end_position: point(new, new_end),
+    }
+}
+
+/// Row and byte column of an offset into `text`.
+fn point(text: &[u8], byte: usize) -> Point {
+    let before = &text[..byte];
+    let row = before.split(|&b| b == b'\n').count() - 1;
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    Point::new(row, byte - line_start)
+}
 
+/// Parse `content` with the format's grammar, reusing `old` after it has been edited to match.
+fn parse_tree<F: Format + ?Sized>(
+    content: &str,
+    format: &F,
+    old: Option<&Tree>,
+) -> io::Result<Tree> {
     let mut parser = Parser::new();
     parser
         .set_language(&format.language())
         .map_err(|e| io::Error::other(format!("Language error: {e}")))?;
 
-    let tree = parser
-        .parse(&content, None)
-        .ok_or_else(|| io::Error::other("Parse failed"))?;
+    parser
+        .parse(content, old)
+        .ok_or_else(|| io::Error::other("Parse failed"))
+}
+
+/// Compiled queries by format name and query source.
+type QueryCache = HashMap<(String, String), Arc<Query>>;
+
+/// Queries compiled so far, shared by every parse in the process.
+static QUERIES: LazyLock<Mutex<QueryCache>> = LazyLock::new(Mutex::default);
 
-    let section_query = Query::new(&format.language(), format.section_query())
-        .map_err(|e| io::Error::other(format!("Query error: {e}")))?;
+/// A format's query, compiled the first time any document in that format is parsed.
+fn compiled_query<F: Format + ?Sized>(format: &F, source: &str) -> io::Result<Arc<Query>> {
+    let key = (format.name().to_string(), source.to_string());
+    if let Some(query) = QUERIES
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned())
+    {
+        return Ok(query);
+    }
+    let query = Arc::new(
+        Query::new(&format.language(), source)
+            .map_err(|e| io::Error::other(format!("Query error: {e}")))?,
+    );
+    if let Ok(mut cache) = QUERIES.lock() {
+        cache.insert(key, Arc::clone(&query));
+    }
+    Ok(query)
+}
 
-    let title_query = Query::new(&format.language(), format.title_query())
-        .map_err(|e| io::Error::other(format!("Query error: {e}")))?;
+/// Sections located by the format's queries in an already parsed tree.
+fn sections_from_tree<F: Format + ?Sized>(
+    content: &str,
+    file_path: &Path,
+    format: &F,
+    tree: &Tree,
+) -> io::Result<Vec<Section>> {
+    let (section_query, title_query) = match format.compiled_queries() {
+        Some(queries) => queries,
+        None => (
+            compiled_query(format, format.section_query())?,
+            compiled_query(format, format.title_query())?,
+        ),
+    };
 
-    // Collect all heading nodes by traversing the entire tree
-    let mut headings: Vec<tree_sitter::Node> = Vec::new();
+    // Collect all heading nodes (with their level capture, if declared) across the tree
+    let capture_names = section_query.capture_names();
+    let mut headings: Vec<(tree_sitter::Node, Option<tree_sitter::Node>)> = Vec::new();
+    let mut seen = HashSet::new();
     let mut cursor = QueryCursor::new();
     let mut matches = cursor.matches(&section_query, tree.root_node(), content.as_bytes());
 
     while let Some(m) = matches.next() {
-        if let Some(c) = m.captures.first() {
-            headings.push(c.node);
+        let named = |name: &str| {
+            m.captures
+                .iter()
+                .find(|c| capture_names[c.index as usize] == name)
+                .map(|c| c.node)
+        };
+        let level_node = format.level_capture().and_then(named);
+        if let Some(heading) = named("heading").or_else(|| m.captures.first().map(|c| c.node)) {
+            // A query may match one heading several times (once per captured child)
+            if seen.insert(heading.id()) {
+                headings.push((heading, level_node));
+            }
         }
     }
 
     let mut sections = Vec::new();
 
-    for (i, heading) in headings.iter().enumerate() {
-        // Determine level from the heading marker child node
-        let mut level = 1;
-        let mut heading_cursor = heading.walk();
-        if heading_cursor.goto_first_child() {
-            loop {
-                let node = heading_cursor.node();
-                let kind = node.kind();
-                // Match atx_h1_marker, atx_h2_marker, etc.
-                if kind.starts_with("atx_h") && kind.ends_with("_marker") {
-                    if let Some(level_char) = kind.chars().nth(5) {
-                        level = level_char.to_digit(10).unwrap_or(1) as usize;
-                    }
-                    break;
-                }
-                if !heading_cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-        }
+    for (i, (heading, level_node)) in headings.iter().enumerate() {
+        let level = format.heading_level(*heading, *level_node, content);
 
         // Extract title using query
         let mut title_cursor = QueryCursor::new();
@@ -152,14 +363,14 @@ pub fn extract_sections<F: Format>(file_path: &Path, format: &F) -> io::Result<V
         let byte_start = heading.end_byte();
         let byte_end = headings
             .get(i + 1)
-            .map_or(content.len(), tree_sitter::Node::start_byte);
+            .map_or(content.len(), |(next, _)| next.start_byte());
 
         // Calculate line coordinates
         // Around line 100-110 in extract_sections
         let line_start = i64::try_from(heading.end_position().row).unwrap_or(0);
         let line_end = headings.get(i + 1).map_or(
             i64::try_from(content.lines().count()).unwrap_or(0),
-            |next| i64::try_from(next.start_position().row).unwrap_or(0),
+            |(next, _)| i64::try_from(next.start_position().row).unwrap_or(0),
         );
 
         let column_start = i64::try_from(heading.start_position().column).unwrap_or(0);
@@ -175,12 +386,11 @@ pub fn extract_sections<F: Format>(file_path: &Path, format: &F) -> io::Result<V
             byte_start,
             byte_end,
             file_path: file_path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: Vec::new(),
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         });
     }
 
@@ -190,7 +400,34 @@ pub fn extract_sections<F: Format>(file_path: &Path, format: &F) -> io::Result<V
     Ok(sections)
 }
 
-fn build_hierarchy(sections: &mut [Section]) {
+/// Read the heading level from an `atx_hN_marker`-style child of the heading node.
+///
+/// Defaults to 1 when the heading carries no numbered marker.
+#[must_use]
+pub fn marker_level(heading: tree_sitter::Node) -> usize {
+    let mut level = 1;
+    let mut heading_cursor = heading.walk();
+    if heading_cursor.goto_first_child() {
+        loop {
+            let node = heading_cursor.node();
+            let kind = node.kind();
+            // Match atx_h1_marker, atx_h2_marker, etc.
+            if kind.starts_with("atx_h") && kind.ends_with("_marker") {
+                if let Some(level_char) = kind.chars().nth(5) {
+                    level = level_char.to_digit(10).unwrap_or(1) as usize;
+                }
+                break;
+            }
+            if !heading_cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+    level
+}
+
+/// Link sections to their parents and children from their levels, in document order.
+pub fn build_hierarchy(sections: &mut [Section]) {
     let mut stack: Vec<(usize, usize)> = Vec::new(); // (index, level)
 
     for i in 0..sections.len() {
@@ -0,0 +1,400 @@
+//! Keybindings for the section list, mapping key presses to the actions they trigger.
+//!
+//! Bindings are key sequences, so vim-style chords such as `gg` sit alongside single keys. The
+//! default keymap reproduces the original hardcoded keys; the `vim` preset adds `hjkl`-style
+//! navigation and moves that avoid Ctrl and Shift arrows, which terminals and tmux often claim.
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+/// Something a key press can do in the section list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Quit, unless edits are unsaved.
+    Quit,
+    /// Select the previous section.
+    Up,
+    /// Select the next section.
+    Down,
+    /// Select the parent section.
+    Parent,
+    /// Select the next section nested beneath this one.
+    Child,
+    /// Select the previous section at the same level.
+    PrevSibling,
+    /// Select the next section at the same level.
+    NextSibling,
+    /// Select the first section.
+    First,
+    /// Select the last section.
+    Last,
+    /// Select the first section at the same level.
+    FirstAtLevel,
+    /// Select the last section at the same level.
+    LastAtLevel,
+    /// Start moving the section, or move it up.
+    MoveUp,
+    /// Start moving the section, or move it down.
+    MoveDown,
+    /// Start moving the section, or raise its heading level.
+    MoveIn,
+    /// Start moving the section, or lower its heading level.
+    MoveOut,
+    /// Move the section being moved to the top of its document.
+    MoveToTop,
+    /// Move the section being moved to the bottom of its document.
+    MoveToBottom,
+    /// Cancel a move.
+    Cancel,
+    /// Open the section in the editor or the diff view.
+    Open,
+    /// Start typing a `:` command.
+    Command,
+    /// Accept the hunk.
+    Accept,
+    /// Reject the hunk.
+    Reject,
+    /// Edit the hunk's lines.
+    Edit,
+    /// Undo the hunk decision or conflict resolution.
+    Undo,
+    /// Toggle ordering diffed files by churn.
+    SortByChurn,
+    /// Resolve the conflict to our side.
+    KeepOurs,
+    /// Resolve the conflict to their side.
+    KeepTheirs,
+    /// Resolve the conflict to both sides.
+    KeepBoth,
+    /// Resolve the conflict to the common ancestor.
+    KeepBase,
+}
+
+/// Actions by the names used in asterism.toml and `:map`.
+const ACTION_NAMES: &[(Action, &str)] = &[
+    (Action::Quit, "quit"),
+    (Action::Up, "up"),
+    (Action::Down, "down"),
+    (Action::Parent, "parent"),
+    (Action::Child, "child"),
+    (Action::PrevSibling, "prev-sibling"),
+    (Action::NextSibling, "next-sibling"),
+    (Action::First, "first"),
+    (Action::Last, "last"),
+    (Action::FirstAtLevel, "first-at-level"),
+    (Action::LastAtLevel, "last-at-level"),
+    (Action::MoveUp, "move-up"),
+    (Action::MoveDown, "move-down"),
+    (Action::MoveIn, "move-in"),
+    (Action::MoveOut, "move-out"),
+    (Action::MoveToTop, "move-to-top"),
+    (Action::MoveToBottom, "move-to-bottom"),
+    (Action::Cancel, "cancel"),
+    (Action::Open, "open"),
+    (Action::Command, "command"),
+    (Action::Accept, "accept"),
+    (Action::Reject, "reject"),
+    (Action::Edit, "edit"),
+    (Action::Undo, "undo"),
+    (Action::SortByChurn, "sort-by-churn"),
+    (Action::KeepOurs, "keep-ours"),
+    (Action::KeepTheirs, "keep-theirs"),
+    (Action::KeepBoth, "keep-both"),
+    (Action::KeepBase, "keep-base"),
+];
+
+impl Action {
+    /// Name used in asterism.toml and `:map`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        ACTION_NAMES
+            .iter()
+            .find(|(action, _)| *action == self)
+            .map_or("", |(_, name)| name)
+    }
+
+    /// Action with the given name.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTION_NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(action, _)| *action)
+    }
+}
+
+/// A key with the modifiers held while pressing it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    /// Key pressed.
+    pub code: KeyCode,
+    /// Ctrl, Alt and Shift as held; Shift is folded into the character for character keys.
+    pub modifiers: KeyModifiers,
+}
+
+/// Keys written by name rather than as the character they type.
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "pageup"),
+    (KeyCode::PageDown, "pagedown"),
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Esc, "esc"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Delete, "delete"),
+    (KeyCode::Char(' '), "space"),
+];
+
+impl KeyChord {
+    /// Chord for a key event, as it is looked up in a keymap.
+    #[must_use]
+    pub fn from_event(key: KeyEvent) -> Self {
+        let mut modifiers =
+            key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if matches!(key.code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self {
+            code: key.code,
+            modifiers,
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match KEY_NAMES.iter().find(|(code, _)| *code == self.code) {
+            Some((_, name)) => f.write_str(name),
+            None => match self.code {
+                KeyCode::Char(c) => write!(f, "{c}"),
+                code => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+/// Parse a key sequence such as `ctrl+up`, `G`, `gg` or `g g`.
+///
+/// Tokens are separated by spaces; each is a key name or character, optionally prefixed by
+/// `ctrl+`, `alt+` or `shift+`. A token of several characters that is not a key name types each
+/// character in turn.
+///
+/// # Errors
+///
+/// Returns an error if the sequence is empty or a token cannot be read as keys.
+pub fn parse_keys(keys: &str) -> io::Result<Vec<KeyChord>> {
+    let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidInput, reason);
+    let mut chords = Vec::new();
+    for token in keys.split_whitespace() {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = token;
+        while let Some((prefix, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+            modifiers |= match prefix.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid(format!("Unknown modifier in key {token:?}"))),
+            };
+            key = rest;
+        }
+
+        let named = KEY_NAMES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(key))
+            .map(|(code, _)| *code);
+        match named {
+            Some(code) => chords.push(KeyChord { code, modifiers }),
+            None if key.chars().count() == 1 || modifiers.is_empty() => {
+                // Shift is carried by the character itself
+                modifiers.remove(KeyModifiers::SHIFT);
+                chords.extend(key.chars().map(|c| KeyChord {
+                    code: KeyCode::Char(c),
+                    modifiers,
+                }));
+            }
+            None => return Err(invalid(format!("Unknown key {token:?}"))),
+        }
+    }
+    if chords.is_empty() {
+        return Err(invalid("No keys given".to_string()));
+    }
+    Ok(chords)
+}
+
+/// Key sequences bound to actions, with the keys of a sequence typed so far.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Vec<KeyChord>, Action)>,
+    pending: Vec<KeyChord>,
+}
+
+/// Keys bound in every keymap, matching asterism's original controls.
+const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("q", Action::Quit),
+    ("up", Action::Up),
+    ("down", Action::Down),
+    ("left", Action::Parent),
+    ("h", Action::Parent),
+    ("right", Action::Child),
+    ("l", Action::Child),
+    ("shift+up", Action::PrevSibling),
+    ("shift+down", Action::NextSibling),
+    ("home", Action::First),
+    ("end", Action::Last),
+    ("shift+home", Action::FirstAtLevel),
+    ("shift+end", Action::LastAtLevel),
+    ("ctrl+up", Action::MoveUp),
+    ("ctrl+down", Action::MoveDown),
+    ("ctrl+left", Action::MoveIn),
+    ("ctrl+h", Action::MoveIn),
+    ("ctrl+right", Action::MoveOut),
+    ("ctrl+l", Action::MoveOut),
+    ("ctrl+home", Action::MoveToTop),
+    ("ctrl+end", Action::MoveToBottom),
+    ("esc", Action::Cancel),
+    ("enter", Action::Open),
+    (":", Action::Command),
+    ("a", Action::Accept),
+    ("r", Action::Reject),
+    ("e", Action::Edit),
+    ("u", Action::Undo),
+    ("s", Action::SortByChurn),
+    ("o", Action::KeepOurs),
+    ("t", Action::KeepTheirs),
+    ("b", Action::KeepBoth),
+    ("B", Action::KeepBase),
+];
+
+/// Keys the `vim` preset adds to the defaults.
+const VIM_BINDINGS: &[(&str, Action)] = &[
+    ("k", Action::Up),
+    ("j", Action::Down),
+    ("{", Action::PrevSibling),
+    ("}", Action::NextSibling),
+    ("gg", Action::First),
+    ("G", Action::Last),
+    ("K", Action::MoveUp),
+    ("J", Action::MoveDown),
+    ("H", Action::MoveIn),
+    ("L", Action::MoveOut),
+];
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_bindings(DEFAULT_BINDINGS)
+    }
+}
+
+impl Keymap {
+    fn from_bindings(bindings: &[(&str, Action)]) -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+            pending: Vec::new(),
+        };
+        for &(keys, action) in bindings {
+            if let Ok(keys) = parse_keys(keys) {
+                keymap.bindings.push((keys, action));
+            }
+        }
+        keymap
+    }
+
+    /// Keymap for a preset: `default` or `vim`.
+    #[must_use]
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "vim" => Some(Self::from_bindings(
+                &[DEFAULT_BINDINGS, VIM_BINDINGS].concat(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Keymap from a preset with the configured keys bound over it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the preset, key or action that is not recognised.
+    pub fn from_config(preset: Option<&str>, keys: &HashMap<String, String>) -> io::Result<Self> {
+        let preset = preset.unwrap_or("default");
+        let mut keymap = Self::preset(preset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown keymap preset {preset:?}"),
+            )
+        })?;
+        // Sorted so that a sequence and its prefix bind the same way on every run
+        let mut keys: Vec<_> = keys.iter().collect();
+        keys.sort();
+        for (keys, action) in keys {
+            keymap.bind(keys, action)?;
+        }
+        Ok(keymap)
+    }
+
+    /// Bind a key sequence to the named action, replacing the sequence's previous binding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the keys cannot be parsed or the action is unknown.
+    pub fn bind(&mut self, keys: &str, action: &str) -> io::Result<()> {
+        let chords = parse_keys(keys)?;
+        let action = Action::from_name(action).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown action {action:?}"),
+            )
+        })?;
+        self.bindings.retain(|(bound, _)| *bound != chords);
+        self.bindings.push((chords, action));
+        Ok(())
+    }
+
+    /// Action completed by a key press, if any.
+    ///
+    /// A press that begins a longer sequence is held until the sequence completes or breaks off;
+    /// a press that breaks one off is looked up on its own.
+    pub fn press(&mut self, key: KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.pending.push(chord);
+        loop {
+            if let Some((_, action)) = self.bindings.iter().find(|(keys, _)| *keys == self.pending)
+            {
+                self.pending.clear();
+                return Some(*action);
+            }
+            let continues = self.bindings.iter().any(|(keys, _)| {
+                keys.len() > self.pending.len() && keys.starts_with(&self.pending)
+            });
+            if continues {
+                return None;
+            }
+            if self.pending.len() == 1 {
+                self.pending.clear();
+                return None;
+            }
+            self.pending = vec![chord];
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/keymap.rs"]
+mod tests;
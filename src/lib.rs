@@ -2,13 +2,33 @@
 //!
 //! asterism uses ratatui to provide hierarchical navigation of markdown documents
 //! and edtui to emulate a vim editor for section content editing.
+//!
+//! Programs that only need to read and rewrite documents can use [`document::Document`] with
+//! default features turned off, leaving out the terminal interface and its dependencies.
 #![allow(clippy::multiple_crate_versions)]
 
+#[cfg(feature = "tui")]
 pub mod app_state;
+pub mod atomic;
 pub mod config;
+pub mod diff_stats;
+pub mod diff_view;
+pub mod document;
 pub mod edit_plan;
 pub mod formats;
+pub mod git;
+#[cfg(feature = "tui")]
 pub mod highlight;
+pub mod index;
 pub mod input;
+#[cfg(feature = "tui")]
+pub mod keymap;
+pub mod patch;
+pub mod reflow;
 pub mod section;
+#[cfg(feature = "tui")]
+pub mod theme;
+#[cfg(feature = "tui")]
 pub mod ui;
+#[cfg(feature = "tui")]
+pub mod watch;
@@ -1,8 +1,15 @@
 //! asterism: A tree-sitter document section editor.
 #![allow(clippy::multiple_crate_versions)]
 
-use asterism::{app_state, config, edit_plan, formats, input, ui};
-use clap::Parser;
+use asterism::formats::registry::FormatRegistry;
+use asterism::formats::Format;
+use asterism::index::{self, ParseIndex};
+use asterism::keymap::Keymap;
+use asterism::section::Section;
+use asterism::theme::{self, Theme};
+use asterism::watch::FileWatcher;
+use asterism::{app_state, config, edit_plan, git, input, ui};
+use clap::{Parser, Subcommand};
 use edtui::EditorEventHandler;
 use ratatui::crossterm::{
     event::{self, Event, KeyCode},
@@ -10,14 +17,29 @@ use ratatui::crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the event loop checks for file changes in watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Parser)]
+#[allow(clippy::struct_excessive_bools)]
 #[command(name = "asterism")]
 #[command(about = "Hyperbolic navigation for tree data", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Read settings from this file alone instead of discovering asterism.toml files
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
     /// Files or directories to edit
     #[arg(value_name = "PATH")]
     paths: Vec<PathBuf>,
@@ -30,93 +52,360 @@ struct Args {
     #[arg(long, short = 'e', value_name = "EXT")]
     ext: Vec<String>,
 
-    /// Parse difftastic JSON output (from stdin or file)
+    /// Only open files matching this glob when scanning directories (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files and directories matching this glob when scanning (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Levels of subdirectories to scan (0 reads only the directory's own files)
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Also scan files listed in .gitignore and .ignore
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// Parse every document afresh instead of reusing the parse index
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Parse all input with this format instead of detecting it (e.g. markdown, difftastic)
+    #[arg(long, short = 'f', value_name = "FORMAT")]
+    format: Option<String>,
+
+    /// Parse difftastic JSON output (shorthand for --format difftastic)
     #[arg(long, short = 'd')]
     difft: bool,
 
-    /// Read difftastic output from stdin
+    /// Original file (or directory of originals) the diff compares against, shown beside hunks
+    #[arg(long, value_name = "PATH")]
+    lhs: Option<PathBuf>,
+
+    /// Review the unstaged changes of a git repository (default: the current one) through difft,
+    /// producing a patch for `git apply --cached`
+    #[arg(long, value_name = "REPO", num_args = 0..=1, default_missing_value = ".")]
+    git: Option<PathBuf>,
+
+    /// Write the staged patch to this file instead of stdout (git mode)
+    #[arg(long, value_name = "FILE")]
+    patch: Option<PathBuf>,
+
+    /// Read input from stdin (detected by content, falling back to difftastic)
     #[arg(long)]
     stdin: bool,
+
+    /// Reload documents when other programs change them on disk
+    #[arg(long, short = 'w')]
+    watch: bool,
 }
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
-    let mut cfg = config::Config::load();
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
 
-    // Override config with command line args
-    if !args.ext.is_empty() {
-        cfg.file_extensions = args.ext;
-    }
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective settings and the files they were read from
+    Show,
+}
 
-    // Handle difftastic mode
-    if args.difft || args.stdin {
-        let json_content = if args.stdin {
-            // Read from stdin
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
-            buffer
-        } else if args.paths.len() == 1 {
-            // Read from file
-            std::fs::read_to_string(&args.paths[0])?
+/// Sections gathered from all inputs, with the files and formats they belong to.
+#[derive(Default)]
+struct Loaded {
+    files: Vec<PathBuf>,
+    formats: HashMap<String, Arc<dyn Format>>,
+    sections: Vec<Section>,
+    unparsed: HashSet<String>,
+    trees: HashMap<String, input::SyntaxTree>,
+}
+
+impl Loaded {
+    /// Record parsed sections against their source, or against the compared files for diffs.
+    fn add(&mut self, source: &Path, format: &Arc<dyn Format>, sections: Vec<Section>) {
+        if format.is_diff() {
+            for section in &sections {
+                if self
+                    .formats
+                    .insert(section.file_path.clone(), Arc::clone(format))
+                    .is_none()
+                {
+                    self.files.push(PathBuf::from(&section.file_path));
+                }
+            }
         } else {
-            eprintln!("In difftastic mode, provide either --stdin or a single JSON file");
-            return Ok(());
-        };
+            self.formats
+                .insert(source.to_string_lossy().to_string(), Arc::clone(format));
+            self.files.push(source.to_path_buf());
+        }
+        self.sections.extend(sections);
+    }
 
-        let sections = formats::difftastic::parse_difftastic_json(&json_content)?;
+    /// Record a document whose sections are parsed once its folder is unfolded.
+    fn defer(&mut self, source: &Path, format: &Arc<dyn Format>) {
+        let file = source.to_string_lossy().to_string();
+        self.formats.insert(file.clone(), Arc::clone(format));
+        self.files.push(source.to_path_buf());
+        self.unparsed.insert(file);
+    }
 
-        if sections.is_empty() {
-            eprintln!("No changes found in difftastic output");
-            return Ok(());
+    /// Parse documents across threads, showing progress when stderr is a terminal.
+    ///
+    /// Past `lazy_threshold` documents, only those in the top folder are parsed now and the rest
+    /// are deferred until their folder is unfolded. New results are saved to `index`.
+    fn parse(
+        &mut self,
+        documents: &[(PathBuf, Arc<dyn Format>)],
+        mut index: Option<&mut ParseIndex>,
+        lazy_threshold: usize,
+    ) {
+        let paths: Vec<&Path> = documents.iter().map(|(doc, _)| doc.as_path()).collect();
+        let root = input::common_directory(&paths);
+        let (eager, deferred): (Vec<_>, Vec<_>) =
+            documents.iter().cloned().partition(|(doc, _)| {
+                documents.len() <= lazy_threshold || doc.parent() == Some(root.as_path())
+            });
+
+        let terminal = io::stderr().is_terminal();
+        let progress = |done: usize, total: usize| {
+            if terminal {
+                eprint!("\rParsing {done}/{total} documents");
+            }
+        };
+        let results = index::parse_documents(&eager, index.as_deref_mut(), &progress);
+        if terminal && !eager.is_empty() {
+            eprint!("\r\x1b[2K");
         }
 
-        // Extract unique file paths from sections
-        let files: Vec<PathBuf> = sections
-            .iter()
-            .map(|s| PathBuf::from(&s.file_path))
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
+        for ((doc, format), result) in eager.iter().zip(results) {
+            match result {
+                Ok(parsed) => {
+                    if let Some(tree) = parsed.tree {
+                        self.trees.insert(doc.to_string_lossy().to_string(), tree);
+                    }
+                    self.add(doc, format, parsed.sections);
+                }
+                Err(e) => eprintln!("Skipping {}: {e}", doc.display()),
+            }
+        }
+        for (doc, format) in &deferred {
+            self.defer(doc, format);
+        }
+        if let Some(Err(e)) = index.map(ParseIndex::save) {
+            eprintln!("Could not save the parse index: {e}");
+        }
+    }
+}
 
-        let state = app_state::AppState::new(files, sections, cfg.wrap_width);
-        return run_tui(state, &cfg);
+/// Run a `config` subcommand.
+fn show_config(action: &ConfigAction, cfg: &config::Config, sources: &[PathBuf]) {
+    let ConfigAction::Show = action;
+    if sources.is_empty() {
+        println!("# No configuration files found, so every setting is a default");
+    }
+    for source in sources {
+        println!("# From {}", source.display());
     }
+    print!("{}", cfg.to_toml());
+}
 
-    // Normal markdown mode
-    let documents = input::find_documents(args.paths, &cfg.file_extensions)?;
+/// Apply command line options over the configuration.
+fn override_config(cfg: &mut config::Config, args: &mut Args) {
+    if !args.ext.is_empty() {
+        cfg.file_extensions = std::mem::take(&mut args.ext);
+    }
+    let discovery = &mut cfg.discovery;
+    discovery.include.append(&mut args.include);
+    discovery.exclude.append(&mut args.exclude);
+    discovery.max_depth = args.max_depth.or(discovery.max_depth);
+    discovery.respect_ignore &= !args.no_ignore;
+    cfg.parse_cache &= !args.no_cache;
+}
+
+/// Load the configuration, exiting with the error when a file cannot be read or parsed.
+fn load_config(explicit: Option<&Path>) -> (config::Config, Vec<PathBuf>) {
+    config::Config::load(explicit).unwrap_or_else(|e| {
+        // Parse errors are reports with the offending line marked, so print them as they are
+        eprintln!("{e}");
+        std::process::exit(1);
+    })
+}
 
-    if documents.is_empty() {
-        eprintln!("No matching files found");
+/// Read an edit plan saved by an earlier session.
+fn read_plan(path: &Path) -> io::Result<edit_plan::EditPlan> {
+    let file_content = std::fs::read_to_string(path)?;
+    serde_json::from_str(&file_content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn main() -> io::Result<()> {
+    let mut args = Args::parse();
+    let (mut cfg, sources) = load_config(args.config.as_deref());
+    if let Some(Command::Config { action }) = &args.command {
+        show_config(action, &cfg, &sources);
         return Ok(());
     }
 
-    let format = formats::markdown::MarkdownFormat;
-    let mut all_sections = Vec::new();
+    override_config(&mut cfg, &mut args);
 
-    for doc in &documents {
-        if let Ok(sections) = input::extract_sections(doc, &format) {
-            all_sections.extend(sections);
+    let registry = FormatRegistry::from_config(&cfg)?;
+
+    let forced = match args.format.as_deref() {
+        Some(name) => Some(registry.by_name(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown format '{name}' (available: {})",
+                    registry.names().join(", ")
+                ),
+            )
+        })?),
+        None if args.difft => registry.by_name("difftastic"),
+        None => None,
+    };
+
+    let mut loaded = Loaded::default();
+    let mut index = None;
+
+    let repo = args.git.as_deref().map(git::repo_root).transpose()?;
+
+    if let Some(repo) = &repo {
+        let json = git::difftastic_diff(repo)?;
+        let format = registry
+            .by_name("difftastic")
+            .ok_or_else(|| io::Error::other("difftastic format not registered"))?;
+
+        // Difftastic reports paths relative to the repository root
+        let mut sections = format.parse(&json, repo)?;
+        for section in &mut sections {
+            section.file_path = repo.join(&section.file_path).to_string_lossy().to_string();
         }
+        loaded.add(repo, &format, sections);
+    } else if args.stdin {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+
+        let Some(format) = forced
+            .or_else(|| registry.sniff(&buffer))
+            .or_else(|| registry.by_name("difftastic"))
+        else {
+            eprintln!("Could not detect the format of stdin, pass --format");
+            return Ok(());
+        };
+
+        let source = Path::new("-");
+        loaded.add(source, &format, format.parse(&buffer, source)?);
+    } else {
+        let documents = discover(args.paths, &cfg, &registry, forced.is_some())?;
+
+        if documents.is_empty() {
+            eprintln!("No matching files found");
+            return Ok(());
+        }
+
+        let documents: Vec<(PathBuf, Arc<dyn Format>)> = documents
+            .into_iter()
+            .filter_map(|doc| {
+                let format = forced.clone().or_else(|| registry.for_path(&doc))?;
+                Some((doc, format))
+            })
+            .collect();
+        index = open_index(&cfg);
+        loaded.parse(&documents, index.as_mut(), cfg.lazy_threshold);
     }
 
-    if all_sections.is_empty() {
+    if loaded.sections.is_empty() && loaded.unparsed.is_empty() {
         eprintln!("No sections found in documents");
         return Ok(());
     }
 
-    let mut state = app_state::AppState::new(documents, all_sections, cfg.wrap_width);
+    let mut state = app_state::AppState::new(loaded.files, loaded.sections, cfg.wrap_width)
+        .with_formats(loaded.formats)
+        .with_unparsed(loaded.unparsed, index)
+        .with_lhs_source(match repo {
+            Some(repo) => Some(app_state::OriginalSource::GitIndex(repo)),
+            None => args.lhs.map(app_state::OriginalSource::Path),
+        });
+    state.trees = loaded.trees;
+    state.patch_out = args.patch;
+    state.write_options.backup = cfg.backup;
+    state.reflow_on_save = cfg.reflow_on_save;
+    theme::install(
+        Theme::from_config(&cfg.theme, theme::no_color_requested())
+            .map_err(|e| io::Error::new(e.kind(), format!("asterism.toml: {e}")))?,
+    );
+    state.keymap = Keymap::from_config(cfg.keymap.as_deref(), &cfg.keys)
+        .map_err(|e| io::Error::new(e.kind(), format!("asterism.toml: {e}")))?;
 
     if let Some(load_path) = args.load_docs {
-        let file_content = std::fs::read_to_string(&load_path)?;
-        let plan: edit_plan::EditPlan = serde_json::from_str(&file_content)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        state.load_docs(plan);
+        state.load_docs(read_plan(&load_path)?);
     }
 
-    run_tui(state, &cfg)
+    let watcher = if args.watch {
+        let documents: Vec<PathBuf> = state
+            .files
+            .iter()
+            .filter(|file| !state.format_for(&file.to_string_lossy()).is_diff())
+            .cloned()
+            .collect();
+        Some(FileWatcher::new(&documents)?)
+    } else {
+        None
+    };
+
+    run_tui(state, &cfg, watcher.as_ref())
+}
+
+/// The parse index from the user's cache directory, unless caching is turned off.
+fn open_index(cfg: &config::Config) -> Option<ParseIndex> {
+    cfg.parse_cache
+        .then(ParseIndex::default_path)
+        .flatten()
+        .map(ParseIndex::load)
+}
+
+/// Expand command line paths into documents.
+///
+/// Files named explicitly only need a recognisable format, not one of the discovery extensions,
+/// so `asterism diff.json` opens without `-e json`.
+fn discover(
+    paths: Vec<PathBuf>,
+    cfg: &config::Config,
+    registry: &FormatRegistry,
+    forced: bool,
+) -> io::Result<Vec<PathBuf>> {
+    if paths.is_empty() {
+        return input::find_documents(paths, &cfg.file_extensions, &cfg.discovery);
+    }
+
+    let mut documents = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            if forced || registry.for_path(&path).is_some() {
+                documents.push(path);
+            }
+        } else {
+            documents.extend(input::find_documents(
+                vec![path],
+                &cfg.file_extensions,
+                &cfg.discovery,
+            )?);
+        }
+    }
+    Ok(documents)
 }
 
-fn run_tui(mut app: app_state::AppState, cfg: &config::Config) -> io::Result<()> {
+fn run_tui(
+    mut app: app_state::AppState,
+    cfg: &config::Config,
+    watcher: Option<&FileWatcher>,
+) -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
@@ -125,7 +414,7 @@ fn run_tui(mut app: app_state::AppState, cfg: &config::Config) -> io::Result<()>
 
     let mut editor_handler = EditorEventHandler::default();
 
-    let result = run_app(&mut terminal, &mut app, cfg, &mut editor_handler);
+    let result = run_app(&mut terminal, &mut app, cfg, &mut editor_handler, watcher);
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
@@ -133,6 +422,12 @@ fn run_tui(mut app: app_state::AppState, cfg: &config::Config) -> io::Result<()>
 
     if let Err(e) = result {
         eprintln!("Error: {e}");
+    } else if app.git_repo().is_some() {
+        if app.patch_out.is_some() {
+            app.write_patch()?;
+        } else {
+            print!("{}", app.staged_patch());
+        }
     } else {
         let plan = app.generate_edit_plan();
         let json = serde_json::to_string_pretty(&plan).map_err(io::Error::other)?;
@@ -148,135 +443,39 @@ fn run_app<B: ratatui::backend::Backend>(
     app: &mut app_state::AppState,
     cfg: &config::Config,
     editor_handler: &mut EditorEventHandler,
+    watcher: Option<&FileWatcher>,
 ) -> io::Result<()> {
     loop {
+        if let Some(watcher) = watcher {
+            for file in watcher.changed() {
+                let file = file.to_string_lossy();
+                if let Err(e) = app.reload_file(&file) {
+                    app.message = Some(format!("Could not reload {file}: {e}"));
+                }
+            }
+        }
+
         terminal.draw(|f| ui::draw(f, app, cfg))?;
 
+        // Wake periodically to pick up file changes when watching
+        if watcher.is_some() && !event::poll(WATCH_INTERVAL)? {
+            continue;
+        }
+
         if let Event::Key(key) = event::read()? {
+            if app.current_view == app_state::View::List {
+                // Status messages last until the next keystroke in the list
+                app.message = None;
+            }
+
             match app.current_view {
-                app_state::View::List => match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Up => {
-                        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+Up: Start move or move up
-                            if app.move_state == app_state::MoveState::None {
-                                app.start_move();
-                            } else {
-                                app.move_section_up();
-                            }
-                        } else if key.modifiers.contains(event::KeyModifiers::SHIFT) {
-                            // Shift+Up: Jump to previous sibling at same level
-                            if let Some(prev_sibling) = app.navigate_to_prev_sibling() {
-                                app.current_node_index = prev_sibling;
-                            }
-                        } else {
-                            // Normal up: Previous navigable node
-                            if let Some(prev) = app.find_prev_node() {
-                                app.current_node_index = prev;
-                            }
-                        }
-                    }
-                    KeyCode::Down => {
-                        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+Down: Start move or move down
-                            if app.move_state == app_state::MoveState::None {
-                                app.start_move();
-                            } else {
-                                app.move_section_down();
-                            }
-                        } else if key.modifiers.contains(event::KeyModifiers::SHIFT) {
-                            // Shift+Down: Jump to next sibling at same level
-                            if let Some(next_sibling) = app.navigate_to_next_sibling() {
-                                app.current_node_index = next_sibling;
-                            }
-                        } else {
-                            // Normal down: Next navigable node
-                            if let Some(next) = app.find_next_node() {
-                                app.current_node_index = next;
-                            }
-                        }
-                    }
-                    KeyCode::Left | KeyCode::Char('h') => {
-                        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+Left: Start move or decrease level
-                            if app.move_state == app_state::MoveState::None {
-                                app.start_move();
-                            } else {
-                                app.move_section_in();
-                            }
-                        } else if let Some(parent_idx) = app.navigate_to_parent() {
-                            app.current_node_index = parent_idx;
-                        }
-                    }
-                    KeyCode::Right | KeyCode::Char('l') => {
-                        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+Right: Start move or increase level
-                            if app.move_state == app_state::MoveState::None {
-                                app.start_move();
-                            } else {
-                                app.move_section_out();
-                            }
-                        } else if let Some(descendant_idx) = app.navigate_to_next_descendant() {
-                            app.current_node_index = descendant_idx;
-                        }
-                    }
-                    KeyCode::Home => {
-                        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+Home: Move to top
-                            if app.move_state != app_state::MoveState::None {
-                                app.move_section_to_top();
-                            }
-                        } else if key.modifiers.contains(event::KeyModifiers::SHIFT) {
-                            // Shift+Home: Jump to first section at same level
-                            if let Some(first_at_level) = app.navigate_to_first_at_level() {
-                                app.current_node_index = first_at_level;
-                            }
-                        } else {
-                            // Home: Jump to first navigable node
-                            if let Some(first) = app.navigate_to_first() {
-                                app.current_node_index = first;
-                            }
-                        }
-                    }
-                    KeyCode::End => {
-                        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                            // Ctrl+End: Move to bottom
-                            if app.move_state != app_state::MoveState::None {
-                                app.move_section_to_bottom();
-                            }
-                        } else if key.modifiers.contains(event::KeyModifiers::SHIFT) {
-                            // Shift+End: Jump to last section at same level
-                            if let Some(last_at_level) = app.navigate_to_last_at_level() {
-                                app.current_node_index = last_at_level;
-                            }
-                        } else {
-                            // End: Jump to last navigable node
-                            if let Some(last) = app.navigate_to_last() {
-                                app.current_node_index = last;
-                            }
-                        }
-                    }
-                    KeyCode::Esc => {
-                        if app.move_state != app_state::MoveState::None {
-                            app.cancel_move();
-                        }
-                    }
-                    KeyCode::Char(':') => {
-                        app.current_view = app_state::View::Command;
-                        app.command_buffer.clear();
-                        app.message = None;
-                    }
-                    KeyCode::Enter => {
-                        // Only enter detail view if on a navigable node
-                        if app.move_state == app_state::MoveState::None
-                            && app.current_node_index < app.tree_nodes.len()
-                            && app.tree_nodes[app.current_node_index].navigable
-                        {
-                            app.enter_detail_view();
+                app_state::View::List => {
+                    if let Some(action) = app.keymap.press(key) {
+                        if app.perform(action) {
+                            return Ok(());
                         }
                     }
-                    _ => {}
-                },
+                }
                 app_state::View::Detail => match key.code {
                     KeyCode::Char(':') => {
                         if let Some(ref editor_state) = app.editor_state {
@@ -293,7 +492,13 @@ fn run_app<B: ratatui::backend::Backend>(
                     KeyCode::Esc => {
                         if let Some(ref editor_state) = app.editor_state {
                             if editor_state.mode == edtui::EditorMode::Normal {
-                                app.exit_detail_view(false);
+                                // Leaving keeps edits in memory for :wa rather than dropping them
+                                let dirty = app.editor_dirty();
+                                app.exit_detail_view(dirty);
+                                if dirty {
+                                    app.message =
+                                        Some("Edits kept in memory (:wa writes all)".to_string());
+                                }
                             } else {
                                 editor_handler
                                     .on_key_event(key, app.editor_state.as_mut().unwrap());
@@ -306,6 +511,63 @@ fn run_app<B: ratatui::backend::Backend>(
                         }
                     }
                 },
+                app_state::View::Diff => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => app.exit_diff_view(),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        if let Some(ref mut view) = app.diff_view {
+                            view.scroll_down(1);
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        if let Some(ref mut view) = app.diff_view {
+                            view.scroll_up(1);
+                        }
+                    }
+                    KeyCode::PageDown => {
+                        if let Some(ref mut view) = app.diff_view {
+                            view.scroll_down(10);
+                        }
+                    }
+                    KeyCode::PageUp => {
+                        if let Some(ref mut view) = app.diff_view {
+                            view.scroll_up(10);
+                        }
+                    }
+                    KeyCode::Char(c @ ('a' | 'r')) => {
+                        app.decide_hunk(if c == 'a' {
+                            app_state::HunkDecision::Accepted
+                        } else {
+                            app_state::HunkDecision::Rejected
+                        });
+                        // Move on to the next hunk, as `git add -p` does
+                        if let Some(next) = app.find_next_node() {
+                            app.current_node_index = next;
+                            app.enter_detail_view();
+                        } else {
+                            app.exit_diff_view();
+                        }
+                    }
+                    KeyCode::Char('e') => app.edit_hunk(),
+                    KeyCode::Char('u') => app.undo_hunk_decision(),
+                    KeyCode::Char(':') => {
+                        app.exit_diff_view();
+                        app.current_view = app_state::View::Command;
+                        app.command_buffer.clear();
+                    }
+                    KeyCode::Char('n') => {
+                        if let Some(next) = app.find_next_node() {
+                            app.current_node_index = next;
+                            app.enter_detail_view();
+                        }
+                    }
+                    KeyCode::Char('p') => {
+                        if let Some(prev) = app.find_prev_node() {
+                            app.current_node_index = prev;
+                            app.enter_detail_view();
+                        }
+                    }
+                    _ => {}
+                },
                 app_state::View::Command => match key.code {
                     KeyCode::Char(c) => {
                         app.command_buffer.push(c);
@@ -315,7 +577,11 @@ fn run_app<B: ratatui::backend::Backend>(
                     }
                     KeyCode::Enter => {
                         let cmd = app.command_buffer.clone();
-                        app.current_view = app_state::View::List;
+                        app.current_view = if app.editor_state.is_some() {
+                            app_state::View::Detail
+                        } else {
+                            app_state::View::List
+                        };
 
                         match cmd.as_str() {
                             "w" => {
@@ -327,8 +593,30 @@ fn run_app<B: ratatui::backend::Backend>(
                                     if let Err(e) = app.save_current() {
                                         app.message = Some(format!("Error saving: {e}"));
                                     }
+                                } else if app.git_repo().is_some() {
+                                    app.message = Some(match (&app.patch_out, app.write_patch()) {
+                                        (_, Err(e)) => format!("Error saving: {e}"),
+                                        (Some(path), Ok(())) => {
+                                            format!("Wrote staged patch to {}", path.display())
+                                        }
+                                        (None, Ok(())) => {
+                                            "The staged patch is printed on exit".to_string()
+                                        }
+                                    });
                                 } else {
-                                    app.message = Some("Nothing to save".to_string());
+                                    let written =
+                                        app.apply_conflict_resolutions().and_then(|conflicts| {
+                                            Ok((conflicts, app.apply_hunk_decisions()?))
+                                        });
+                                    app.message = Some(match written {
+                                        Ok((0, 0)) => "Nothing to save".to_string(),
+                                        Ok((0, n)) => format!("Wrote {n} hunk decisions"),
+                                        Ok((n, 0)) => format!("Resolved {n} conflicts"),
+                                        Ok((c, n)) => format!(
+                                            "Resolved {c} conflicts and wrote {n} hunk decisions"
+                                        ),
+                                        Err(e) => format!("Error saving: {e}"),
+                                    });
                                 }
                             }
                             "x" => {
@@ -340,11 +628,24 @@ fn run_app<B: ratatui::backend::Backend>(
                                     if let Err(e) = app.save_current() {
                                         app.message = Some(format!("Error saving: {e}"));
                                     } else {
-                                        app.exit_detail_view(true);
+                                        app.exit_detail_view(false);
                                     }
                                 }
                             }
-                            "q" | "q!" => {
+                            "q" => {
+                                if app.editor_dirty() {
+                                    app.message = Some(
+                                        "Unsaved changes (:w saves, :q! discards)".to_string(),
+                                    );
+                                } else if app.editor_state.is_some() {
+                                    app.exit_detail_view(false);
+                                } else if app.move_state != app_state::MoveState::None {
+                                    app.cancel_move();
+                                } else if !app.guard_quit() {
+                                    return Ok(());
+                                }
+                            }
+                            "q!" => {
                                 if app.editor_state.is_some() {
                                     app.exit_detail_view(false);
                                 } else if app.move_state != app_state::MoveState::None {
@@ -353,12 +654,32 @@ fn run_app<B: ratatui::backend::Backend>(
                                     return Ok(());
                                 }
                             }
+                            cmd if cmd == "map" || cmd.starts_with("map ") => {
+                                app.message = Some(map_command(app, &cmd[3..]));
+                            }
+                            "gq" => app.reflow_paragraph(),
+                            "reflow" => app.reflow_section(),
+                            "wa" => {
+                                let reordered = app.move_state == app_state::MoveState::Moved;
+                                let written = if reordered {
+                                    app.save_section_reorder().and_then(|()| app.write_all())
+                                } else {
+                                    app.write_all()
+                                };
+                                app.message = Some(match written {
+                                    Ok(0) if reordered => "Saved".to_string(),
+                                    Ok(0) => "Nothing to save".to_string(),
+                                    Ok(1) => "Wrote 1 section".to_string(),
+                                    Ok(n) => format!("Wrote {n} sections"),
+                                    Err(e) => format!("Error saving: {e}"),
+                                });
+                            }
                             "wn" => {
                                 if app.editor_state.is_some() {
                                     if let Err(e) = app.save_current() {
                                         app.message = Some(format!("Error saving: {e}"));
                                     } else if let Some(next) = app.find_next_node() {
-                                        app.exit_detail_view(true);
+                                        app.exit_detail_view(false);
                                         app.current_node_index = next;
                                         app.enter_detail_view();
                                     } else {
@@ -371,7 +692,7 @@ fn run_app<B: ratatui::backend::Backend>(
                                     if let Err(e) = app.save_current() {
                                         app.message = Some(format!("Error saving: {e}"));
                                     } else if let Some(prev) = app.find_prev_node() {
-                                        app.exit_detail_view(true);
+                                        app.exit_detail_view(false);
                                         app.current_node_index = prev;
                                         app.enter_detail_view();
                                     } else {
@@ -395,3 +716,14 @@ fn run_app<B: ratatui::backend::Backend>(
         }
     }
 }
+
+/// Rebind a key sequence from `:map KEYS ACTION`, describing the outcome.
+fn map_command(app: &mut app_state::AppState, args: &str) -> String {
+    let Some((keys, action)) = args.trim().rsplit_once(' ') else {
+        return "Usage: :map KEYS ACTION (e.g. :map ctrl+k move-up)".to_string();
+    };
+    match app.keymap.bind(keys, action) {
+        Ok(()) => format!("Mapped {} to {action}", keys.trim()),
+        Err(e) => format!("Cannot map: {e}"),
+    }
+}
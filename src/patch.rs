@@ -0,0 +1,250 @@
+//! Unified diff output for reviewed hunks, in the form `git apply` accepts.
+//!
+//! Each hunk is written against the original file with surrounding context. Hunks close enough for
+//! their context to overlap are merged into one, since git rejects hunks whose context has already
+//! been consumed and anchors hunks without trailing context to the end of the file.
+
+use crate::diff_view::{build_rows, first_line, DiffRow};
+use crate::formats::difftastic::Hunk;
+use crate::section::ChunkType;
+use std::fmt::Write;
+
+/// Unchanged lines written before and after each patch hunk.
+pub const PATCH_CONTEXT: usize = 3;
+
+/// Marker following a line that ends its file without a trailing newline.
+const NO_NEWLINE: &str = "\\ No newline at end of file\n";
+
+/// A hunk chosen for the patch, with its replacement lines when it was edited.
+pub struct PatchHunk<'a> {
+    /// Hunk as parsed from the diff.
+    pub hunk: &'a Hunk,
+    /// Lines replacing the hunk's modified side, when edited.
+    pub edited: Option<&'a [String]>,
+}
+
+impl PatchHunk<'_> {
+    /// Original line range the hunk covers, 0-indexed and exclusive.
+    fn lhs_lines(&self) -> (usize, usize) {
+        let range = self.hunk.range;
+        let start = first_line(range.lhs_start, range.lhs_count);
+        (start, start + range.lhs_count as usize)
+    }
+}
+
+/// Patch for one file, applying only the given hunks to `original`.
+///
+/// `path` is relative to the repository root; `modified` supplies the new text of lines the
+/// hunks changed. Returns an empty string when there are no hunks.
+#[must_use]
+pub fn file_patch(path: &str, original: &str, modified: &str, hunks: &mut [PatchHunk]) -> String {
+    if hunks.is_empty() {
+        return String::new();
+    }
+    hunks.sort_by_key(|h| h.lhs_lines().0);
+
+    let lines: Vec<&str> = original.lines().collect();
+    let span = |from: usize, to: usize| {
+        let from = from.min(lines.len());
+        &lines[from..to.clamp(from, lines.len())]
+    };
+    let modified_lines = modified.lines().count();
+    let unterminated = |text: &str, count: usize| {
+        (!text.is_empty() && !text.ends_with('\n')).then(|| count.saturating_sub(1))
+    };
+    let ends = Ends {
+        old: unterminated(original, lines.len()),
+        new: unterminated(modified, modified_lines),
+    };
+
+    let whole_file = match &hunks[..] {
+        [PatchHunk { hunk, edited: None }] => hunk.whole_file(),
+        _ => None,
+    };
+    let mut out = format!("diff --git a/{path} b/{path}\n");
+    match whole_file {
+        Some(ChunkType::Added) => {
+            let _ = write!(out, "new file mode 100644\n--- /dev/null\n+++ b/{path}\n");
+        }
+        Some(ChunkType::Deleted) => {
+            let _ = write!(
+                out,
+                "deleted file mode 100644\n--- a/{path}\n+++ /dev/null\n"
+            );
+        }
+        _ => {
+            let _ = write!(out, "--- a/{path}\n+++ b/{path}\n");
+        }
+    }
+    // Lines added minus lines removed by the hunks written so far
+    let mut delta = 0i64;
+
+    let mut rest = &hunks[..];
+    while let Some(first) = rest.first() {
+        // Merge hunks while the gap between them is within both hunks' context
+        let mut count = 1;
+        while count < rest.len()
+            && rest[count].lhs_lines().0 <= rest[count - 1].lhs_lines().1 + 2 * PATCH_CONTEXT
+        {
+            count += 1;
+        }
+        let (block, remaining) = rest.split_at(count);
+        rest = remaining;
+
+        let lead_start = first.lhs_lines().0.saturating_sub(PATCH_CONTEXT);
+        let last_end = block[block.len() - 1].lhs_lines().1;
+        let trail_end = (last_end + PATCH_CONTEXT).min(lines.len());
+
+        let mut body = Body {
+            ends,
+            ..Body::default()
+        };
+        let mut position = lead_start;
+        for staged in block {
+            let (start, end) = staged.lhs_lines();
+            body.context(position, span(position, start));
+            match staged.edited {
+                Some(edited) => {
+                    // Edited lines replacing the end of the modified file end it as it did
+                    let range = staged.hunk.range;
+                    let r_end =
+                        first_line(range.rhs_start, range.rhs_count) + range.rhs_count as usize;
+                    let at_end = r_end >= modified_lines && ends.new.is_some();
+                    body.replace(start, span(start, end), edited, at_end);
+                }
+                None => body.rows(&build_rows(staged.hunk, Some(original), Some(modified), 0)),
+            }
+            position = end;
+        }
+        body.context(position, span(position, trail_end));
+        body.finish();
+
+        let start = |from: i64, count: usize| if count == 0 { from } else { from + 1 };
+        let old_from = i64::try_from(lead_start).unwrap_or(0);
+        let _ = writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            start(old_from, body.old),
+            body.old,
+            start(old_from + delta, body.new),
+            body.new
+        );
+        out.push_str(&body.text);
+        delta += i64::try_from(body.new).unwrap_or(0) - i64::try_from(body.old).unwrap_or(0);
+    }
+    out
+}
+
+/// Last line of each side when it has no trailing newline, which `git apply` needs marked.
+#[derive(Clone, Copy, Default)]
+struct Ends {
+    /// Index of the original's last line.
+    old: Option<usize>,
+    /// Index of the modified file's last line.
+    new: Option<usize>,
+}
+
+/// Lines of one patch hunk and how many it spans on each side.
+#[derive(Default)]
+struct Body {
+    text: String,
+    old: usize,
+    new: usize,
+    /// Added lines held back so each run of changes lists removals first, each with whether it
+    /// ends the modified file without a newline.
+    pending: Vec<(String, bool)>,
+    ends: Ends,
+    /// Offset in `text` of a context line ending the original without a newline.
+    unterminated_context: Option<usize>,
+}
+
+impl Body {
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        // Lines added after an unterminated last line give it a newline on the new side
+        if let Some(offset) = self.unterminated_context.take() {
+            let line = self.text.split_off(offset);
+            let line = &line[1..];
+            let _ = write!(self.text, "-{line}{NO_NEWLINE}+{line}");
+        }
+        for (line, unterminated) in self.pending.drain(..) {
+            let _ = writeln!(self.text, "+{line}");
+            if unterminated {
+                self.text.push_str(NO_NEWLINE);
+            }
+            self.new += 1;
+        }
+    }
+
+    /// Write unchanged original lines, the first of them at index `first`.
+    fn context(&mut self, first: usize, lines: &[&str]) {
+        self.flush();
+        for (index, line) in (first..).zip(lines) {
+            if self.ends.old == Some(index) {
+                self.unterminated_context = Some(self.text.len());
+            }
+            let _ = writeln!(self.text, " {line}");
+            self.old += 1;
+            self.new += 1;
+        }
+    }
+
+    fn remove(&mut self, index: usize, line: &str) {
+        let _ = writeln!(self.text, "-{line}");
+        if self.ends.old == Some(index) {
+            self.text.push_str(NO_NEWLINE);
+        }
+        self.old += 1;
+    }
+
+    /// Replace original lines from index `first` with edited ones, the last of which ends the
+    /// file without a newline when `at_end`.
+    fn replace(&mut self, first: usize, original: &[&str], edited: &[String], at_end: bool) {
+        self.flush();
+        for (index, line) in (first..).zip(original) {
+            self.remove(index, line);
+        }
+        let last = edited.len().saturating_sub(1);
+        self.pending.extend(
+            edited
+                .iter()
+                .enumerate()
+                .map(|(i, line)| (line.clone(), at_end && i == last)),
+        );
+        self.flush();
+    }
+
+    fn rows(&mut self, rows: &[DiffRow]) {
+        for row in rows {
+            match (&row.lhs, &row.rhs) {
+                (Some(lhs), Some(rhs)) if !lhs.changed && !rhs.changed => {
+                    self.context(lhs.line_number, &[lhs.text.as_str()]);
+                }
+                _ => {
+                    if let Some(lhs) = &row.lhs {
+                        self.remove(lhs.line_number, &lhs.text);
+                    }
+                    if let Some(rhs) = &row.rhs {
+                        let unterminated = self.ends.new == Some(rhs.line_number);
+                        self.pending.push((rhs.text.clone(), unterminated));
+                    }
+                }
+            }
+        }
+        self.flush();
+    }
+
+    /// Close the hunk, marking an unterminated last line left unchanged on both sides.
+    fn finish(&mut self) {
+        self.flush();
+        if self.unterminated_context.take().is_some() {
+            self.text.push_str(NO_NEWLINE);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/patch.rs"]
+mod tests;
@@ -0,0 +1,243 @@
+//! Hard-wrapping of markdown prose at the configured width.
+//!
+//! Only paragraphs are refilled, including those in list items and block quotes, which keep
+//! their markers and indent continuation lines beneath them. Code blocks, tables, headings,
+//! HTML and link reference definitions are left exactly as written.
+
+use std::ops::Range;
+
+/// A run of lines that is either one prose paragraph or kept as written.
+struct Block {
+    lines: Range<usize>,
+    prose: bool,
+}
+
+/// Hard-wrap every prose paragraph in `text` at `width` characters.
+#[must_use]
+pub fn reflow(text: &str, width: usize) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    blocks(&lines)
+        .iter()
+        .flat_map(|block| render(&lines[block.lines.clone()], block.prose, width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Hard-wrap the paragraph containing line `row`, or `None` if that line is not prose.
+#[must_use]
+pub fn reflow_paragraph(text: &str, row: usize, width: usize) -> Option<String> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let blocks = blocks(&lines);
+    if !blocks
+        .iter()
+        .any(|block| block.prose && block.lines.contains(&row))
+    {
+        return None;
+    }
+    Some(
+        blocks
+            .iter()
+            .flat_map(|block| {
+                let prose = block.prose && block.lines.contains(&row);
+                render(&lines[block.lines.clone()], prose, width)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+fn render(lines: &[&str], prose: bool, width: usize) -> Vec<String> {
+    if prose && width > 0 {
+        fill(lines, width)
+    } else {
+        lines.iter().map(ToString::to_string).collect()
+    }
+}
+
+/// Split lines into paragraphs and the lines between them.
+fn blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut paragraph: Option<usize> = None;
+
+    let close = |blocks: &mut Vec<Block>, paragraph: &mut Option<usize>, end: usize| {
+        if let Some(start) = paragraph.take() {
+            // A delimiter row makes the paragraph a table
+            let prose = !lines[start..end].iter().any(|line| is_delimiter_row(line));
+            blocks.push(Block {
+                lines: start..end,
+                prose,
+            });
+        }
+    };
+
+    for (row, line) in lines.iter().enumerate() {
+        let (quote, rest) = split_quote(line);
+        if let Some((marker, len)) = fence {
+            blocks.push(verbatim(row));
+            let trimmed = rest.trim();
+            if trimmed.len() >= len && trimmed.chars().all(|c| c == marker) {
+                fence = None;
+            }
+            continue;
+        }
+
+        let opens_fence = fence_marker(rest);
+        let indented_code = paragraph.is_none() && indent(rest) >= 4;
+        if opens_fence.is_some() || indented_code || is_verbatim(rest) {
+            close(&mut blocks, &mut paragraph, row);
+            blocks.push(verbatim(row));
+            fence = opens_fence;
+            continue;
+        }
+
+        // A list item or a change of quoting starts a new paragraph
+        if let Some(start) = paragraph {
+            if list_marker(rest).is_some() || split_quote(lines[start]).0.trim() != quote.trim() {
+                close(&mut blocks, &mut paragraph, row);
+            }
+        }
+        paragraph.get_or_insert(row);
+
+        if is_hard_break(line) {
+            close(&mut blocks, &mut paragraph, row + 1);
+        }
+    }
+    close(&mut blocks, &mut paragraph, lines.len());
+    blocks
+}
+
+fn verbatim(row: usize) -> Block {
+    Block {
+        lines: row..row + 1,
+        prose: false,
+    }
+}
+
+/// Refill a paragraph's words into lines no wider than `width` where words allow.
+fn fill(lines: &[&str], width: usize) -> Vec<String> {
+    let (quote, first) = split_quote(lines[0]);
+    let marker = list_marker(first).unwrap_or_else(|| indent(first));
+    let lead = format!("{quote}{}", &first[..marker]);
+    let hanging = format!("{quote}{}", " ".repeat(first[..marker].chars().count()));
+
+    let words = first[marker..].split_whitespace().chain(
+        lines[1..]
+            .iter()
+            .flat_map(|line| split_quote(line).1.split_whitespace()),
+    );
+
+    let mut filled = Vec::new();
+    let mut current = lead;
+    let mut empty = true;
+    for word in words {
+        let fits = current.chars().count() + 1 + word.chars().count() <= width;
+        // Moving a word such as `-` or `#` to the start of a line would change the markup
+        if empty || fits || starts_block(word) {
+            if !empty {
+                current.push(' ');
+            }
+            current.push_str(word);
+            empty = false;
+        } else {
+            filled.push(std::mem::replace(&mut current, format!("{hanging}{word}")));
+        }
+    }
+    if lines[lines.len() - 1].ends_with("  ") {
+        current.push_str("  ");
+    }
+    filled.push(current);
+    filled
+}
+
+/// Split a line into its block quote markers and the rest.
+fn split_quote(line: &str) -> (&str, &str) {
+    let mut end = 0;
+    loop {
+        let rest = &line[end..];
+        let trimmed = rest.trim_start_matches(' ');
+        if rest.len() - trimmed.len() > 3 || !trimmed.starts_with('>') {
+            return line.split_at(end);
+        }
+        end = line.len() - trimmed.len() + 1;
+        if line[end..].starts_with(' ') {
+            end += 1;
+        }
+    }
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Length of a list item marker with its indent and the spaces after it.
+fn list_marker(line: &str) -> Option<usize> {
+    let lead = indent(line);
+    let rest = &line[lead..];
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    let marker = match rest[digits..].chars().next()? {
+        '-' | '*' | '+' if digits == 0 => 1,
+        '.' | ')' if (1..=9).contains(&digits) => digits + 1,
+        _ => return None,
+    };
+    let after = &rest[marker..];
+    let spaces = after.len() - after.trim_start_matches(' ').len();
+    (spaces > 0 && !after.trim().is_empty()).then_some(lead + marker + spaces.min(4))
+}
+
+/// Opening code fence, as its character and length.
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = trimmed.chars().take_while(|&c| c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+/// Lines that are never part of a paragraph and are kept as written.
+fn is_verbatim(line: &str) -> bool {
+    let trimmed = line.trim();
+    let marks: Vec<char> = trimmed.chars().filter(|&c| c != ' ').collect();
+    let rule = marks.len() >= 3
+        && matches!(marks[0], '-' | '*' | '_' | '=')
+        && marks.iter().all(|&c| c == marks[0]);
+    trimmed.is_empty()
+        || rule
+        || trimmed.starts_with('#')
+        || trimmed.starts_with('|')
+        || trimmed.starts_with('<')
+        || (trimmed.starts_with('[') && trimmed.contains("]:"))
+}
+
+/// Table delimiter row, such as `| --- | :-: |`.
+fn is_delimiter_row(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.contains('|')
+        && trimmed.contains('-')
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+/// Line ending in a markdown hard break, which must stay a line break.
+fn is_hard_break(line: &str) -> bool {
+    line.ends_with("  ") || line.ends_with('\\')
+}
+
+/// Word that would begin a heading, quote, list, rule or fence at the start of a line.
+fn starts_block(word: &str) -> bool {
+    let digits = word.chars().take_while(char::is_ascii_digit).count();
+    word.starts_with(['#', '>'])
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || word
+            .chars()
+            .all(|c| matches!(c, '-' | '+' | '*' | '=' | '_'))
+        || (digits > 0 && matches!(&word[digits..], "." | ")"))
+}
+
+#[cfg(test)]
+#[path = "tests/reflow.rs"]
+mod tests;
@@ -5,6 +5,10 @@
 //! in the document tree through parent/child relationships and maintain
 //! precise byte and line coordinates for content extraction and modification.
 
+use crate::formats::difftastic::Hunk;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
 #[derive(Clone)]
 /// Hierarchical document division with precise coordinates for extraction and modification.
 pub struct Section {
@@ -26,22 +30,70 @@ pub struct Section {
     pub byte_end: usize,
     /// Source file containing this section.
     pub file_path: String,
+    /// Identity that survives re-parsing, assigned by [`assign_ids`].
+    pub id: SectionId,
     /// Index of the containing section in the hierarchy.
     pub parent_index: Option<usize>,
     /// Indices of directly nested subsections.
     pub children_indices: Vec<usize>,
     /// Edited content for this section (if modified)
     pub section_content: Option<Vec<String>>,
-    /// The chunk type (for diffs)
-    pub chunk_type: Option<ChunkType>,
-    /// The LHS (for diffs)
-    pub lhs_content: Option<String>,
-    /// The RHS (for diffs)
-    pub rhs_content: Option<String>,
+    /// Typed diff lines behind a hunk section, shown side by side in the diff view.
+    pub hunk: Option<Box<Hunk>>,
+}
+
+impl Section {
+    /// Kind of change a diff hunk section makes, or `None` for document sections.
+    #[must_use]
+    pub fn chunk_type(&self) -> Option<ChunkType> {
+        self.hunk.as_ref().map(|hunk| hunk.range.chunk_type())
+    }
+}
+
+/// Identity of a section that survives its file being parsed again.
+///
+/// Section indices shift whenever a file is re-parsed and titles alone repeat (a document may
+/// have several "Examples"), so a section is known by the titles leading down to it and which of
+/// the sections sharing that path it is.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SectionId {
+    /// Source file containing the section.
+    pub file_path: String,
+    /// Titles of the enclosing sections, outermost first, ending with the section's own.
+    pub titles: Vec<String>,
+    /// Number of earlier sections in the file with the same titles.
+    pub occurrence: usize,
+}
+
+/// Give each section the [`SectionId`] its place among the sections of its file implies.
+pub fn assign_ids(sections: &mut [Section]) {
+    let mut enclosing: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+    let mut seen: HashMap<(String, Vec<String>), usize> = HashMap::new();
+    for section in sections {
+        let stack = enclosing.entry(section.file_path.clone()).or_default();
+        while stack
+            .last()
+            .is_some_and(|(level, _)| *level >= section.level)
+        {
+            stack.pop();
+        }
+        stack.push((section.level, section.title.clone()));
+
+        let titles: Vec<String> = stack.iter().map(|(_, title)| title.clone()).collect();
+        let count = seen
+            .entry((section.file_path.clone(), titles.clone()))
+            .or_default();
+        section.id = SectionId {
+            file_path: section.file_path.clone(),
+            titles,
+            occurrence: *count,
+        };
+        *count += 1;
+    }
 }
 
 /// What sort of hunk (syntactic diff atomic unit) it is.
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ChunkType {
     /// Only RHS exists
     Added,
@@ -71,7 +123,7 @@ pub enum NodeType {
         path: String,
     },
     /// Actual document section (navigable)
-    Section(Section),
+    Section(Box<Section>),
 }
 
 /// A node in the unified file + section tree.
@@ -110,11 +162,17 @@ impl TreeNode {
         }
     }
 
+    /// Whether the list cursor can rest here: on sections, and on folders so they can be folded.
+    #[must_use]
+    pub fn selectable(&self) -> bool {
+        self.navigable || matches!(self.node_type, NodeType::Directory { .. })
+    }
+
     /// Create a section node
     #[must_use]
     pub fn section(section: Section, tree_level: usize, section_index: usize) -> Self {
         Self {
-            node_type: NodeType::Section(section),
+            node_type: NodeType::Section(Box::new(section)),
             tree_level,
             navigable: true,
             section_index: Some(section_index),
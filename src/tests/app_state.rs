@@ -1,7 +1,15 @@
-use super::AppState;
-use crate::section::Section;
+use super::{AppState, HunkDecision, OriginalSource};
+use crate::formats::conflict::{ConflictFormat, Resolution};
+use crate::formats::difftastic::DifftasticFormat;
+use crate::formats::markdown::MarkdownFormat;
+use crate::formats::Format;
+use crate::keymap::Action;
+use crate::section::{Section, SectionId};
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
 use tempfile::NamedTempFile;
 
 #[test]
@@ -23,12 +31,11 @@ fn test_edit_persists_correctly() {
             byte_start: 10,
             byte_end: 12,
             file_path: path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: vec![1],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
         Section {
             title: "World".to_string(),
@@ -40,12 +47,11 @@ fn test_edit_persists_correctly() {
             byte_start: 23,
             byte_end: 25,
             file_path: path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: Some(0),
             children_indices: vec![],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
     ];
 
@@ -95,12 +101,11 @@ fn test_edit_plan_captures_changes() {
         byte_start: 9,
         byte_end: 17,
         file_path: path.to_string_lossy().to_string(),
+        id: SectionId::default(),
         parent_index: None,
         children_indices: vec![],
         section_content: None,
-        chunk_type: None,
-        lhs_content: None,
-        rhs_content: None,
+        hunk: None,
     }];
 
     let mut app = AppState::new(vec![path.clone()], sections, 100);
@@ -135,59 +140,7 @@ fn test_multiple_edits_correct_offsets() {
     writeln!(file, "# One\n\nA\n\n## Two\n\nB\n\n### Three\n\nC").unwrap();
     let path = file.path().to_path_buf();
 
-    let sections = vec![
-        Section {
-            title: "One".to_string(),
-            level: 1,
-            line_start: 3,
-            line_end: 5,
-            column_start: 1,
-            column_end: 6,
-            byte_start: 8,
-            byte_end: 10,
-            file_path: path.to_string_lossy().to_string(),
-            parent_index: None,
-            children_indices: vec![1],
-            section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
-        },
-        Section {
-            title: "Two".to_string(),
-            level: 2,
-            line_start: 6,
-            line_end: 8,
-            column_start: 1,
-            column_end: 7,
-            byte_start: 19,
-            byte_end: 21,
-            file_path: path.to_string_lossy().to_string(),
-            parent_index: Some(0),
-            children_indices: vec![2],
-            section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
-        },
-        Section {
-            title: "Three".to_string(),
-            level: 3,
-            line_start: 9,
-            line_end: 11,
-            column_start: 1,
-            column_end: 10,
-            byte_start: 33,
-            byte_end: 35,
-            file_path: path.to_string_lossy().to_string(),
-            parent_index: Some(1),
-            children_indices: vec![],
-            section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
-        },
-    ];
+    let sections = MarkdownFormat.extract_sections(&path).unwrap();
 
     let mut app = AppState::new(vec![path.clone()], sections, 100);
 
@@ -252,12 +205,11 @@ fn test_tree_structure_single_file() {
             byte_start: 8,
             byte_end: 10,
             file_path: path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: vec![1],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
         Section {
             title: "Two".to_string(),
@@ -269,12 +221,11 @@ fn test_tree_structure_single_file() {
             byte_start: 19,
             byte_end: 21,
             file_path: path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: Some(0),
             children_indices: vec![],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
     ];
 
@@ -308,12 +259,11 @@ fn test_tree_structure_multi_file() {
             byte_start: 8,
             byte_end: 10,
             file_path: path1.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: vec![],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
         Section {
             title: "Two".to_string(),
@@ -325,12 +275,11 @@ fn test_tree_structure_multi_file() {
             byte_start: 8,
             byte_end: 10,
             file_path: path2.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: vec![],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
     ];
 
@@ -377,12 +326,11 @@ fn test_navigation_skips_files() {
             byte_start: 8,
             byte_end: 10,
             file_path: path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: vec![1],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
         Section {
             title: "Two".to_string(),
@@ -394,12 +342,11 @@ fn test_navigation_skips_files() {
             byte_start: 19,
             byte_end: 21,
             file_path: path.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: Some(0),
             children_indices: vec![],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
         Section {
             title: "Three".to_string(),
@@ -411,12 +358,11 @@ fn test_navigation_skips_files() {
             byte_start: 10,
             byte_end: 12,
             file_path: path2.to_string_lossy().to_string(),
+            id: SectionId::default(),
             parent_index: None,
             children_indices: vec![],
             section_content: None,
-            chunk_type: None,
-            lhs_content: None,
-            rhs_content: None,
+            hunk: None,
         },
     ];
 
@@ -444,3 +390,559 @@ fn test_navigation_skips_files() {
         "Should be on 'One' section"
     );
 }
+
+#[test]
+fn test_hunk_decisions_write_rhs() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "a\nb\nlet x = 2;\nc\nd\nadded()\ne\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+
+    // `let x = 1;` became `let x = 2;`, then `added()` was inserted as a separate chunk
+    let json = format!(
+        r#"{{"chunks":[[{{"lhs":{{"line_number":2,"changes":[{{"start":8,"end":9,"content":"1","highlight":"normal"}}]}},"rhs":{{"line_number":2,"changes":[{{"start":8,"end":9,"content":"2","highlight":"normal"}}]}}}}],[{{"rhs":{{"line_number":5,"changes":[{{"start":0,"end":7,"content":"added()","highlight":"normal"}}]}}}}]],"language":"Text","path":"{path}","status":"changed"}}"#
+    );
+    let format: Arc<dyn Format> = Arc::new(DifftasticFormat);
+    let sections = format.parse(&json, Path::new("-")).unwrap();
+    let mut original = NamedTempFile::new().unwrap();
+    write!(original, "a\nb\nlet x = 1;\nc\nd\ne\n").unwrap();
+    let mut app = AppState::new(vec![file.path().to_path_buf()], sections, 100)
+        .with_formats(HashMap::from([(path.clone(), format)]));
+    app.lhs_source = Some(OriginalSource::Path(original.path().to_path_buf()));
+
+    let hunk_node = |app: &AppState, idx| {
+        app.tree_nodes
+            .iter()
+            .position(|n| n.section_index == Some(idx))
+            .unwrap()
+    };
+
+    // Reject the first hunk, edit the second
+    app.current_node_index = hunk_node(&app, 0);
+    app.decide_hunk(HunkDecision::Rejected);
+    app.current_node_index = hunk_node(&app, 1);
+    app.edit_hunk();
+    app.editor_state.as_mut().unwrap().lines = edtui::Lines::from("edited()\nmore()");
+    app.save_current().unwrap();
+    app.exit_detail_view(true);
+
+    let plan = app.generate_edit_plan();
+    assert_eq!(plan.edits.len(), 2);
+    assert!(plan.edits.iter().all(|edit| edit.verbatim));
+
+    assert_eq!(app.apply_hunk_decisions().unwrap(), 2);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "a\nb\nlet x = 1;\nc\nd\nedited()\nmore()\ne\n"
+    );
+    assert_eq!(
+        app.hunk_decisions.get(&app.sections[1].id),
+        Some(&HunkDecision::Edited)
+    );
+    assert_eq!(
+        (app.sections[1].line_start, app.sections[1].line_end),
+        (5, 7)
+    );
+}
+
+#[test]
+fn test_created_file_sized_from_rhs() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "one\ntwo\nthree\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+
+    let json = format!(r#"{{"language":"Text","path":"{path}","status":"created"}}"#);
+    let sections = DifftasticFormat.parse(&json, Path::new("-")).unwrap();
+    assert_eq!(sections[0].title, "File created @@ -0,0 +1,0 @@");
+
+    let app = AppState::new(vec![file.path().to_path_buf()], sections, 100);
+    assert_eq!(app.sections[0].title, "File created @@ -0,0 +1,3 @@");
+    assert_eq!(
+        (app.sections[0].line_start, app.sections[0].line_end),
+        (0, 3)
+    );
+}
+
+#[test]
+fn test_reject_needs_original() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "a\nlet x = 2;\nb\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+
+    let json = format!(
+        r#"{{"chunks":[[{{"lhs":{{"line_number":1,"changes":[{{"start":8,"end":9,"content":"1","highlight":"normal"}}]}},"rhs":{{"line_number":1,"changes":[{{"start":8,"end":9,"content":"2","highlight":"normal"}}]}}}}]],"language":"Text","path":"{path}","status":"changed"}}"#
+    );
+    let format: Arc<dyn Format> = Arc::new(DifftasticFormat);
+    let sections = format.parse(&json, Path::new("-")).unwrap();
+    let mut app = AppState::new(vec![file.path().to_path_buf()], sections, 100)
+        .with_formats(HashMap::from([(path.clone(), format)]));
+
+    app.current_node_index = app
+        .tree_nodes
+        .iter()
+        .position(|n| n.section_index == Some(0))
+        .unwrap();
+    app.decide_hunk(HunkDecision::Rejected);
+
+    assert!(app.hunk_decisions.is_empty());
+    assert!(app.sections[0].section_content.is_none());
+    assert_eq!(app.apply_hunk_decisions().unwrap(), 0);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nlet x = 2;\nb\n");
+}
+
+#[test]
+fn test_files_sorted_by_churn() {
+    let json = r#"{"chunks":[[{"rhs":{"line_number":4,"changes":[]}}]],"language":"Rust","path":"a.rs","status":"changed"}
+{"chunks":[[{"lhs":{"line_number":4,"changes":[]}},{"lhs":{"line_number":5,"changes":[]}}]],"language":"Python","path":"b.py","status":"changed"}"#;
+    let format: Arc<dyn Format> = Arc::new(DifftasticFormat);
+    let sections = format.parse(json, Path::new("-")).unwrap();
+    let formats = HashMap::from([
+        ("a.rs".to_string(), format.clone()),
+        ("b.py".to_string(), format),
+    ]);
+    let mut app = AppState::new(vec![], sections, 100).with_formats(formats);
+
+    let file_labels = |app: &AppState| -> Vec<String> {
+        app.tree_nodes
+            .iter()
+            .filter_map(|n| match &n.node_type {
+                crate::section::NodeType::File { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        file_labels(&app),
+        vec![
+            "a.rs (changed, Rust) +1 -0 ~0",
+            "b.py (changed, Python) +0 -2 ~0"
+        ]
+    );
+    assert_eq!(
+        app.diff_summary().as_deref(),
+        Some("2 files changed, 2 hunks, +1 -2 ~0")
+    );
+
+    app.toggle_churn_sort();
+    assert!(file_labels(&app)[0].starts_with("b.py"));
+}
+
+#[test]
+fn test_conflict_resolution_written() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> x\nb\n<<<<<<< HEAD\n1\n=======\n2\n>>>>>>> x\nc\n"
+    )
+    .unwrap();
+    let path = file.path().to_string_lossy().to_string();
+    let format: Arc<dyn Format> = Arc::new(ConflictFormat);
+    let sections = format.extract_sections(file.path()).unwrap();
+    let mut app = AppState::new(vec![file.path().to_path_buf()], sections, 100)
+        .with_formats(HashMap::from([(path.clone(), format)]));
+
+    // Resolve the first conflict from its theirs side, the second as both
+    app.current_node_index = 2;
+    app.resolve_conflict(Resolution::Theirs);
+    app.current_node_index = 3;
+    app.resolve_conflict(Resolution::Both);
+    assert_eq!(app.conflict_resolutions.len(), 2);
+
+    assert_eq!(app.apply_conflict_resolutions().unwrap(), 2);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "a\ntheirs\nb\n1\n2\nc\n"
+    );
+    assert!(app.sections.is_empty());
+}
+
+#[test]
+fn test_conflict_side_resolves_its_own_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("a.md");
+    let merged = dir.path().join("b.txt");
+    fs::write(&notes, "# A\n\nbody\n").unwrap();
+    fs::write(&merged, "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> x\n").unwrap();
+
+    let markdown: Arc<dyn Format> = Arc::new(MarkdownFormat);
+    let conflict: Arc<dyn Format> = Arc::new(ConflictFormat);
+    let mut sections = markdown.extract_sections(&notes).unwrap();
+    sections.extend(conflict.extract_sections(&merged).unwrap());
+    let formats = HashMap::from([
+        (notes.to_string_lossy().to_string(), markdown),
+        (merged.to_string_lossy().to_string(), conflict),
+    ]);
+    let mut app =
+        AppState::new(vec![notes.clone(), merged.clone()], sections, 100).with_formats(formats);
+
+    app.current_node_index = app
+        .tree_nodes
+        .iter()
+        .position(|n| {
+            n.section_index
+                .is_some_and(|i| app.sections[i].title.starts_with("Theirs"))
+        })
+        .unwrap();
+    app.resolve_conflict(Resolution::Theirs);
+
+    assert_eq!(app.apply_conflict_resolutions().unwrap(), 1);
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "# A\n\nbody\n");
+    assert_eq!(fs::read_to_string(&merged).unwrap(), "theirs\n");
+}
+
+#[test]
+fn test_reload_keeps_cursor_on_section() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "# A\n\na\n\n## B\n\nb\n\n# C\n\nc\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+    let format = MarkdownFormat;
+    let sections = format.extract_sections(file.path()).unwrap();
+    let mut app = AppState::new(vec![file.path().to_path_buf()], sections, 100);
+
+    // Nothing moved, as after asterism's own saves
+    assert!(!app.reload_file(&path).unwrap());
+
+    app.current_node_index = 1;
+    let before = app.get_current_section().unwrap().line_start;
+    fs::write(&path, "# New\n\n# A\n\nmore\na\n\n## B\n\nb\n").unwrap();
+    assert!(app.reload_file(&path).unwrap());
+
+    let section = app.get_current_section().unwrap();
+    assert_eq!(section.title, "B");
+    assert_eq!(section.line_start, before + 3);
+    assert_eq!(app.sections.len(), 3);
+}
+
+#[test]
+fn test_write_all_writes_unsaved_sections() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "# A\n\na\n\n# B\n\nb\n\n# C\n\nc\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+    let sections = MarkdownFormat.extract_sections(file.path()).unwrap();
+    let mut app = AppState::new(vec![file.path().to_path_buf()], sections, 100);
+    assert!(!app.guard_quit());
+
+    app.sections[0].section_content = Some(vec![String::new(), "first".to_string()]);
+    app.sections[2].section_content = Some(vec![String::new(), "third".to_string()]);
+    assert_eq!(app.unsaved_sections(), vec!["A", "C"]);
+    assert!(app.file_dirty(&path));
+    assert!(app.guard_quit());
+    assert!(app.message.as_deref().unwrap().starts_with("Unsaved: A, C"));
+
+    assert_eq!(app.write_all().unwrap(), 2);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# A\n\nfirst\n\n# B\n\nb\n\n# C\n\nthird\n\n"
+    );
+    assert!(app.unsaved_sections().is_empty());
+    assert!(!app.guard_quit());
+}
+
+#[test]
+fn test_reflow_on_save_wraps_prose() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "# A\n\na\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+    let sections = MarkdownFormat.extract_sections(file.path()).unwrap();
+    let mut app = AppState::new(vec![file.path().to_path_buf()], sections, 12);
+    app.reflow_on_save = true;
+
+    app.enter_detail_view();
+    app.editor_state.as_mut().unwrap().lines =
+        edtui::Lines::from("\none two three four five\n\n    code that is not wrapped");
+    app.save_current().unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# A\n\none two\nthree four\nfive\n\n    code that is not wrapped\n\n"
+    );
+}
+
+#[test]
+fn test_folders_nest_and_fold() {
+    use crate::section::NodeType;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut files = Vec::new();
+    let mut sections = Vec::new();
+    for name in [
+        "docs/b/README.md",
+        "docs/a/README.md",
+        "top.md",
+        "docs/a/README.md",
+    ] {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "# Title\n\nbody\n").unwrap();
+        if !files.contains(&path) {
+            sections.extend(MarkdownFormat.extract_sections(&path).unwrap());
+        }
+        files.push(path);
+    }
+    let mut app = AppState::new(files, sections, 100);
+
+    let rows = |app: &AppState| -> Vec<(String, usize)> {
+        app.tree_nodes
+            .iter()
+            .map(|node| {
+                let label = match &node.node_type {
+                    NodeType::Directory { name, .. } => format!("{name}/"),
+                    NodeType::File { name, .. } => name.clone(),
+                    NodeType::Section(section) => format!("# {}", section.title),
+                };
+                (label, node.tree_level)
+            })
+            .collect()
+    };
+    let expected = [
+        ("docs/", 0),
+        ("a/", 1),
+        ("README.md", 2),
+        ("# Title", 3),
+        ("b/", 1),
+        ("README.md", 2),
+        ("# Title", 3),
+        ("top.md", 0),
+        ("# Title", 1),
+    ];
+    assert_eq!(
+        rows(&app),
+        expected.map(|(label, level)| (label.to_string(), level))
+    );
+
+    // The cursor starts on the first section and stops on folders on its way up
+    assert_eq!(app.current_node_index, 3);
+    app.perform(Action::Up);
+    assert_eq!(app.current_node_index, 1);
+
+    app.perform(Action::Open);
+    assert_eq!(app.current_node_index, 1);
+    assert!(app.current_directory().unwrap().ends_with('a'));
+    assert_eq!(rows(&app)[1], ("a (1 file)/".to_string(), 1));
+    assert_eq!(rows(&app)[2], ("b/".to_string(), 1));
+
+    app.perform(Action::Child);
+    assert_eq!(app.tree_nodes.len(), expected.len());
+}
+
+#[test]
+fn test_unparsed_folders_parse_when_unfolded() {
+    let dir = tempfile::tempdir().unwrap();
+    let top = dir.path().join("top.md");
+    let nested = dir.path().join("docs/nested.md");
+    fs::create_dir_all(nested.parent().unwrap()).unwrap();
+    fs::write(&top, "# Top\n").unwrap();
+    fs::write(&nested, "# Nested\n\n## Later\n").unwrap();
+
+    let format: Arc<dyn Format> = Arc::new(MarkdownFormat);
+    let mut formats = HashMap::new();
+    for path in [&top, &nested] {
+        formats.insert(path.to_string_lossy().to_string(), Arc::clone(&format));
+    }
+    let sections = MarkdownFormat.extract_sections(&top).unwrap();
+    let unparsed = [nested.to_string_lossy().to_string()].into();
+    let mut app = AppState::new(vec![top, nested], sections, 100)
+        .with_formats(formats)
+        .with_unparsed(unparsed, None);
+
+    // The deferred document's folder starts folded, with nothing parsed beneath it
+    assert_eq!(app.sections.len(), 1);
+    let folder = app
+        .tree_nodes
+        .iter()
+        .position(|node| matches!(node.node_type, crate::section::NodeType::Directory { .. }))
+        .unwrap();
+    app.current_node_index = folder;
+
+    app.perform(Action::Open);
+    assert!(app.unparsed.is_empty());
+    let titles: Vec<&str> = app.sections.iter().map(|s| s.title.as_str()).collect();
+    assert_eq!(titles, ["Top", "Nested", "Later"]);
+    assert_eq!(app.tree_nodes.len(), 6);
+}
+
+#[test]
+fn test_section_ids_survive_reparse() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        "# Guide\n\n## Examples\n\na\n\n# Reference\n\n## Examples\n\nb\n"
+    )
+    .unwrap();
+    let path = file.path().to_path_buf();
+    let sections = MarkdownFormat.extract_sections(&path).unwrap();
+    let mut app = AppState::new(vec![path.clone()], sections, 100);
+
+    let ids: Vec<(Vec<String>, usize)> = app
+        .sections
+        .iter()
+        .map(|s| (s.id.titles.clone(), s.id.occurrence))
+        .collect();
+    assert_eq!(ids[3], (vec!["Reference".into(), "Examples".into()], 0));
+
+    // Saving the second "Examples" keeps the cursor on it, not the first one
+    let second = app
+        .tree_nodes
+        .iter()
+        .position(|n| n.section_index == Some(3))
+        .unwrap();
+    app.current_node_index = second;
+    app.enter_detail_view();
+    app.editor_state.as_mut().unwrap().lines = edtui::Lines::from("b\nmore\nlines");
+    app.save_current().unwrap();
+    let id = &app.get_current_section().unwrap().id;
+    assert_eq!(id.titles, ["Reference", "Examples"]);
+    app.exit_detail_view(true);
+
+    // A saved plan finds its section by ID after lines are added above it
+    let mut plan = app.generate_edit_plan();
+    plan.edits.retain(|edit| edit.item_name == "Examples");
+    plan.edits.truncate(1);
+    plan.edits[0].section_content = "planned".to_string();
+    let content = fs::read_to_string(&path).unwrap();
+    fs::write(&path, format!("Intro\n\n{content}")).unwrap();
+    let sections = MarkdownFormat.extract_sections(&path).unwrap();
+    let mut app = AppState::new(vec![path], sections, 100);
+    app.load_docs(plan);
+    let staged: Vec<&str> = app
+        .sections
+        .iter()
+        .filter(|s| s.section_content.is_some())
+        .map(|s| s.id.titles[0].as_str())
+        .collect();
+    assert_eq!(staged, ["Reference"]);
+}
+
+#[test]
+fn test_reorder_rewrites_only_reordered_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("a.md");
+    let merged = dir.path().join("b.txt");
+    let conflicted = "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> x\n";
+    fs::write(&notes, "# A\n\na\n\n# B\n\nb\n").unwrap();
+    fs::write(&merged, conflicted).unwrap();
+
+    let markdown: Arc<dyn Format> = Arc::new(MarkdownFormat);
+    let conflict: Arc<dyn Format> = Arc::new(ConflictFormat);
+    let mut sections = markdown.extract_sections(&notes).unwrap();
+    sections.extend(conflict.extract_sections(&merged).unwrap());
+    let formats = HashMap::from([
+        (notes.to_string_lossy().to_string(), markdown),
+        (merged.to_string_lossy().to_string(), conflict),
+    ]);
+    let mut app =
+        AppState::new(vec![notes.clone(), merged.clone()], sections, 100).with_formats(formats);
+    let conflict_sections = app.sections.len() - 2;
+
+    app.current_node_index = app
+        .tree_nodes
+        .iter()
+        .position(|n| {
+            n.section_index
+                .is_some_and(|i| app.sections[i].title == "B")
+        })
+        .unwrap();
+    app.start_move();
+    assert!(app.move_section_up());
+    app.save_section_reorder().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&notes).unwrap(),
+        "# B\n\nb\n\n# A\n\na\n\n"
+    );
+    assert_eq!(fs::read_to_string(&merged).unwrap(), conflicted);
+    assert_eq!(app.sections.len(), 2 + conflict_sections);
+    assert_eq!(app.sections[0].title, "B");
+
+    // Sections of a format that cannot be reordered are never rewritten
+    app.current_node_index = app
+        .tree_nodes
+        .iter()
+        .position(|n| n.section_index == Some(0))
+        .unwrap();
+    app.start_move();
+    let last = app.sections.len() - 1;
+    app.sections.swap(last - 1, last);
+    app.mark_moved();
+    assert!(app.save_section_reorder().is_err());
+    assert_eq!(fs::read_to_string(&merged).unwrap(), conflicted);
+}
+
+#[test]
+fn test_save_keeps_other_unsaved_sections() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "# A\n\na\n\n# B\n\nb\n").unwrap();
+    let path = file.path().to_path_buf();
+    let sections = MarkdownFormat.extract_sections(&path).unwrap();
+    let mut app = AppState::new(vec![path.clone()], sections, 100);
+    let open = |app: &mut AppState, title: &str| {
+        app.current_node_index = app
+            .tree_nodes
+            .iter()
+            .position(|n| {
+                n.section_index
+                    .is_some_and(|i| app.sections[i].title == title)
+            })
+            .unwrap();
+        app.enter_detail_view();
+    };
+
+    open(&mut app, "A");
+    app.editor_state.as_mut().unwrap().lines = edtui::Lines::from("first");
+    app.exit_detail_view(true);
+    open(&mut app, "B");
+    app.editor_state.as_mut().unwrap().lines = edtui::Lines::from("second");
+    app.save_current().unwrap();
+    app.exit_detail_view(false);
+
+    assert_eq!(app.unsaved_sections(), vec!["A"]);
+    assert!(app.guard_quit());
+    assert_eq!(app.write_all().unwrap(), 1);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# A\n\nfirst\n\n# B\n\nsecond\n\n"
+    );
+}
+
+#[test]
+fn test_resolution_survives_saving_another_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("a.md");
+    let merged = dir.path().join("b.txt");
+    fs::write(&notes, "# A\n\na\n").unwrap();
+    fs::write(&merged, "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> x\n").unwrap();
+
+    let markdown: Arc<dyn Format> = Arc::new(MarkdownFormat);
+    let conflict: Arc<dyn Format> = Arc::new(ConflictFormat);
+    let mut sections = markdown.extract_sections(&notes).unwrap();
+    sections.extend(conflict.extract_sections(&merged).unwrap());
+    let formats = HashMap::from([
+        (notes.to_string_lossy().to_string(), markdown),
+        (merged.to_string_lossy().to_string(), conflict),
+    ]);
+    let mut app =
+        AppState::new(vec![notes.clone(), merged.clone()], sections, 100).with_formats(formats);
+    let select = |app: &mut AppState, title: &str| {
+        app.current_node_index = app
+            .tree_nodes
+            .iter()
+            .position(|n| {
+                n.section_index
+                    .is_some_and(|i| app.sections[i].title.starts_with(title))
+            })
+            .unwrap();
+    };
+
+    select(&mut app, "Theirs");
+    app.resolve_conflict(Resolution::Theirs);
+
+    // Saving a.md moves its sections after b.txt's
+    select(&mut app, "A");
+    app.enter_detail_view();
+    app.editor_state.as_mut().unwrap().lines = edtui::Lines::from("edited");
+    app.save_current().unwrap();
+    app.exit_detail_view(false);
+
+    assert_eq!(app.apply_conflict_resolutions().unwrap(), 1);
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "# A\n\nedited\n\n");
+    assert_eq!(fs::read_to_string(&merged).unwrap(), "theirs\n");
+}
@@ -0,0 +1,97 @@
+use super::{backup_path, rollback, write_file, write_files, WriteOptions};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_write_file_leaves_no_temporary() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("doc.md");
+    fs::write(&path, "old\n").unwrap();
+
+    write_file(&path, b"new\n").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_backup_keeps_previous_contents() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("doc.md");
+    fs::write(&path, "old\n").unwrap();
+
+    write_files(
+        &[(path.clone(), "new\n".to_string())],
+        WriteOptions { backup: true },
+    )
+    .unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+    assert_eq!(fs::read_to_string(backup_path(&path, 0)).unwrap(), "old\n");
+}
+
+#[test]
+fn test_backup_never_replaces_existing_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("doc.md");
+    fs::write(&path, "old\n").unwrap();
+    fs::write(backup_path(&path, 0), "kept\n").unwrap();
+    let backup = WriteOptions { backup: true };
+
+    write_files(&[(path.clone(), "new\n".to_string())], backup).unwrap();
+    write_files(&[(path.clone(), "newer\n".to_string())], backup).unwrap();
+
+    assert_eq!(fs::read_to_string(backup_path(&path, 0)).unwrap(), "kept\n");
+    assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "old\n");
+    assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "new\n");
+}
+
+#[test]
+fn test_failed_write_restores_earlier_files() {
+    let dir = tempdir().unwrap();
+    let first = dir.path().join("a.md");
+    let created = dir.path().join("b.md");
+    fs::write(&first, "a\n").unwrap();
+    fs::write(backup_path(&first, 0), "earlier\n").unwrap();
+    let unwritable = dir.path().join("missing").join("c.md");
+
+    let err = write_files(
+        &[
+            (first.clone(), "changed\n".to_string()),
+            (created.clone(), "new\n".to_string()),
+            (unwritable, "lost\n".to_string()),
+        ],
+        WriteOptions { backup: true },
+    )
+    .unwrap_err();
+
+    assert_eq!(fs::read_to_string(&first).unwrap(), "a\n");
+    assert!(!created.exists());
+    assert_eq!(
+        fs::read_to_string(backup_path(&first, 0)).unwrap(),
+        "earlier\n"
+    );
+    assert!(!backup_path(&first, 1).exists());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    let message = err.to_string();
+    assert!(message.starts_with("Could not write"));
+    assert!(message.contains("restored") && message.contains("a.md") && message.contains("b.md"));
+}
+
+#[test]
+fn test_unrestored_file_keeps_backup() {
+    let dir = tempdir().unwrap();
+    let lost = dir.path().join("missing").join("a.md");
+    let backup = dir.path().join("a.md.orig");
+    fs::write(&backup, "a\n").unwrap();
+
+    let outcome = rollback(
+        &[(lost, "changed\n".to_string())],
+        &[Some(b"a\n".to_vec())],
+        &[Some(backup.clone())],
+    );
+
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "a\n");
+    assert!(outcome.contains("could not restore"));
+    assert!(outcome.ends_with(&format!("; backups kept at {}", backup.display())));
+}
@@ -0,0 +1,72 @@
+use super::{discover, Config, CONFIG_FILE};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_later_files_override_earlier() {
+    let dir = tempdir().unwrap();
+    let user = dir.path().join("user.toml");
+    let repo = dir.path().join("repo.toml");
+    fs::write(
+        &user,
+        "wrap_width = 80\nbackup = true\n\n[keys]\nK = \"move-up\"\n\n[theme]\nname = \"light\"\n\n[[custom_formats]]\nname = \"a\"\ngrammar = \"markdown\"\nsection_query = \"\"\ntitle_query = \"\"\n",
+    )
+    .unwrap();
+    fs::write(
+        &repo,
+        "wrap_width = 120\n\n[keys]\nJ = \"move-down\"\n\n[theme]\nglyphs = \"ascii\"\n\n[[custom_formats]]\nname = \"b\"\ngrammar = \"markdown\"\nsection_query = \"\"\ntitle_query = \"\"\n",
+    )
+    .unwrap();
+
+    let config = Config::from_files(&[user, repo]).unwrap();
+    assert_eq!(config.wrap_width, 120);
+    assert!(config.backup);
+    assert_eq!(config.keys.len(), 2);
+    assert_eq!(config.theme.name.as_deref(), Some("light"));
+    assert_eq!(config.theme.glyphs.as_deref(), Some("ascii"));
+    assert_eq!(config.custom_formats.len(), 2);
+}
+
+#[test]
+fn test_parse_errors_name_the_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join(CONFIG_FILE);
+    fs::write(&path, "wrap_width = \"wide\"\n").unwrap();
+
+    let Err(err) = Config::from_files(std::slice::from_ref(&path)) else {
+        panic!("invalid wrap_width was accepted");
+    };
+    assert!(err.to_string().starts_with(&path.display().to_string()));
+}
+
+#[test]
+fn test_discover_finds_repository_and_working_directory() {
+    let dir = tempdir().unwrap();
+    let nested = dir.path().join("docs");
+    fs::create_dir_all(dir.path().join(".git")).unwrap();
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(dir.path().join(CONFIG_FILE), "").unwrap();
+    fs::write(nested.join(CONFIG_FILE), "").unwrap();
+
+    let found = discover(&nested);
+    assert!(found.ends_with(&[dir.path().join(CONFIG_FILE), nested.join(CONFIG_FILE)]));
+}
+
+#[test]
+fn test_show_round_trips() {
+    let config = Config {
+        wrap_width: 72,
+        file_extensions: vec!["md".to_string(), "mdx".to_string()],
+        keys: [("K".to_string(), "move-up".to_string())].into(),
+        ..Config::default()
+    };
+
+    let shown = config.to_toml();
+    let dir = tempdir().unwrap();
+    let path = dir.path().join(CONFIG_FILE);
+    fs::write(&path, &shown).unwrap();
+    let reread = Config::from_files(&[path]).unwrap();
+    assert_eq!(reread.wrap_width, 72);
+    assert_eq!(reread.file_extensions, ["md", "mdx"]);
+    assert_eq!(reread.keys["K"], "move-up");
+}
@@ -0,0 +1,103 @@
+use super::{parse_conflicts, resolve, ConflictFormat, Resolution};
+#[cfg(feature = "tui")]
+use crate::app_state::AppState;
+use crate::formats::Format;
+#[cfg(feature = "tui")]
+use std::collections::HashMap;
+#[cfg(feature = "tui")]
+use std::fs;
+#[cfg(feature = "tui")]
+use std::sync::Arc;
+#[cfg(feature = "tui")]
+use tempfile::NamedTempFile;
+
+const MERGED: &str = "\
+start
+<<<<<<< HEAD
+ours
+||||||| base
+original
+=======
+theirs 1
+theirs 2
+>>>>>>> feature
+end
+";
+
+#[test]
+fn test_conflict_sections_with_sides() {
+    let sections = parse_conflicts(MERGED, "f.txt").unwrap();
+
+    let titles: Vec<_> = sections.iter().map(|s| s.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "Conflict 1 (line 2)",
+            "Ours (HEAD)",
+            "Base (base)",
+            "Theirs (feature)"
+        ]
+    );
+    assert_eq!((sections[0].line_start, sections[0].line_end), (1, 9));
+    assert_eq!(sections[0].children_indices, vec![1, 2, 3]);
+    assert_eq!((sections[3].line_start, sections[3].line_end), (6, 8));
+    assert_eq!(
+        &MERGED[sections[3].byte_start..sections[3].byte_end],
+        "theirs 1\ntheirs 2\n"
+    );
+}
+
+#[test]
+fn test_resolutions() {
+    let lines: Vec<&str> = MERGED.lines().collect();
+    let conflict = &lines[1..9];
+
+    assert_eq!(resolve(conflict, Resolution::Ours).unwrap(), vec!["ours"]);
+    assert_eq!(
+        resolve(conflict, Resolution::Both).unwrap(),
+        vec!["ours", "theirs 1", "theirs 2"]
+    );
+    assert_eq!(
+        resolve(conflict, Resolution::Base).unwrap(),
+        vec!["original"]
+    );
+
+    let two_way = ["<<<<<<< HEAD", "a", "=======", "b", ">>>>>>> x"];
+    assert!(resolve(&two_way, Resolution::Base).is_none());
+}
+
+#[test]
+fn test_sniffed_and_unterminated() {
+    assert!(ConflictFormat.sniff(MERGED));
+    assert!(!ConflictFormat.sniff("# Heading\n\n=======\n"));
+    assert!(parse_conflicts("<<<<<<< HEAD\nours\n=======\n", "f.txt").is_err());
+}
+
+#[test]
+#[cfg(feature = "tui")]
+fn test_saving_unchanged_side_keeps_file() {
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), MERGED).unwrap();
+    let path = file.path().to_path_buf();
+
+    let sections = parse_conflicts(MERGED, &path.to_string_lossy()).unwrap();
+    let format: Arc<dyn Format> = Arc::new(ConflictFormat);
+    let mut app = AppState::new(vec![path.clone()], sections, 100).with_formats(HashMap::from([(
+        path.to_string_lossy().to_string(),
+        format,
+    )]));
+
+    for _ in 0..2 {
+        app.current_node_index = app
+            .tree_nodes
+            .iter()
+            .position(|n| {
+                n.section_index
+                    .is_some_and(|i| app.sections[i].title == "Ours (HEAD)")
+            })
+            .unwrap();
+        app.enter_detail_view();
+        app.save_current().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), MERGED);
+    }
+}
@@ -0,0 +1,34 @@
+use super::DiffStats;
+use crate::formats::difftastic::parse_difftastic_json;
+
+#[test]
+fn test_lines_counted_by_side() {
+    let json = r#"{"chunks":[[{"lhs":{"line_number":1,"changes":[]},"rhs":{"line_number":1,"changes":[]}},{"rhs":{"line_number":2,"changes":[]}},{"rhs":{"line_number":3,"changes":[]}}],[{"lhs":{"line_number":9,"changes":[]}}]],"language":"Rust","path":"lib.rs","status":"changed"}"#;
+
+    let sections = parse_difftastic_json(json).unwrap();
+    let stats = DiffStats::of_sections(&sections);
+
+    assert_eq!(
+        stats,
+        DiffStats {
+            added: 2,
+            removed: 1,
+            modified: 1
+        }
+    );
+    assert_eq!(stats.churn(), 4);
+    assert_eq!(stats.to_string(), "+2 -1 ~1");
+}
+
+#[test]
+fn test_created_file_counted_from_range() {
+    let json = r#"{"language":"Rust","path":"does/not/exist.rs","status":"created"}"#;
+
+    let sections = parse_difftastic_json(json).unwrap();
+
+    assert_eq!(DiffStats::of_sections(&sections), DiffStats::default());
+    assert_eq!(
+        sections[0].hunk.as_ref().unwrap().language.as_deref(),
+        Some("Rust")
+    );
+}
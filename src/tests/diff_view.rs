@@ -0,0 +1,59 @@
+use super::DiffView;
+use crate::formats::difftastic::parse_difftastic_json;
+
+// `let x = 1;` became `let x = 2;` on line 3, and line 6 was inserted
+const JSON: &str = r#"{"chunks":[[{"lhs":{"line_number":2,"changes":[{"start":8,"end":9,"content":"1","highlight":"normal"}]},"rhs":{"line_number":2,"changes":[{"start":8,"end":9,"content":"2","highlight":"normal"}]}},{"rhs":{"line_number":5,"changes":[{"start":0,"end":7,"content":"added()","highlight":"normal"}]}}]],"language":"Rust","path":"lib.rs","status":"changed"}"#;
+
+const RHS: &str = "a\nb\nlet x = 2;\nc\nd\nadded()\ne\nf\ng\nh\n";
+
+fn hunk() -> crate::formats::difftastic::Hunk {
+    let sections = parse_difftastic_json(JSON).unwrap();
+    *sections[0].hunk.clone().unwrap()
+}
+
+#[test]
+fn test_rows_align_changes_with_context() {
+    let view = DiffView::new(&hunk(), None, Some(RHS));
+
+    let rhs: Vec<_> = view
+        .rows
+        .iter()
+        .map(|row| row.rhs.as_ref().map(|c| c.text.as_str()))
+        .collect();
+    assert_eq!(
+        rhs,
+        vec![
+            Some("a"),
+            Some("b"),
+            Some("let x = 2;"),
+            Some("c"),
+            Some("d"),
+            Some("added()"),
+            Some("e"),
+            Some("f"),
+            Some("g"),
+        ]
+    );
+
+    // The inserted line has no original counterpart
+    assert!(view.rows[5].lhs.is_none());
+    assert!(view.rows[5].rhs.as_ref().unwrap().changed);
+    assert_eq!(view.rows[6].lhs.as_ref().unwrap().line_number, 5);
+}
+
+#[test]
+fn test_lhs_rebuilt_from_rhs_without_original() {
+    let view = DiffView::new(&hunk(), None, Some(RHS));
+
+    let lhs = view.rows[2].lhs.as_ref().unwrap();
+    assert_eq!(lhs.text, "let x = 1;");
+    assert_eq!((lhs.changes[0].start, lhs.changes[0].end), (8, 9));
+}
+
+#[test]
+fn test_original_source_preferred() {
+    let lhs = "a\nb\nlet  x = 1;\nc\nd\ne\n";
+    let view = DiffView::new(&hunk(), Some(lhs), Some(RHS));
+
+    assert_eq!(view.rows[2].lhs.as_ref().unwrap().text, "let  x = 1;");
+}
@@ -1,4 +1,5 @@
-use crate::formats::difftastic::parse_difftastic_json;
+use crate::formats::difftastic::{parse_difftastic_json, HunkRange};
+use crate::section::ChunkType;
 
 #[test]
 fn test_parse_single_file_diff() {
@@ -123,3 +124,44 @@ fn test_file_path_tracking() {
         "Should preserve full file path"
     );
 }
+
+#[test]
+fn test_hunk_header_spans_chunk_lines() {
+    // One chunk changing lines 3 and 5, then an insertion of two lines after line 9
+    let json = r#"{"chunks":[[{"lhs":{"line_number":2,"changes":[]},"rhs":{"line_number":2,"changes":[]}},{"lhs":{"line_number":4,"changes":[]},"rhs":{"line_number":4,"changes":[]}}],[{"rhs":{"line_number":9,"changes":[]}},{"rhs":{"line_number":10,"changes":[]}}]],"language":"Text","path":"test.md","status":"changed"}"#;
+
+    let sections = parse_difftastic_json(json).unwrap();
+
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[0].title, "Hunk 1 @@ -3,3 +3,3 @@");
+    assert_eq!(sections[1].title, "Hunk 2 @@ -9,0 +10,2 @@");
+    assert_eq!((sections[1].line_start, sections[1].line_end), (9, 11));
+}
+
+#[test]
+fn test_hunks_classified_with_side_text() {
+    let json = r#"{"chunks":[[{"lhs":{"line_number":1,"changes":[{"start":0,"end":3,"content":"old","highlight":"normal"}]},"rhs":{"line_number":1,"changes":[{"start":0,"end":3,"content":"new","highlight":"normal"}]}}],[{"rhs":{"line_number":6,"changes":[{"start":0,"end":5,"content":"extra","highlight":"normal"}]}}]],"language":"Text","path":"a.txt","status":"changed"}
+{"language":"Text","path":"gone.txt","status":"deleted"}"#;
+
+    let sections = parse_difftastic_json(json).unwrap();
+
+    let side = |idx: usize, lhs: bool| {
+        let line = &sections[idx].hunk.as_ref().unwrap().lines[0];
+        let side = if lhs { &line.lhs } else { &line.rhs };
+        side.as_ref().map(|side| side.changes[0].content.clone())
+    };
+    assert_eq!(sections[0].chunk_type(), Some(ChunkType::Modified));
+    assert_eq!(side(0, true).as_deref(), Some("old"));
+    assert_eq!(side(0, false).as_deref(), Some("new"));
+    assert_eq!(sections[1].chunk_type(), Some(ChunkType::Added));
+    assert_eq!(side(1, true), None);
+    assert_eq!(sections[2].chunk_type(), Some(ChunkType::Deleted));
+}
+
+#[test]
+fn test_hunk_header_round_trips() {
+    let range: HunkRange = "@@ -3,2 +4 @@ fn main()".parse().unwrap();
+    assert_eq!(range.to_string(), "@@ -3,2 +4,1 @@");
+    assert_eq!(range.chunk_type(), ChunkType::Modified);
+    assert!("@@ -x +1 @@".parse::<HunkRange>().is_err());
+}
@@ -0,0 +1,80 @@
+use super::*;
+use crate::formats::jupyter::NotebookFormat;
+use crate::formats::markdown::MarkdownFormat;
+
+const GUIDE: &str = "Preamble line\n\n# Guide\n\nIntro.\n\n## Examples\n\nFirst.\n\n# Reference\n\n## Examples\n\nSecond.\n";
+
+fn guide() -> Document {
+    Document::parse(GUIDE, Arc::new(MarkdownFormat)).unwrap()
+}
+
+#[test]
+fn test_lookup_by_heading_path() {
+    let doc = guide();
+    assert_eq!(doc.to_string(), GUIDE);
+    assert_eq!(doc.get_body(&["Guide", "Examples"]).unwrap(), "First.");
+    assert_eq!(doc.get_body(&["Reference", "Examples"]).unwrap(), "Second.");
+    assert_eq!(doc.get_body(&["Guide"]).unwrap(), "Intro.");
+    assert_eq!(doc.find(&["Reference"]).unwrap().level, 1);
+
+    let missing = doc.get_body(&["Examples"]).unwrap_err();
+    assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_set_body_insert_and_rename() {
+    let mut doc = guide();
+    doc.set_body(&["Guide", "Examples"], "Replaced\nbody")
+        .unwrap();
+    doc.insert_child(&["Guide"], "Setup", "Install it.")
+        .unwrap();
+    doc.insert_child(&[], "Appendix", "").unwrap();
+    doc.rename(&["Reference"], "API").unwrap();
+
+    assert_eq!(
+        doc.to_string(),
+        "Preamble line\n\n# Guide\n\nIntro.\n\n## Examples\n\nReplaced\nbody\n\n## Setup\n\nInstall it.\n\n# API\n\n## Examples\n\nSecond.\n\n# Appendix\n"
+    );
+    assert_eq!(doc.get_body(&["API", "Examples"]).unwrap(), "Second.");
+    assert!(doc.rename(&["API"], "Two\nlines").is_err());
+}
+
+#[test]
+fn test_move_subtree_relevels_headings() {
+    let mut doc = guide();
+    doc.move_subtree(&["Reference"], &["Guide", "Examples"])
+        .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "Preamble line\n\n# Guide\n\nIntro.\n\n## Examples\n\nFirst.\n\n### Reference\n\n#### Examples\n\nSecond.\n"
+    );
+    assert_eq!(
+        doc.get_body(&["Guide", "Examples", "Reference", "Examples"])
+            .unwrap(),
+        "Second."
+    );
+
+    doc.move_subtree(&["Guide", "Examples", "Reference"], &[])
+        .unwrap();
+    assert_eq!(doc.find(&["Reference", "Examples"]).unwrap().level, 2);
+
+    let into_itself = doc.move_subtree(&["Guide"], &["Guide", "Examples"]);
+    assert_eq!(into_itself.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_open_and_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("guide.md");
+    std::fs::write(&path, GUIDE).unwrap();
+
+    let mut doc = Document::open(&path, Arc::new(MarkdownFormat)).unwrap();
+    doc.set_body(&["Guide"], "Updated.").unwrap();
+    doc.save().unwrap();
+    let reopened = Document::open(&path, Arc::new(MarkdownFormat)).unwrap();
+    assert_eq!(reopened.get_body(&["Guide"]).unwrap(), "Updated.");
+
+    assert!(guide().save().is_err());
+    let notebook = Document::parse("{}", Arc::new(NotebookFormat));
+    assert_eq!(notebook.err().unwrap().kind(), io::ErrorKind::Unsupported);
+}
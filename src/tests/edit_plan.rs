@@ -18,6 +18,8 @@ fn test_single_line_replacement() {
         column_end: 7,
         section_content: "Modified".to_string(), // No padding
         item_name: "test".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan { edits: vec![edit] };
@@ -46,6 +48,8 @@ fn test_section_replacement_with_empty_lines() {
         column_end: 2,
         section_content: "Yeah".to_string(),
         item_name: "Hello".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan { edits: vec![edit] };
@@ -83,6 +87,8 @@ fn test_boundary_mode_exclude() {
         column_end: 2,
         section_content: "REPLACED".to_string(), // No padding
         item_name: "test".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan { edits: vec![edit] };
@@ -117,6 +123,8 @@ fn test_line_numbering_off_by_one() {
         column_end: 7,
         section_content: "SECOND".to_string(),
         item_name: "test".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan { edits: vec![edit] };
@@ -204,6 +212,8 @@ fn test_line_indexing_zero_vs_one() {
         column_end: 7,
         section_content: "ZERO".to_string(),
         item_name: "test".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan {
@@ -225,6 +235,8 @@ fn test_line_indexing_zero_vs_one() {
         column_end: 7,
         section_content: "ONE".to_string(),
         item_name: "test".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan2 = EditPlan {
@@ -265,6 +277,8 @@ fn test_app_section_to_textum_conversion() {
         column_end: sections[0].column_end,
         section_content: "Yeah".to_string(),
         item_name: "Hello".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     println!("\nEdit structure:");
@@ -333,6 +347,8 @@ fn test_exact_scenario() {
         column_end: sec.column_end,
         section_content: "Yeah".to_string(),
         item_name: "Hello".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan { edits: vec![edit] };
@@ -400,6 +416,8 @@ fn test_diagnose_line_numbers() {
         column_end: sec.column_end,
         section_content: "Yeah".to_string(),
         item_name: "Hello".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     println!(
@@ -446,6 +464,8 @@ fn test_textum_line_behavior() {
         column_end: 0,
         section_content: "REPLACED".to_string(),
         item_name: "test".to_string(),
+        verbatim: false,
+        section_id: None,
     };
 
     let mut plan = EditPlan { edits: vec![edit] };
@@ -477,3 +497,38 @@ fn test_textum_line_behavior() {
         "Line 3 should be unchanged and budged along"
     );
 }
+
+#[test]
+fn test_verbatim_replacement_keeps_lines_exact() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "a\nb\nc\nd\n").unwrap();
+    let path = file.path().to_string_lossy().to_string();
+
+    let edit = |line_start, line_end, content: &str| Edit {
+        file_name: path.clone(),
+        line_start,
+        line_end,
+        column_start: 0,
+        column_end: 0,
+        section_content: content.to_string(),
+        item_name: "hunk".to_string(),
+        verbatim: true,
+        section_id: None,
+    };
+
+    // Replace one line with two, delete one, and insert at both ends
+    let mut plan = EditPlan {
+        edits: vec![
+            edit(1, 2, "B1\nB2"),
+            edit(3, 4, ""),
+            edit(0, 0, "start"),
+            edit(4, 4, "end"),
+        ],
+    };
+    plan.apply().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "start\na\nB1\nB2\nc\nend\n"
+    );
+}
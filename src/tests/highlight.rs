@@ -0,0 +1,24 @@
+use super::{find_syntax, syntax_extension, LineHighlighter};
+
+#[test]
+fn test_syntax_found_by_language_then_extension() {
+    let by_language = find_syntax(Some("Python"), "script").unwrap();
+    assert_eq!(by_language.name, "Python");
+    assert_eq!(syntax_extension(by_language), "py");
+
+    // Languages syntect does not know fall back to the path
+    let by_path = find_syntax(Some("Nonexistent"), "src/lib.rs").unwrap();
+    assert_eq!(by_path.name, "Rust");
+
+    assert!(find_syntax(Some("Text"), "notes.txt").is_none());
+}
+
+#[test]
+fn test_highlighted_pieces_cover_line() {
+    let mut highlighter = LineHighlighter::new(find_syntax(Some("Rust"), "").unwrap());
+    let pieces = highlighter.styles("let x = 1;");
+
+    assert!(pieces.len() > 1);
+    let text: String = pieces.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(text, "let x = 1;");
+}
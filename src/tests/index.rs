@@ -0,0 +1,67 @@
+use super::{parse_documents, ParseIndex};
+use crate::formats::markdown::MarkdownFormat;
+use crate::formats::Format;
+use std::fs::{self, File};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tempfile::tempdir;
+
+#[test]
+fn test_parse_documents_in_order_with_progress() {
+    let dir = tempdir().unwrap();
+    let format: Arc<dyn Format> = Arc::new(MarkdownFormat);
+    let documents: Vec<_> = (0..20)
+        .map(|i| {
+            let path = dir.path().join(format!("{i}.md"));
+            fs::write(&path, format!("# Doc {i}\n\ntext\n")).unwrap();
+            (path, Arc::clone(&format))
+        })
+        .collect();
+
+    let calls = AtomicUsize::new(0);
+    let results = parse_documents(&documents, None, &|_, total| {
+        assert_eq!(total, 20);
+        calls.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(calls.into_inner(), 20);
+    for (i, result) in results.into_iter().enumerate() {
+        let parsed = result.unwrap();
+        assert_eq!(parsed.sections[0].title, format!("Doc {i}"));
+        assert!(parsed.tree.is_some());
+    }
+}
+
+#[test]
+fn test_index_reuses_unchanged_files() {
+    let dir = tempdir().unwrap();
+    let index_path = dir.path().join("cache/index.json");
+    let doc = dir.path().join("a.md");
+    fs::write(&doc, "# A\n\ntext\n").unwrap();
+    let format: Arc<dyn Format> = Arc::new(MarkdownFormat);
+    let documents = [(doc.clone(), Arc::clone(&format))];
+
+    let mut index = ParseIndex::load(index_path.clone());
+    parse_documents(&documents, Some(&mut index), &|_, _| {});
+    index.save().unwrap();
+
+    let mut index = ParseIndex::load(index_path.clone());
+    let cached = index.lookup(&doc, &MarkdownFormat).unwrap();
+    assert_eq!(cached[0].title, "A");
+    assert_eq!(cached[0].file_path, doc.to_string_lossy());
+
+    // A new modification time alone is settled by the content hash
+    let later = SystemTime::now() + Duration::from_secs(60);
+    File::options()
+        .write(true)
+        .open(&doc)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    assert!(index.lookup(&doc, &MarkdownFormat).is_some());
+
+    fs::write(&doc, "# B\n\ntext\n").unwrap();
+    assert!(index.lookup(&doc, &MarkdownFormat).is_none());
+    let results = parse_documents(&documents, Some(&mut index), &|_, _| {});
+    assert_eq!(results[0].as_ref().unwrap().sections[0].title, "B");
+}
@@ -38,7 +38,12 @@ fn test_find_documents_single_file() {
     let path = file.path().with_extension("md");
     fs::rename(file.path(), &path).unwrap();
 
-    let results = find_documents(vec![path.clone()], &["md".to_string()]).unwrap();
+    let results = find_documents(
+        vec![path.clone()],
+        &["md".to_string()],
+        &Discovery::default(),
+    )
+    .unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0], path);
 
@@ -111,3 +116,119 @@ fn test_tree_sitter_parsing() {
     let mut cursor = root.walk();
     print_tree(&mut cursor, markdown.as_bytes(), 0);
 }
+
+fn discovered(dir: &Path, discovery: &Discovery) -> Vec<String> {
+    let mut found: Vec<String> =
+        find_documents(vec![dir.to_path_buf()], &["md".to_string()], discovery)
+            .unwrap()
+            .iter()
+            .map(|path| {
+                path.strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+    found.sort();
+    found
+}
+
+#[test]
+fn test_discovery_rules() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for file in [
+        "a.md",
+        "notes.txt",
+        "docs/b.md",
+        "docs/deep/c.md",
+        "drafts/d.md",
+        "node_modules/pkg/e.md",
+    ] {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "# T\n").unwrap();
+    }
+    fs::write(root.join(".ignore"), "drafts/\n").unwrap();
+
+    let defaults = Discovery::default();
+    assert_eq!(
+        discovered(root, &defaults),
+        ["a.md", "docs/b.md", "docs/deep/c.md"]
+    );
+
+    let unignored = Discovery {
+        respect_ignore: false,
+        ..Discovery::default()
+    };
+    assert!(discovered(root, &unignored).contains(&"drafts/d.md".to_string()));
+
+    let globbed = Discovery {
+        include: vec!["docs/**".to_string()],
+        exclude: vec!["deep".to_string()],
+        ..Discovery::default()
+    };
+    assert_eq!(discovered(root, &globbed), ["docs/b.md"]);
+
+    let shallow = Discovery {
+        max_depth: Some(1),
+        ..Discovery::default()
+    };
+    assert_eq!(discovered(root, &shallow), ["a.md", "docs/b.md"]);
+
+    let bad_glob = Discovery {
+        include: vec!["[".to_string()],
+        ..Discovery::default()
+    };
+    assert!(find_documents(vec![root.to_path_buf()], &["md".to_string()], &bad_glob).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_discovery_survives_symlink_loops() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::write(root.join("docs/a.md"), "# A\n").unwrap();
+    std::os::unix::fs::symlink(root, root.join("docs/loop")).unwrap();
+    std::os::unix::fs::symlink(root.join("docs"), root.join("alias")).unwrap();
+
+    // Each document is found once, whichever link leads to it first
+    assert_eq!(discovered(root, &Discovery::default()).len(), 1);
+}
+
+#[test]
+fn test_reparse_matches_fresh_parse() {
+    let path = Path::new("notes.md");
+    let versions = [
+        "# One\n\nA\n\n## Two\n\nB\n\n### Three\n\nC\n",
+        "# One\n\nA longer first body\nover two lines\n\n## Two\n\nB\n\n### Three\n\nC\n",
+        "# One\n\nA longer first body\nover two lines\n\n## Two\n\n### Three\n\nC — é\n",
+        "# One\n\n## Inserted\n\nnew\n\n## Two\n\n### Three\n\nC — é\n",
+        "# Renamed\n",
+    ];
+
+    let mut tree = None;
+    for content in versions {
+        let (sections, next) = reparse_sections(content, path, &MarkdownFormat, tree).unwrap();
+        let fresh = parse_sections(content, path, &MarkdownFormat).unwrap();
+        let coordinates = |sections: &[Section]| -> Vec<_> {
+            sections
+                .iter()
+                .map(|s| {
+                    (
+                        s.title.clone(),
+                        s.level,
+                        s.line_start,
+                        s.line_end,
+                        s.byte_start,
+                        s.byte_end,
+                        s.parent_index,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(coordinates(&sections), coordinates(&fresh), "{content}");
+        tree = Some(next);
+    }
+}
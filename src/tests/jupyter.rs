@@ -0,0 +1,152 @@
+use super::NotebookFormat;
+#[cfg(feature = "tui")]
+use crate::app_state::AppState;
+use crate::formats::Format;
+use serde_json::Value;
+#[cfg(feature = "tui")]
+use std::collections::HashMap;
+#[cfg(feature = "tui")]
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "tui")]
+use std::sync::Arc;
+#[cfg(feature = "tui")]
+use tempfile::NamedTempFile;
+
+const NOTEBOOK: &str = r###"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["# Analysis\n", "\n", "Intro text\n", "\n", "## Loading\n", "\n", "Read the data"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 3,
+   "metadata": {"tags": ["setup"]},
+   "outputs": [{"name": "stdout", "output_type": "stream", "text": ["42\n"]}],
+   "source": ["import pandas as pd\n", "df = pd.read_csv('x.csv')"]
+  },
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": "Some notes without a heading"
+  }
+ ],
+ "metadata": {"kernelspec": {"name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"###;
+
+#[test]
+fn test_notebook_hierarchy() {
+    let sections = NotebookFormat
+        .parse(NOTEBOOK, Path::new("analysis.ipynb"))
+        .unwrap();
+
+    let titles: Vec<_> = sections.iter().map(|s| s.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "Analysis",
+            "Loading",
+            "[code] import pandas as pd",
+            "[markdown] Some notes without a heading"
+        ]
+    );
+
+    // Cells without headings nest under the most recent heading
+    assert_eq!(sections[2].level, 3);
+    assert_eq!(sections[2].parent_index, Some(1));
+    assert_eq!(sections[3].parent_index, Some(1));
+    assert_eq!(sections[2].line_start, 1, "Sections address their cell");
+}
+
+#[test]
+fn test_section_body_reads_cell_source() {
+    let sections = NotebookFormat.parse(NOTEBOOK, Path::new("nb")).unwrap();
+
+    let body = NotebookFormat.section_body(NOTEBOOK, &sections[0]);
+    assert_eq!(body.trim(), "Intro text");
+
+    let code = NotebookFormat.section_body(NOTEBOOK, &sections[2]);
+    assert_eq!(code, "import pandas as pd\ndf = pd.read_csv('x.csv')");
+}
+
+#[test]
+fn test_splice_preserves_outputs_and_metadata() {
+    let sections = NotebookFormat.parse(NOTEBOOK, Path::new("nb")).unwrap();
+
+    let updated = NotebookFormat
+        .splice_section(NOTEBOOK, &sections[2], "\nimport polars as pl\n")
+        .unwrap()
+        .unwrap();
+    let notebook: Value = serde_json::from_str(&updated).unwrap();
+
+    let cell = &notebook["cells"][1];
+    assert_eq!(cell["source"], serde_json::json!(["import polars as pl"]));
+    assert_eq!(cell["execution_count"], 3);
+    assert_eq!(cell["outputs"][0]["text"][0], "42\n");
+    assert_eq!(cell["metadata"]["tags"][0], "setup");
+    assert_eq!(notebook["metadata"]["kernelspec"]["name"], "python3");
+}
+
+#[test]
+fn test_splice_heading_body_keeps_rest_of_cell() {
+    let sections = NotebookFormat.parse(NOTEBOOK, Path::new("nb")).unwrap();
+
+    let updated = NotebookFormat
+        .splice_section(NOTEBOOK, &sections[0], "\nRewritten intro\n")
+        .unwrap()
+        .unwrap();
+    let reparsed = NotebookFormat.parse(&updated, Path::new("nb")).unwrap();
+
+    assert_eq!(
+        NotebookFormat.section_body(&updated, &reparsed[0]).trim(),
+        "Rewritten intro"
+    );
+    assert_eq!(
+        NotebookFormat.section_body(&updated, &reparsed[1]).trim(),
+        "Read the data"
+    );
+}
+
+#[test]
+#[cfg(feature = "tui")]
+fn test_save_current_writes_notebook() {
+    let file = NamedTempFile::with_suffix(".ipynb").unwrap();
+    fs::write(file.path(), NOTEBOOK).unwrap();
+    let path = file.path().to_path_buf();
+    let path_str = path.to_string_lossy().to_string();
+
+    let sections = NotebookFormat.extract_sections(&path).unwrap();
+    let format: Arc<dyn Format> = Arc::new(NotebookFormat);
+    let mut app = AppState::new(vec![path.clone()], sections, 100)
+        .with_formats(HashMap::from([(path_str, format)]));
+
+    app.current_node_index = app
+        .tree_nodes
+        .iter()
+        .position(|n| n.section_index == Some(2))
+        .unwrap();
+    app.enter_detail_view();
+    let shown: String = app
+        .editor_state
+        .as_ref()
+        .unwrap()
+        .lines
+        .iter_row()
+        .map(|line| line.iter().collect::<String>() + "\n")
+        .collect();
+    assert!(shown.contains("import pandas as pd"));
+
+    if let Some(ref mut editor_state) = app.editor_state {
+        editor_state.lines = edtui::Lines::from("\nprint('hi')\n");
+    }
+    app.save_current().unwrap();
+
+    let notebook: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(notebook["cells"][1]["source"][0], "print('hi')");
+    assert_eq!(notebook["cells"][1]["outputs"][0]["text"][0], "42\n");
+}
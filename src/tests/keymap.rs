@@ -0,0 +1,74 @@
+use super::{parse_keys, Action, KeyChord, Keymap};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+fn char_key(c: char) -> KeyEvent {
+    let modifiers = if c.is_ascii_uppercase() {
+        KeyModifiers::SHIFT
+    } else {
+        KeyModifiers::NONE
+    };
+    key(KeyCode::Char(c), modifiers)
+}
+
+#[test]
+fn test_parse_keys() {
+    assert_eq!(
+        parse_keys("ctrl+up").unwrap(),
+        vec![KeyChord {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::CONTROL
+        }]
+    );
+    assert_eq!(parse_keys("gg").unwrap(), parse_keys("g g").unwrap());
+    assert_eq!(parse_keys("shift+g").unwrap(), parse_keys("g").unwrap());
+    assert!(parse_keys("hyper+x").is_err());
+    assert!(parse_keys("ctrl+foo").is_err());
+    assert!(parse_keys("").is_err());
+}
+
+#[test]
+fn test_default_keymap_matches_original_keys() {
+    let mut keymap = Keymap::default();
+    assert_eq!(
+        keymap.press(key(KeyCode::Up, KeyModifiers::CONTROL)),
+        Some(Action::MoveUp)
+    );
+    assert_eq!(
+        keymap.press(key(KeyCode::Home, KeyModifiers::SHIFT)),
+        Some(Action::FirstAtLevel)
+    );
+    assert_eq!(keymap.press(char_key('B')), Some(Action::KeepBase));
+    assert_eq!(keymap.press(char_key('j')), None);
+}
+
+#[test]
+fn test_vim_sequences() {
+    let mut keymap = Keymap::preset("vim").unwrap();
+    assert_eq!(keymap.press(char_key('g')), None);
+    assert_eq!(keymap.press(char_key('g')), Some(Action::First));
+    assert_eq!(keymap.press(char_key('G')), Some(Action::Last));
+    // A broken-off sequence falls back to the key on its own
+    assert_eq!(keymap.press(char_key('g')), None);
+    assert_eq!(keymap.press(char_key('j')), Some(Action::Down));
+    assert_eq!(keymap.press(char_key('}')), Some(Action::NextSibling));
+}
+
+#[test]
+fn test_config_bindings_override_preset() {
+    let keys = HashMap::from([("ctrl+k".to_string(), "move-up".to_string())]);
+    let mut keymap = Keymap::from_config(Some("vim"), &keys).unwrap();
+    assert_eq!(
+        keymap.press(key(KeyCode::Char('k'), KeyModifiers::CONTROL)),
+        Some(Action::MoveUp)
+    );
+
+    keymap.bind("q", "undo").unwrap();
+    assert_eq!(keymap.press(char_key('q')), Some(Action::Undo));
+    assert!(keymap.bind("x", "fly").is_err());
+    assert!(Keymap::from_config(Some("emacs"), &HashMap::new()).is_err());
+}
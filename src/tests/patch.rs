@@ -0,0 +1,127 @@
+use super::{file_patch, PatchHunk};
+use crate::formats::difftastic::{parse_difftastic_json, size_whole_file, Hunk};
+
+const ORIGINAL: &str = "a\nb\nlet x = 1;\nc\nd\ne\nf\n";
+const MODIFIED: &str = "a\nb\nlet x = 2;\nc\nd\ne\nf\n";
+
+fn hunks(json: &str) -> Vec<Hunk> {
+    parse_difftastic_json(json)
+        .unwrap()
+        .into_iter()
+        .filter_map(|s| s.hunk.map(|h| *h))
+        .collect()
+}
+
+fn changed_line() -> Vec<Hunk> {
+    hunks(
+        r#"{"chunks":[[{"lhs":{"line_number":2,"changes":[{"start":8,"end":9,"content":"1","highlight":"normal"}]},"rhs":{"line_number":2,"changes":[{"start":8,"end":9,"content":"2","highlight":"normal"}]}}]],"language":"Rust","path":"lib.rs","status":"changed"}"#,
+    )
+}
+
+#[test]
+fn test_patch_with_context() {
+    let hunks = changed_line();
+    let mut staged = vec![PatchHunk {
+        hunk: &hunks[0],
+        edited: None,
+    }];
+
+    let patch = file_patch("lib.rs", ORIGINAL, MODIFIED, &mut staged);
+
+    assert_eq!(
+        patch,
+        "diff --git a/lib.rs b/lib.rs\n--- a/lib.rs\n+++ b/lib.rs\n\
+         @@ -1,6 +1,6 @@\n a\n b\n-let x = 1;\n+let x = 2;\n c\n d\n e\n"
+    );
+}
+
+#[test]
+fn test_edited_hunk_replaces_lines() {
+    let hunks = changed_line();
+    let edited = vec!["let x = 3;".to_string(), "let y = 4;".to_string()];
+    let mut staged = vec![PatchHunk {
+        hunk: &hunks[0],
+        edited: Some(&edited),
+    }];
+
+    let patch = file_patch("lib.rs", ORIGINAL, MODIFIED, &mut staged);
+
+    assert!(patch.contains("@@ -1,6 +1,7 @@\n a\n b\n-let x = 1;\n+let x = 3;\n+let y = 4;\n c\n"));
+}
+
+#[test]
+fn test_later_hunks_offset_by_earlier_ones() {
+    let original = (0..20)
+        .map(|i| format!("l{i}\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    let modified = original
+        .replacen("l1\n", "l1\nnew\n", 1)
+        .replace("l15", "L15");
+    let hunks = hunks(
+        r#"{"chunks":[[{"rhs":{"line_number":2,"changes":[]}}],[{"lhs":{"line_number":15,"changes":[]},"rhs":{"line_number":16,"changes":[]}}]],"language":"Text","path":"f","status":"changed"}"#,
+    );
+    let mut staged: Vec<_> = hunks
+        .iter()
+        .map(|hunk| PatchHunk { hunk, edited: None })
+        .collect();
+
+    let patch = file_patch("f", &original, &modified, &mut staged);
+
+    assert!(
+        patch.contains("@@ -1,5 +1,6 @@\n l0\n l1\n+new\n l2\n l3\n l4\n"),
+        "{patch}"
+    );
+    assert!(
+        patch.contains("@@ -13,7 +14,7 @@\n l12\n l13\n l14\n-l15\n+L15\n l16\n"),
+        "{patch}"
+    );
+}
+
+#[test]
+fn test_deleted_file_removes_every_line() {
+    let mut sections =
+        parse_difftastic_json(r#"{"language":"Rust","path":"lib.rs","status":"deleted"}"#).unwrap();
+    size_whole_file(&mut sections[0], ORIGINAL);
+    let hunk = sections[0].hunk.as_deref().unwrap();
+    let mut staged = vec![PatchHunk { hunk, edited: None }];
+
+    let patch = file_patch("lib.rs", ORIGINAL, "", &mut staged);
+
+    assert_eq!(
+        patch,
+        "diff --git a/lib.rs b/lib.rs\ndeleted file mode 100644\n--- a/lib.rs\n+++ /dev/null\n\
+         @@ -1,7 +0,0 @@\n-a\n-b\n-let x = 1;\n-c\n-d\n-e\n-f\n"
+    );
+}
+
+#[test]
+fn test_missing_trailing_newline_marked() {
+    let hunks = hunks(
+        r#"{"chunks":[[{"lhs":{"line_number":1,"changes":[]},"rhs":{"line_number":1,"changes":[]}}]],"language":"Text","path":"f","status":"changed"}"#,
+    );
+    let mut staged = vec![PatchHunk {
+        hunk: &hunks[0],
+        edited: None,
+    }];
+
+    let patch = file_patch("f", "a\nb", "a\nc", &mut staged);
+
+    assert!(
+        patch.ends_with(
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n"
+        ),
+        "{patch}"
+    );
+
+    // An unchanged last line only needs marking as context
+    let mut staged = vec![PatchHunk {
+        hunk: &hunks[0],
+        edited: None,
+    }];
+    let patch = file_patch("f", "a\nb\nz", "a\nc\nz", &mut staged);
+    assert!(
+        patch.ends_with("-b\n+c\n z\n\\ No newline at end of file\n"),
+        "{patch}"
+    );
+}
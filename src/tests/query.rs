@@ -0,0 +1,91 @@
+use super::{LevelRule, QueryFormat};
+use crate::config::{Config, FormatDefinition};
+use crate::formats::registry::FormatRegistry;
+use crate::formats::Format;
+use std::path::Path;
+
+fn setext_definition() -> FormatDefinition {
+    FormatDefinition {
+        name: "setext".to_string(),
+        extensions: vec!["stx".to_string()],
+        grammar: "markdown".to_string(),
+        section_query:
+            "(setext_heading [(setext_h1_underline) (setext_h2_underline)] @level) @heading"
+                .to_string(),
+        title_query: "(setext_heading heading_content: (paragraph) @title)".to_string(),
+        level_capture: Some("level".to_string()),
+        level_rule: None,
+    }
+}
+
+#[test]
+fn test_setext_headings_by_kind() {
+    let format = QueryFormat::new(&setext_definition()).unwrap();
+    let content = "Title\n=====\n\nIntro\n\nPart\n----\n\nBody\n";
+
+    let sections = format.parse(content, Path::new("doc.stx")).unwrap();
+
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[0].title, "Title");
+    assert_eq!(sections[0].level, 1);
+    assert_eq!(sections[1].title, "Part");
+    assert_eq!(sections[1].level, 2);
+    assert_eq!(sections[1].parent_index, Some(0));
+}
+
+#[test]
+fn test_length_rule_counts_marker_run() {
+    let def = FormatDefinition {
+        name: "hashes".to_string(),
+        extensions: vec![],
+        grammar: "markdown".to_string(),
+        section_query: "(atx_heading (_) @marker) @heading".to_string(),
+        title_query: "(atx_heading heading_content: (inline) @title)".to_string(),
+        level_capture: Some("marker".to_string()),
+        level_rule: Some("length".to_string()),
+    };
+    let format = QueryFormat::new(&def).unwrap();
+
+    let sections = format
+        .parse("# One\n\n### Three\n", Path::new("x"))
+        .unwrap();
+
+    assert_eq!(sections[0].level, 1);
+    assert_eq!(sections[1].level, 3);
+}
+
+#[test]
+fn test_level_rule_parse() {
+    assert_eq!(LevelRule::parse("depth").unwrap(), LevelRule::Depth);
+    assert_eq!(LevelRule::parse("2").unwrap(), LevelRule::Fixed(2));
+    assert!(LevelRule::parse("0").is_err());
+    assert!(LevelRule::parse("deep").is_err());
+}
+
+#[test]
+fn test_invalid_definitions_rejected() {
+    let mut def = setext_definition();
+    def.grammar = "latex".to_string();
+    let err = QueryFormat::new(&def).err().unwrap();
+    assert!(err.to_string().contains("unknown grammar"), "{err}");
+
+    let mut def = setext_definition();
+    def.section_query = "(no_such_node) @heading".to_string();
+    assert!(QueryFormat::new(&def).is_err());
+
+    let mut def = setext_definition();
+    def.level_capture = Some("missing".to_string());
+    let err = QueryFormat::new(&def).err().unwrap();
+    assert!(err.to_string().contains("@missing"), "{err}");
+}
+
+#[test]
+fn test_registry_registers_custom_format() {
+    let mut cfg = facet_toml::from_str::<Config>("").unwrap();
+    cfg.custom_formats = vec![setext_definition()];
+    let registry = FormatRegistry::from_config(&cfg).unwrap();
+
+    let format = registry.for_path(Path::new("notes.stx")).unwrap();
+    assert_eq!(format.name(), "setext");
+    assert!(registry.by_name("setext").is_some());
+}
@@ -0,0 +1,58 @@
+use super::{reflow, reflow_paragraph};
+
+#[test]
+fn test_reflow_fills_paragraphs() {
+    let text = "One two three four five six seven\neight nine.\n\nTen eleven twelve.";
+    assert_eq!(
+        reflow(text, 20),
+        "One two three four\nfive six seven eight\nnine.\n\nTen eleven twelve."
+    );
+}
+
+#[test]
+fn test_reflow_keeps_markup() {
+    let text = "\
+- a list item whose words run on
+  past the width
+> quoted words that are long enough to wrap
+```
+code that is far longer than the width allows
+```
+| a | b |
+|---|---|
+[link]: https://example.com/a/long/path/that/stays/put";
+    assert_eq!(
+        reflow(text, 20),
+        "\
+- a list item whose
+  words run on past
+  the width
+> quoted words that
+> are long enough to
+> wrap
+```
+code that is far longer than the width allows
+```
+| a | b |
+|---|---|
+[link]: https://example.com/a/long/path/that/stays/put"
+    );
+}
+
+#[test]
+fn test_reflow_never_starts_a_line_with_markup() {
+    // Wrapping before `-` or `#` would turn the rest of the line into a list item or heading
+    assert_eq!(reflow("aaaa bbbb - cccc # dd", 9), "aaaa bbbb -\ncccc # dd");
+    assert_eq!(reflow("short  \nbreak kept", 40), "short  \nbreak kept");
+}
+
+#[test]
+fn test_reflow_paragraph_under_cursor() {
+    let text = "one two\nthree\n\nfour five\nsix\n\n    code";
+    assert_eq!(
+        reflow_paragraph(text, 4, 80).as_deref(),
+        Some("one two\nthree\n\nfour five six\n\n    code")
+    );
+    assert_eq!(reflow_paragraph(text, 2, 80), None);
+    assert_eq!(reflow_paragraph(text, 6, 80), None);
+}
@@ -0,0 +1,111 @@
+use super::FormatRegistry;
+use crate::config::Config;
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+fn test_config(formats: &[(&str, &str)]) -> Config {
+    let mut cfg = facet_toml::from_str::<Config>("").unwrap();
+    cfg.formats = formats
+        .iter()
+        .map(|(ext, name)| ((*ext).to_string(), (*name).to_string()))
+        .collect();
+    cfg
+}
+
+#[test]
+fn test_extension_lookup() {
+    let registry = FormatRegistry::default();
+
+    assert_eq!(
+        registry.for_path(Path::new("README.md")).unwrap().name(),
+        "markdown"
+    );
+    assert_eq!(
+        registry
+            .for_path(Path::new("NOTES.MARKDOWN"))
+            .unwrap()
+            .name(),
+        "markdown"
+    );
+    assert!(
+        registry.by_extension("json").is_none(),
+        "JSON is left to sniffing"
+    );
+}
+
+#[test]
+fn test_sniffs_difftastic_json() {
+    let mut file = NamedTempFile::with_suffix(".json").unwrap();
+    writeln!(
+        file,
+        r#"{{"chunks":[],"language":"Rust","path":"src/main.rs","status":"changed"}}"#
+    )
+    .unwrap();
+
+    let registry = FormatRegistry::default();
+    let format = registry.for_path(file.path()).unwrap();
+    assert_eq!(format.name(), "difftastic");
+    assert!(format.is_diff());
+}
+
+#[test]
+fn test_unrecognised_file() {
+    let mut file = NamedTempFile::with_suffix(".json").unwrap();
+    writeln!(file, r#"{{"name":"not a diff"}}"#).unwrap();
+
+    let registry = FormatRegistry::default();
+    assert!(registry.for_path(file.path()).is_none());
+}
+
+#[test]
+fn test_config_maps_extension() {
+    let cfg = test_config(&[("mdx", "markdown")]);
+    let registry = FormatRegistry::from_config(&cfg).unwrap();
+
+    assert_eq!(
+        registry.for_path(Path::new("page.mdx")).unwrap().name(),
+        "markdown"
+    );
+}
+
+#[test]
+fn test_config_discovery_extensions_default_to_markdown() {
+    let mut cfg = test_config(&[]);
+    cfg.file_extensions = vec!["txt".to_string()];
+    let registry = FormatRegistry::from_config(&cfg).unwrap();
+
+    assert_eq!(
+        registry.for_path(Path::new("notes.txt")).unwrap().name(),
+        "markdown"
+    );
+}
+
+#[test]
+fn test_config_unknown_format_rejected() {
+    let cfg = test_config(&[("adoc", "asciidoc")]);
+    let err = FormatRegistry::from_config(&cfg).err().unwrap();
+    assert!(err.to_string().contains("asciidoc"));
+}
+
+#[test]
+fn test_conflicts_sniffed_only_for_unclaimed_extensions() {
+    let conflict = "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> branch\n";
+    let mut cfg = test_config(&[]);
+    cfg.file_extensions = vec!["txt".to_string()];
+    let registry = FormatRegistry::from_config(&cfg).unwrap();
+
+    for (suffix, expected) in [
+        (".md", "markdown"),
+        (".txt", "conflict"),
+        (".rs", "conflict"),
+    ] {
+        let mut file = NamedTempFile::with_suffix(suffix).unwrap();
+        write!(file, "{conflict}").unwrap();
+        assert_eq!(
+            registry.for_path(file.path()).unwrap().name(),
+            expected,
+            "{suffix}"
+        );
+    }
+}
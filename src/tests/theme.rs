@@ -0,0 +1,72 @@
+use super::{Theme, ThemeConfig};
+use ratatui::style::Color;
+
+#[test]
+fn test_config_overrides_bundled_theme() {
+    let config = ThemeConfig {
+        name: Some("light".to_string()),
+        headings: Some(vec!["red".to_string(), "#112233".to_string()]),
+        added: Some("lightgreen".to_string()),
+        syntax: Some("none".to_string()),
+        glyphs: Some("ascii".to_string()),
+        ..ThemeConfig::default()
+    };
+    let theme = Theme::from_config(&config, false).unwrap();
+
+    assert_eq!(theme.heading(1), Color::Red);
+    assert_eq!(theme.heading(2), Color::Rgb(0x11, 0x22, 0x33));
+    assert_eq!(theme.heading(3), Color::Red);
+    assert_eq!(theme.added, Color::LightGreen);
+    assert_eq!(theme.accent, Theme::light().accent);
+    assert_eq!(theme.syntax, None);
+    assert_eq!(theme.glyphs.pipe, "|");
+}
+
+#[test]
+fn test_unknown_names_are_errors() {
+    let named = |name: &str| ThemeConfig {
+        name: Some(name.to_string()),
+        ..ThemeConfig::default()
+    };
+    assert!(Theme::from_config(&named("neon"), false).is_err());
+
+    let bad_colour = ThemeConfig {
+        removed: Some("not-a-colour".to_string()),
+        ..ThemeConfig::default()
+    };
+    assert!(Theme::from_config(&bad_colour, false).is_err());
+
+    let bad_syntax = ThemeConfig {
+        syntax: Some("no such theme".to_string()),
+        ..ThemeConfig::default()
+    };
+    assert!(Theme::from_config(&bad_syntax, false).is_err());
+}
+
+#[test]
+fn test_no_color_ignores_configured_colours() {
+    let config = ThemeConfig {
+        headings: Some(vec!["red".to_string()]),
+        glyphs: Some("ascii".to_string()),
+        ..ThemeConfig::default()
+    };
+    let theme = Theme::from_config(&config, true).unwrap();
+
+    assert!(!theme.color);
+    assert_eq!(theme.heading(1), Color::Reset);
+    assert_eq!(theme.syntax, None);
+    assert_eq!(theme.glyphs.pipe, "|");
+}
+
+#[test]
+fn test_theme_table_in_config() {
+    let config: crate::config::Config = facet_toml::from_str(
+        "keymap = \"vim\"\n\n[keys]\n\"ctrl+k\" = \"move-up\"\n\n[theme]\nname = \"light\"\nheadings = [\"red\"]\n",
+    )
+    .unwrap();
+    assert_eq!(config.theme.name.as_deref(), Some("light"));
+    assert_eq!(config.keys["ctrl+k"], "move-up");
+
+    let theme = Theme::from_config(&config.theme, false).unwrap();
+    assert_eq!(theme.heading(2), Color::Red);
+}
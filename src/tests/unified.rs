@@ -0,0 +1,106 @@
+use super::{parse_unified_diff, UnifiedDiffFormat};
+use crate::diff_view::DiffView;
+use crate::formats::Format;
+
+const GIT_DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 86bba90..8426a2e 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,5 +1,6 @@ mod a;
+ l1
+-l2
++L2
++new
+ l3
+ l4
+ l5
+@@ -13,4 +14,3 @@
+ l13
+---flag
+ l15
+ l16
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1,2 +0,0 @@
+-one
+-two
+";
+
+#[test]
+fn test_hunks_grouped_by_file() {
+    let sections = parse_unified_diff(GIT_DIFF).unwrap();
+
+    let titles: Vec<_> = sections.iter().map(|s| s.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "Hunk 1 @@ -1,5 +1,6 @@",
+            "Hunk 2 @@ -13,4 +14,3 @@",
+            "Hunk 1 @@ -1,2 +0,0 @@",
+        ]
+    );
+    assert_eq!(sections[0].file_path, "src/lib.rs");
+    assert_eq!(
+        sections[2].file_path, "gone.txt",
+        "Deletions keep the old path"
+    );
+}
+
+#[test]
+fn test_removed_lines_pair_with_added() {
+    let sections = parse_unified_diff(GIT_DIFF).unwrap();
+    let hunk = sections[0].hunk.as_ref().unwrap();
+
+    assert_eq!(hunk.lines.len(), 2);
+    let first = &hunk.lines[0];
+    assert_eq!(first.lhs.as_ref().unwrap().line_number, 1);
+    assert_eq!(first.rhs.as_ref().unwrap().changes[0].content, "L2");
+    assert!(hunk.lines[1].lhs.is_none());
+
+    // A removed line that itself starts with dashes is still a removal
+    let second = sections[1].hunk.as_ref().unwrap();
+    assert_eq!(
+        second.lines[0].lhs.as_ref().unwrap().changes[0].content,
+        "--flag"
+    );
+}
+
+#[test]
+fn test_diff_view_rows_without_files() {
+    let sections = parse_unified_diff(GIT_DIFF).unwrap();
+    let hunk = sections[0].hunk.as_ref().unwrap();
+
+    assert_eq!(
+        DiffView::original_lines(hunk, None, None)[1..2],
+        ["l2".to_string()]
+    );
+    assert_eq!(
+        DiffView::modified_lines(hunk, None, None),
+        ["l1", "L2", "new", "l3", "l4", "l5"]
+    );
+
+    let view = DiffView::new(hunk, None, None);
+    let rhs: Vec<_> = view
+        .rows
+        .iter()
+        .map(|row| row.rhs.as_ref().map(|c| c.text.as_str()))
+        .collect();
+    assert_eq!(rhs[0], Some("l1"));
+    assert_eq!(rhs[4], Some("l4"));
+}
+
+#[test]
+fn test_sniff() {
+    assert!(UnifiedDiffFormat.sniff(GIT_DIFF));
+    assert!(UnifiedDiffFormat.sniff("--- old.txt\t2024-01-01\n+++ new.txt\n@@ -1 +1 @@\n"));
+    assert!(!UnifiedDiffFormat.sniff("# Heading\n\n--- \n"));
+}
+
+#[test]
+fn test_short_hunk_is_an_error() {
+    let short = "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n@@ -9 +9 @@\n-x\n+y\n";
+    assert!(parse_unified_diff(short).is_err());
+}
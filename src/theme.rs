@@ -0,0 +1,276 @@
+//! Colours and glyphs for the interface, from a bundled theme adjusted by asterism.toml.
+//!
+//! The theme is installed once at startup and read wherever something is drawn, including the
+//! formats that render their own tree entries. Setting `NO_COLOR` selects the monochrome theme,
+//! which distinguishes selection and moves by text attributes such as bold and reversed alone.
+
+pub use crate::config::ThemeConfig;
+use ratatui::style::{Color, Modifier, Style};
+use std::io;
+use std::str::FromStr;
+use std::sync::{LazyLock, OnceLock};
+
+/// Characters drawn in the tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Vertical line joining a parent to its later children.
+    pub pipe: &'static str,
+    /// Marker before a directory name.
+    pub directory: &'static str,
+    /// Marker before a file name.
+    pub file: &'static str,
+    /// Marker for unsaved edits.
+    pub dirty: &'static str,
+}
+
+impl Glyphs {
+    const UNICODE: Self = Self {
+        pipe: "│",
+        directory: "📁",
+        file: "📄",
+        dirty: "●",
+    };
+    const ASCII: Self = Self {
+        pipe: "|",
+        directory: "+",
+        file: "-",
+        dirty: "*",
+    };
+}
+
+/// Colours, styles and glyphs used to draw the interface.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Whether colour is used at all.
+    pub color: bool,
+    /// Heading prefix colours, cycled through by level.
+    pub headings: Vec<Color>,
+    /// Directories, diffed files and the other side of a conflict.
+    pub accent: Color,
+    /// File names.
+    pub file: Color,
+    /// Entries that cannot be selected, line numbers and context.
+    pub dimmed: Color,
+    /// Added lines, accepted hunks and our side of a conflict.
+    pub added: Color,
+    /// Removed lines, rejected hunks and conflicts themselves.
+    pub removed: Color,
+    /// Modified lines, edited hunks and unsaved edits.
+    pub modified: Color,
+    /// Background of changed spans in removed lines.
+    pub removed_tint: Color,
+    /// Background of changed spans in added lines.
+    pub added_tint: Color,
+    /// The selected entry.
+    pub selected: Style,
+    /// A section picked up to move.
+    pub moving: Style,
+    /// A section moved but not yet saved.
+    pub moved: Style,
+    /// Text in the editor.
+    pub editor: Style,
+    /// Syntect theme for syntax highlighting, or none to leave code unhighlighted.
+    pub syntax: Option<String>,
+    /// Tree characters.
+    pub glyphs: Glyphs,
+}
+
+impl Theme {
+    /// Dark theme, the default.
+    #[must_use]
+    pub fn dark() -> Self {
+        Self {
+            color: true,
+            headings: vec![
+                Color::Cyan,
+                Color::Green,
+                Color::LightYellow,
+                Color::Magenta,
+                Color::Blue,
+                Color::Red,
+            ],
+            accent: Color::Cyan,
+            file: Color::Blue,
+            dimmed: Color::DarkGray,
+            added: Color::Green,
+            removed: Color::Red,
+            modified: Color::LightYellow,
+            removed_tint: Color::Rgb(80, 30, 30),
+            added_tint: Color::Rgb(30, 70, 30),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            moving: Style::default()
+                .fg(Color::Rgb(255, 165, 0))
+                .add_modifier(Modifier::BOLD),
+            moved: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            editor: Style::default().bg(Color::Black).fg(Color::White),
+            syntax: Some("base16-eighties.dark".to_string()),
+            glyphs: Glyphs::UNICODE,
+        }
+    }
+
+    /// Theme for light terminal backgrounds.
+    #[must_use]
+    pub fn light() -> Self {
+        Self {
+            headings: vec![
+                Color::Blue,
+                Color::Green,
+                Color::Magenta,
+                Color::Red,
+                Color::Cyan,
+                Color::DarkGray,
+            ],
+            accent: Color::Blue,
+            file: Color::Magenta,
+            dimmed: Color::Gray,
+            modified: Color::Rgb(170, 110, 0),
+            removed_tint: Color::Rgb(255, 215, 215),
+            added_tint: Color::Rgb(215, 245, 215),
+            moving: Style::default()
+                .fg(Color::Rgb(200, 100, 0))
+                .add_modifier(Modifier::BOLD),
+            editor: Style::default(),
+            syntax: Some("InspiredGitHub".to_string()),
+            ..Self::dark()
+        }
+    }
+
+    /// Theme without colour, for `NO_COLOR` and terminals that lack it.
+    #[must_use]
+    pub fn mono() -> Self {
+        Self {
+            color: false,
+            headings: vec![Color::Reset],
+            accent: Color::Reset,
+            file: Color::Reset,
+            dimmed: Color::Reset,
+            added: Color::Reset,
+            removed: Color::Reset,
+            modified: Color::Reset,
+            removed_tint: Color::Reset,
+            added_tint: Color::Reset,
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            moving: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            moved: Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC),
+            editor: Style::default(),
+            syntax: None,
+            glyphs: Glyphs::UNICODE,
+        }
+    }
+
+    /// Bundled theme by name.
+    #[must_use]
+    pub fn bundled(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "mono" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+
+    /// Theme from asterism.toml, or the monochrome theme when `no_color` is set.
+    ///
+    /// Without colour, only the glyph choice is taken from the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming a theme, colour, syntect theme or glyph set that is not recognised.
+    pub fn from_config(config: &ThemeConfig, no_color: bool) -> io::Result<Self> {
+        let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidInput, reason);
+        let color = |value: &Option<String>, current: Color| -> io::Result<Color> {
+            value.as_deref().map_or(Ok(current), |value| {
+                Color::from_str(value).map_err(|_| invalid(format!("Unknown colour {value:?}")))
+            })
+        };
+
+        let name = config.name.as_deref().unwrap_or("dark");
+        let mut theme =
+            Self::bundled(name).ok_or_else(|| invalid(format!("Unknown theme {name:?}")))?;
+        if no_color {
+            theme = Self::mono();
+        } else {
+            if let Some(headings) = &config.headings {
+                theme.headings = headings
+                    .iter()
+                    .map(|heading| color(&Some(heading.clone()), Color::Reset))
+                    .collect::<io::Result<_>>()?;
+                if theme.headings.is_empty() {
+                    theme.headings.push(Color::Reset);
+                }
+            }
+            theme.accent = color(&config.accent, theme.accent)?;
+            theme.file = color(&config.file, theme.file)?;
+            theme.added = color(&config.added, theme.added)?;
+            theme.removed = color(&config.removed, theme.removed)?;
+            theme.modified = color(&config.modified, theme.modified)?;
+            if config.selection.is_some() {
+                theme.selected = Style::default().bg(color(&config.selection, Color::Reset)?);
+            }
+            if let Some(fg) = theme.moving.fg {
+                theme.moving = theme.moving.fg(color(&config.moving, fg)?);
+            }
+            match config.syntax.as_deref() {
+                None => {}
+                Some("none") => theme.syntax = None,
+                Some(syntax) if crate::highlight::has_theme(syntax) => {
+                    theme.syntax = Some(syntax.to_string());
+                }
+                Some(syntax) => return Err(invalid(format!("Unknown syntax theme {syntax:?}"))),
+            }
+        }
+
+        theme.glyphs = match config.glyphs.as_deref() {
+            None | Some("unicode") => Glyphs::UNICODE,
+            Some("ascii") => Glyphs::ASCII,
+            Some(glyphs) => return Err(invalid(format!("Unknown glyph set {glyphs:?}"))),
+        };
+        Ok(theme)
+    }
+
+    /// Colour of a heading prefix at the given level.
+    #[must_use]
+    pub fn heading(&self, level: usize) -> Color {
+        self.headings[(level.max(1) - 1) % self.headings.len()]
+    }
+
+    /// Foreground style in a colour, or plain when the theme has none.
+    #[must_use]
+    pub fn fg(&self, color: Color) -> Style {
+        if self.color {
+            Style::default().fg(color)
+        } else {
+            Style::default()
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+static INSTALLED: OnceLock<Theme> = OnceLock::new();
+static DEFAULT: LazyLock<Theme> = LazyLock::new(Theme::default);
+
+/// Make `theme` the one used for drawing; only the first theme installed takes effect.
+pub fn install(theme: Theme) {
+    let _ = INSTALLED.set(theme);
+}
+
+/// Theme used for drawing, the default dark theme until one is installed.
+#[must_use]
+pub fn current() -> &'static Theme {
+    INSTALLED.get().unwrap_or(&DEFAULT)
+}
+
+/// Whether the `NO_COLOR` convention asks for output without colour.
+#[must_use]
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+#[path = "tests/theme.rs"]
+mod tests;
@@ -3,10 +3,12 @@
 //! The draw function dispatches based on the current view (list or editor).
 //! The list view shows a unified tree with files and sections using box-drawing characters.
 
-use crate::app_state::{AppState, MoveState, View};
+use crate::app_state::{AppState, HunkDecision, MoveState, View};
 use crate::config::Config;
-use crate::formats::Format;
+use crate::diff_view::{ChangedSpan, DiffCell, DiffRow};
+use crate::highlight::{find_syntax, syntax_extension, syntax_theme, LineHighlighter};
 use crate::section::NodeType;
+use crate::theme;
 use edtui::{EditorTheme, EditorView, SyntaxHighlighter};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
@@ -22,6 +24,7 @@ pub fn draw(f: &mut Frame, app: &mut AppState, _cfg: &Config) {
         View::List => draw_list(f, app),
         View::Command => draw_list_with_command(f, app),
         View::Detail => draw_detail(f, app),
+        View::Diff => draw_diff(f, app),
     }
 }
 
@@ -37,7 +40,8 @@ fn get_tree_prefix(level: usize, parent_states: &[bool]) -> String {
     for i in 0..level.saturating_sub(1) {
         prefix.push_str("  "); // Two spaces for indentation
         if i < parent_states.len() && parent_states[i] {
-            prefix.push_str("│ ");
+            prefix.push_str(theme::current().glyphs.pipe);
+            prefix.push(' ');
         } else {
             prefix.push_str("  ");
         }
@@ -59,16 +63,6 @@ fn draw_list(f: &mut Frame, app: &AppState) {
         .constraints([Constraint::Min(0), Constraint::Length(3)])
         .split(f.area());
 
-    // Determine if we're in difftastic mode by checking if any section has chunk_type
-    let is_difftastic = app.sections.iter().any(|s| s.chunk_type.is_some())
-        || app.sections.iter().any(|s| s.title.contains("@@"));
-
-    let format: Box<dyn Format> = if is_difftastic {
-        Box::new(crate::formats::difftastic::DifftasticFormat)
-    } else {
-        Box::new(crate::formats::markdown::MarkdownFormat)
-    };
-
     let mut is_last_at_level = vec![false; app.tree_nodes.len()];
 
     // Calculate which nodes are last children of their parent for box-drawing
@@ -106,6 +100,7 @@ fn draw_list(f: &mut Frame, app: &AppState) {
     // Track which parent levels still have siblings coming
     let mut parent_has_siblings: Vec<bool> = Vec::new();
 
+    let theme = theme::current();
     let items: Vec<ListItem> = app
         .tree_nodes
         .iter()
@@ -130,37 +125,44 @@ fn draw_list(f: &mut Frame, app: &AppState) {
                     let spans = vec![
                         Span::raw(tree_prefix),
                         Span::styled(
-                            format!("📁 {name}"),
-                            Style::default()
-                                .fg(Color::Cyan)
-                                .add_modifier(Modifier::BOLD),
+                            format!("{} {name}", theme.glyphs.directory),
+                            theme.fg(theme.accent).add_modifier(Modifier::BOLD),
                         ),
                     ];
                     Line::from(spans)
                 }
-                NodeType::File { name, .. } => {
-                    let spans = vec![
+                NodeType::File { name, path } => {
+                    let mut spans = vec![
                         Span::raw(tree_prefix),
                         Span::styled(
-                            format!("  📄 {name}"),
-                            Style::default()
-                                .fg(Color::Blue)
-                                .add_modifier(Modifier::BOLD),
+                            format!("  {} {name}", theme.glyphs.file),
+                            theme.fg(theme.file).add_modifier(Modifier::BOLD),
                         ),
                     ];
+                    if app.file_dirty(path) {
+                        spans.push(Span::styled(
+                            format!(" {}", theme.glyphs.dirty),
+                            theme.fg(theme.modified),
+                        ));
+                    }
                     Line::from(spans)
                 }
                 NodeType::Section(section) => {
                     // Calculate indentation based on section level
                     let indent = "  ".repeat(section.level.saturating_sub(1));
 
-                    let mut highlighted_line = format
-                        .as_ref()
+                    let mut highlighted_line = app
+                        .format_for(&section.file_path)
                         .format_section_display(section.level, &section.title);
 
                     // Prepend indent + tree prefix
                     let mut spans = vec![Span::raw(indent), Span::raw(tree_prefix)];
                     spans.append(&mut highlighted_line.spans);
+                    if let Some(marker) =
+                        node.section_index.and_then(|idx| decision_marker(app, idx))
+                    {
+                        spans.push(marker);
+                    }
 
                     Line::from(spans)
                 }
@@ -169,25 +171,21 @@ fn draw_list(f: &mut Frame, app: &AppState) {
             // Determine style based on selection and move state
             let style = if node.section_index == app.moving_section_index {
                 match app.move_state {
-                    MoveState::Selected => Style::default()
-                        .fg(Color::Rgb(255, 165, 0)) // Orange
-                        .add_modifier(Modifier::BOLD),
-                    MoveState::Moved => {
-                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
-                    }
+                    MoveState::Selected => theme.moving,
+                    MoveState::Moved => theme.moved,
                     MoveState::None => {
                         if i == app.current_node_index {
-                            Style::default().add_modifier(Modifier::REVERSED)
+                            theme.selected
                         } else {
                             Style::default()
                         }
                     }
                 }
-            } else if i == app.current_node_index && node.navigable {
-                Style::default().add_modifier(Modifier::REVERSED)
-            } else if !node.navigable {
+            } else if i == app.current_node_index && node.selectable() {
+                theme.selected
+            } else if !node.selectable() {
                 // Dim non-navigable nodes slightly
-                Style::default().fg(Color::DarkGray)
+                theme.fg(theme.dimmed)
             } else {
                 Style::default()
             };
@@ -205,12 +203,22 @@ fn draw_list(f: &mut Frame, app: &AppState) {
         (false, MoveState::None) => "Sections".to_string(),
         (false, _) => "Sections (MOVING)".to_string(),
     };
+    let title = app.diff_summary().unwrap_or(title);
 
     let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
 
     f.render_widget(list, chunks[0]);
 
-    let help = if app.move_state == MoveState::None {
+    let help = if let Some(ref msg) = app.message {
+        msg.as_str()
+    } else if app
+        .get_current_section()
+        .is_some_and(|s| app.format_for(&s.file_path).name() == "conflict")
+    {
+        "↑/↓: Navigate | o/t/b/B: Keep ours/theirs/both/base | u: Undo | :w Write | Enter: Edit | q: Quit"
+    } else if app.diff_summary().is_some() {
+        "↑/↓: Navigate | Enter: View | a/r/e/u: Accept/Reject/Edit/Undo | s: Sort by churn | q: Quit"
+    } else if app.move_state == MoveState::None {
         "↑/↓: Navigate | ←/→: Parent/Child | Enter: Edit | Ctrl+↑/↓/←/→: Start Move | q: Quit"
     } else {
         "Ctrl+↑/↓: Move | Ctrl+←/→: Level | Ctrl+Home/End: Top/Bottom | :w Save | Esc: Cancel"
@@ -227,16 +235,6 @@ fn draw_list_with_command(f: &mut Frame, app: &AppState) {
         .constraints([Constraint::Min(0), Constraint::Length(3)])
         .split(f.area());
 
-    // Determine if we're in difftastic mode by checking if any section has chunk_type
-    let is_difftastic = app.sections.iter().any(|s| s.chunk_type.is_some())
-        || app.sections.iter().any(|s| s.title.contains("@@"));
-
-    let format: Box<dyn Format> = if is_difftastic {
-        Box::new(crate::formats::difftastic::DifftasticFormat)
-    } else {
-        Box::new(crate::formats::markdown::MarkdownFormat)
-    };
-
     // Calculate which nodes are last at their level
     let mut is_last_at_level: Vec<bool> = vec![false; app.tree_nodes.len()];
     for (i, node) in app.tree_nodes.iter().enumerate() {
@@ -256,6 +254,7 @@ fn draw_list_with_command(f: &mut Frame, app: &AppState) {
 
     let mut parent_has_siblings: Vec<bool> = Vec::new();
 
+    let theme = theme::current();
     let items: Vec<ListItem> = app
         .tree_nodes
         .iter()
@@ -283,34 +282,41 @@ fn draw_list_with_command(f: &mut Frame, app: &AppState) {
                     let spans = vec![
                         Span::raw(tree_prefix),
                         Span::styled(
-                            format!("📁 {name}"),
-                            Style::default()
-                                .fg(Color::Cyan)
-                                .add_modifier(Modifier::BOLD),
+                            format!("{} {name}", theme.glyphs.directory),
+                            theme.fg(theme.accent).add_modifier(Modifier::BOLD),
                         ),
                     ];
                     Line::from(spans)
                 }
-                NodeType::File { name, .. } => {
-                    let spans = vec![
+                NodeType::File { name, path } => {
+                    let mut spans = vec![
                         Span::raw(tree_prefix),
                         Span::styled(
-                            format!("  📄 {name}"),
-                            Style::default()
-                                .fg(Color::Blue)
-                                .add_modifier(Modifier::BOLD),
+                            format!("  {} {name}", theme.glyphs.file),
+                            theme.fg(theme.file).add_modifier(Modifier::BOLD),
                         ),
                     ];
+                    if app.file_dirty(path) {
+                        spans.push(Span::styled(
+                            format!(" {}", theme.glyphs.dirty),
+                            theme.fg(theme.modified),
+                        ));
+                    }
                     Line::from(spans)
                 }
                 NodeType::Section(section) => {
-                    let mut highlighted_line = format
-                        .as_ref()
+                    let mut highlighted_line = app
+                        .format_for(&section.file_path)
                         .format_section_display(section.level, &section.title);
 
                     // Prepend tree prefix
                     let mut spans = vec![Span::raw(tree_prefix)];
                     spans.append(&mut highlighted_line.spans);
+                    if let Some(marker) =
+                        node.section_index.and_then(|idx| decision_marker(app, idx))
+                    {
+                        spans.push(marker);
+                    }
 
                     Line::from(spans)
                 }
@@ -318,24 +324,20 @@ fn draw_list_with_command(f: &mut Frame, app: &AppState) {
 
             let style = if node.section_index == app.moving_section_index {
                 match app.move_state {
-                    MoveState::Selected => Style::default()
-                        .fg(Color::Rgb(255, 165, 0))
-                        .add_modifier(Modifier::BOLD),
-                    MoveState::Moved => {
-                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
-                    }
+                    MoveState::Selected => theme.moving,
+                    MoveState::Moved => theme.moved,
                     MoveState::None => {
                         if i == app.current_node_index {
-                            Style::default().add_modifier(Modifier::REVERSED)
+                            theme.selected
                         } else {
                             Style::default()
                         }
                     }
                 }
-            } else if i == app.current_node_index && node.navigable {
-                Style::default().add_modifier(Modifier::REVERSED)
-            } else if !node.navigable {
-                Style::default().fg(Color::DarkGray)
+            } else if i == app.current_node_index && node.selectable() {
+                theme.selected
+            } else if !node.selectable() {
+                theme.fg(theme.dimmed)
             } else {
                 Style::default()
             };
@@ -393,19 +395,38 @@ fn draw_detail(f: &mut Frame, app: &mut AppState) {
             .block(Block::default().borders(Borders::ALL).title("Navigation"));
         f.render_widget(breadcrumb_widget, chunks[0]);
 
+        // Hunks are highlighted as the language they change, other sections as markdown
+        let extension = section
+            .hunk
+            .as_ref()
+            .and_then(|hunk| find_syntax(hunk.language.as_deref(), &section.file_path))
+            .map_or("md", syntax_extension);
+
         // Editor
-        let max_width = app.get_max_line_width();
-        let title = format!("Section: {} (max line: {} chars)", section.title, max_width);
+        let title = format!(
+            "Section: {} (wraps at {} chars)",
+            section.title, app.wrap_width
+        );
 
         if let Some(ref mut editor_state) = app.editor_state {
             let block = Block::default().borders(Borders::ALL).title(title);
             let inner = block.inner(chunks[1]);
             f.render_widget(block, chunks[1]);
 
-            let syntax_highlighter = SyntaxHighlighter::new("dracula", "md");
+            let theme = theme::current();
+            // The named theme is only a starting point, replaced by the configured one
+            let syntax_highlighter = syntax_theme().map(|syntax| {
+                SyntaxHighlighter::new("dracula", extension).custom_theme(syntax.clone())
+            });
+            let mut editor_theme = EditorTheme::default().base(theme.editor);
+            if !theme.color {
+                editor_theme = editor_theme
+                    .cursor_style(Style::default().add_modifier(Modifier::REVERSED))
+                    .selection_style(Style::default().add_modifier(Modifier::UNDERLINED));
+            }
             let editor = EditorView::new(editor_state)
-                .theme(EditorTheme::default())
-                .syntax_highlighter(Some(syntax_highlighter))
+                .theme(editor_theme)
+                .syntax_highlighter(syntax_highlighter)
                 .wrap(true);
 
             f.render_widget(editor, inner);
@@ -418,10 +439,203 @@ fn draw_detail(f: &mut Frame, app: &mut AppState) {
     } else if let Some(ref msg) = app.message {
         msg.clone()
     } else {
-        ":w Save | :x Save & Exit | :q Quit | :q! Force Quit | :wn Save & Next | :wp Save & Prev"
+        ":w Save | :x Save & Exit | :q Quit | :q! Force Quit | :wn Save & Next | :wp Save & Prev | :wa Save All | :gq Reflow"
             .to_string()
     };
 
     let help = Paragraph::new(help_text).block(Block::default().borders(Borders::ALL));
     f.render_widget(help, chunks[2]);
 }
+
+/// Marker showing the review decision on a diff hunk, the resolution of a conflict, or that a
+/// section holds unwritten edits.
+fn decision_marker(app: &AppState, section_index: usize) -> Option<Span<'static>> {
+    let theme = theme::current();
+    let id = &app.sections[section_index].id;
+    if let Some(resolution) = app.conflict_resolutions.get(id) {
+        return Some(Span::styled(
+            format!(" ✓ {}", resolution.label()),
+            theme.fg(theme.added),
+        ));
+    }
+    let staging = app.git_repo().is_some();
+    let Some(decision) = app.hunk_decisions.get(id) else {
+        return app.sections[section_index]
+            .section_content
+            .is_some()
+            .then(|| {
+                Span::styled(
+                    format!(" {} unsaved", theme.glyphs.dirty),
+                    theme.fg(theme.modified),
+                )
+            });
+    };
+    let (text, color) = match (decision, staging) {
+        (HunkDecision::Accepted, false) => (" ✓ accepted", theme.added),
+        (HunkDecision::Accepted, true) => (" ✓ staged", theme.added),
+        (HunkDecision::Rejected, false) => (" ✗ rejected", theme.removed),
+        (HunkDecision::Rejected, true) => (" ✗ unstaged", theme.removed),
+        (HunkDecision::Edited, _) => (" ✎ edited", theme.modified),
+    };
+    Some(Span::styled(text, theme.fg(color)))
+}
+
+/// Colour for a changed span by the syntax category difftastic gave it; plain changes take the
+/// colour of their side.
+fn change_color(highlight: &str, side: Color) -> Color {
+    if !theme::current().color {
+        return Color::Reset;
+    }
+    match highlight {
+        "keyword" => Color::Magenta,
+        "string" => Color::Yellow,
+        "comment" => Color::DarkGray,
+        "type" => Color::Cyan,
+        "delimiter" => Color::White,
+        "tree_sitter_error" => Color::LightRed,
+        _ => side,
+    }
+}
+
+/// Render one side of a diff row as a line-numbered line with its changed spans emphasised.
+///
+/// With a syntax for the file, text takes the theme's colours and changed spans are set on a tint
+/// of the side's colour; otherwise changed spans are coloured by difftastic's category.
+fn diff_cell_line(
+    cell: Option<&DiffCell>,
+    side: Color,
+    tint: Color,
+    highlighter: Option<&mut LineHighlighter>,
+) -> Line<'static> {
+    let Some(cell) = cell else {
+        return Line::from(Span::raw("     "));
+    };
+
+    let theme = theme::current();
+    let gutter_style = if cell.changed {
+        theme.fg(side)
+    } else {
+        theme.fg(theme.dimmed)
+    };
+    let mut spans = vec![Span::styled(
+        format!("{:>4} ", cell.line_number + 1),
+        gutter_style,
+    )];
+
+    let syntax_known = highlighter.is_some();
+    let pieces = match highlighter {
+        Some(highlighter) => highlighter.styles(&cell.text),
+        None => vec![(Style::default(), cell.text.clone())],
+    };
+    let changed_style = |change: &ChangedSpan, base: Style| {
+        let style = if syntax_known {
+            base.bg(tint)
+        } else {
+            base.fg(change_color(&change.highlight, side))
+        };
+        style.add_modifier(Modifier::BOLD)
+    };
+
+    let mut offset = 0;
+    for (style, text) in pieces {
+        let end = offset + text.len();
+        let mut pos = offset;
+        while pos < end {
+            let change = cell.changes.iter().find(|c| c.start <= pos && pos < c.end);
+            let next = match change {
+                Some(change) => change.end,
+                None => cell
+                    .changes
+                    .iter()
+                    .map(|c| c.start)
+                    .filter(|&start| start > pos)
+                    .min()
+                    .unwrap_or(end),
+            }
+            .min(end);
+            // A change boundary inside a character leaves the rest of the piece unmarked
+            let Some(piece) = text.get(pos - offset..next - offset) else {
+                spans.push(Span::styled(text[pos - offset..].to_string(), style));
+                break;
+            };
+            let piece_style = change.map_or(style, |change| changed_style(change, style));
+            spans.push(Span::styled(piece.to_string(), piece_style));
+            pos = next;
+        }
+        offset = end;
+    }
+
+    Line::from(spans)
+}
+
+fn draw_diff(f: &mut Frame, app: &AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Hunk
+            Constraint::Min(0),    // Sides
+            Constraint::Length(3), // Help
+        ])
+        .split(f.area());
+
+    if let Some(section) = app.get_current_section() {
+        let header = Paragraph::new(format!("{} > {}", section.file_path, section.title))
+            .block(Block::default().borders(Borders::ALL).title("Diff"));
+        f.render_widget(header, chunks[0]);
+    }
+
+    if let Some(ref view) = app.diff_view {
+        let sides = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+
+        let theme = theme::current();
+        let syntax = app.get_current_section().and_then(|section| {
+            syntax_theme()?;
+            let language = section.hunk.as_ref()?.language.as_deref();
+            find_syntax(language, &section.file_path)
+        });
+        let mut lhs_highlighter = syntax.map(LineHighlighter::new);
+        let mut rhs_highlighter = syntax.map(LineHighlighter::new);
+
+        let rows = view.rows.iter().skip(view.scroll);
+        let lhs: Vec<Line> = rows
+            .clone()
+            .map(|row: &DiffRow| {
+                diff_cell_line(
+                    row.lhs.as_ref(),
+                    theme.removed,
+                    theme.removed_tint,
+                    lhs_highlighter.as_mut(),
+                )
+            })
+            .collect();
+        let rhs: Vec<Line> = rows
+            .map(|row: &DiffRow| {
+                diff_cell_line(
+                    row.rhs.as_ref(),
+                    theme.added,
+                    theme.added_tint,
+                    rhs_highlighter.as_mut(),
+                )
+            })
+            .collect();
+
+        f.render_widget(
+            Paragraph::new(lhs).block(Block::default().borders(Borders::ALL).title("Original")),
+            sides[0],
+        );
+        f.render_widget(
+            Paragraph::new(rhs).block(Block::default().borders(Borders::ALL).title("Modified")),
+            sides[1],
+        );
+    }
+
+    let help_text = app
+        .message
+        .clone()
+        .unwrap_or_else(|| "↑/↓ Scroll | a Accept | r Reject | e Edit | u Undo | n/p Next/Prev hunk | :w Write | Esc Back".to_string());
+    let help = Paragraph::new(help_text).block(Block::default().borders(Borders::ALL));
+    f.render_widget(help, chunks[2]);
+}
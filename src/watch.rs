@@ -0,0 +1,73 @@
+//! File watching, so sections follow documents that other programs edit while asterism is open.
+//!
+//! Sections cache byte and line offsets, which go stale as soon as a file changes underneath them.
+//! The watcher observes the directories holding the open documents rather than the files
+//! themselves, since editors commonly save by replacing a file, which would end a watch on it.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+
+/// Reports which of a set of files have changed on disk.
+pub struct FileWatcher {
+    /// Kept alive for as long as events are wanted.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// Watched files by canonical path, mapped back to the paths sections were loaded from.
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl FileWatcher {
+    /// Start watching the given files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform watcher cannot be created or a directory cannot be watched.
+    pub fn new(files: &[PathBuf]) -> io::Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+
+        let files: Vec<(PathBuf, PathBuf)> = files
+            .iter()
+            .map(|file| (canonical(file), file.clone()))
+            .collect();
+        let dirs: HashSet<&Path> = files.iter().filter_map(|(file, _)| file.parent()).collect();
+        for dir in dirs {
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(io::Error::other)?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            files,
+        })
+    }
+
+    /// Watched files modified, created or removed since the last call, without blocking.
+    #[must_use]
+    pub fn changed(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for event in self.events.try_iter().filter_map(Result::ok) {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in &event.paths {
+                let path = canonical(path);
+                if let Some((_, file)) = self.files.iter().find(|(watched, _)| *watched == path) {
+                    if !changed.contains(file) {
+                        changed.push(file.clone());
+                    }
+                }
+            }
+        }
+        changed
+    }
+}